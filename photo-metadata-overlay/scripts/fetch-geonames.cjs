@@ -0,0 +1,57 @@
+#!/usr/bin/env node
+
+/**
+ * 下载GeoNames城市数据作为内置离线地名库
+ * 生成 src-tauri/assets/gazetteer/ 下zlib压缩的 cities15000.txt.zlib 与 admin1CodesASCII.txt.zlib
+ * 数据来自 https://www.geonames.org/ ，以 CC BY 4.0 许可发布
+ */
+
+const { execSync } = require('child_process');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const zlib = require('zlib');
+
+const BASE_URL = 'https://download.geonames.org/export/dump';
+const CITIES = 'cities15000';
+const OUTPUT_DIR = path.join(__dirname, '..', 'src-tauri', 'assets', 'gazetteer');
+
+async function download(url) {
+  console.log(`⬇️  ${url}`);
+  const response = await fetch(url);
+  if (!response.ok) {
+    throw new Error(`${url}: HTTP ${response.status}`);
+  }
+  return Buffer.from(await response.arrayBuffer());
+}
+
+/** 从zip中取出单个文件，优先使用unzip，没有时使用bsdtar（Windows、macOS自带） */
+function extract(archive, name) {
+  const zipPath = path.join(fs.mkdtempSync(path.join(os.tmpdir(), 'geonames-')), `${name}.zip`);
+  fs.writeFileSync(zipPath, archive);
+  const options = { maxBuffer: 256 * 1024 * 1024 };
+  try {
+    return execSync(`unzip -p "${zipPath}" "${name}"`, options);
+  } catch {
+    return execSync(`tar -xOf "${zipPath}" "${name}"`, options);
+  }
+}
+
+function write(name, data) {
+  const output = path.join(OUTPUT_DIR, `${name}.zlib`);
+  fs.writeFileSync(output, zlib.deflateSync(data, { level: 9 }));
+  console.log(`✅ ${path.relative(process.cwd(), output)} (${data.length} → ${fs.statSync(output).size} bytes)`);
+}
+
+async function main() {
+  const cities = extract(await download(`${BASE_URL}/${CITIES}.zip`), `${CITIES}.txt`);
+  const admin1 = await download(`${BASE_URL}/admin1CodesASCII.txt`);
+
+  write(`${CITIES}.txt`, cities);
+  write('admin1CodesASCII.txt', admin1);
+}
+
+main().catch((error) => {
+  console.error(`❌ ${error.message}`);
+  process.exit(1);
+});
@@ -1,3 +1,17 @@
+use std::path::Path;
+
+/// 内置GeoNames地名库（由 scripts/fetch-geonames.cjs 生成）
+const GEONAMES_ASSETS: &[&str] = &["assets/gazetteer/cities15000.txt.zlib", "assets/gazetteer/admin1CodesASCII.txt.zlib"];
+
 fn main() {
+    // 有GeoNames数据时打包进程序，否则使用内置城市表
+    println!("cargo::rustc-check-cfg=cfg(bundled_geonames)");
+    println!("cargo::rerun-if-changed=assets/gazetteer");
+    if GEONAMES_ASSETS.iter().all(|asset| Path::new(asset).is_file()) {
+        println!("cargo::rustc-cfg=bundled_geonames");
+    } else {
+        println!("cargo::warning=GeoNames gazetteer not found, run `npm run fetch:geonames` to bundle it");
+    }
+
     tauri_build::build()
 }
@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 应用数据目录
+/// 启动时由Tauri设置，用于存放用户自行放入的地名库、字体等较大的数据文件
+pub struct AppPaths;
+
+impl AppPaths {
+    /// 记录应用数据目录，只有第一次调用生效
+    pub fn init(app_data_dir: PathBuf) {
+        let _ = APP_DATA_DIR.set(app_data_dir);
+    }
+
+    /// 应用数据目录；未初始化时（如单元测试）返回None
+    pub fn data_dir() -> Option<&'static Path> {
+        APP_DATA_DIR.get().map(PathBuf::as_path)
+    }
+
+    /// 应用数据目录下的子目录
+    pub fn subdir(name: &str) -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join(name))
+    }
+}
@@ -0,0 +1,138 @@
+use crate::app_paths::AppPaths;
+use crate::camera_names::CameraNames;
+use crate::font_registry::FontRegistry;
+use crate::types::*;
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg;
+use std::path::PathBuf;
+
+/// 内置Logo：品牌键、浅色底、深色底（与前端 src/assets/logos 相同）
+const BUNDLED_LOGOS: &[(&str, &str, &str)] = &[
+    ("canon", include_str!("../assets/logos/canon.svg"), include_str!("../assets/logos/canon-dark.svg")),
+    ("nikon", include_str!("../assets/logos/nikon.svg"), include_str!("../assets/logos/nikon-dark.svg")),
+    ("sony", include_str!("../assets/logos/sony.svg"), include_str!("../assets/logos/sony-dark.svg")),
+    ("fujifilm", include_str!("../assets/logos/fujifilm.svg"), include_str!("../assets/logos/fujifilm-dark.svg")),
+];
+
+/// 用户Logo文件夹（应用数据目录下）
+const USER_LOGO_DIR: &str = "logos";
+
+/// 用户Logo支持的格式，按顺序查找
+const USER_LOGO_EXTENSIONS: &[&str] = &["svg", "png"];
+
+/// 品牌Logo
+/// 按规范化后的相机品牌查找Logo并栅格化，用户Logo优先于内置Logo
+pub struct BrandLogoService;
+
+impl BrandLogoService {
+    /// 渲染指定高度的品牌Logo，没有对应的Logo时返回None
+    /// `variant` 应已解析为 Light 或 Dark（Auto 按 Light 处理）
+    pub fn render(make: &str, variant: LogoVariant, height: u32) -> Option<RgbaImage> {
+        let key = Self::logo_key(make);
+        if key.is_empty() || height == 0 {
+            return None;
+        }
+
+        let result = match Self::user_logo(&key, variant) {
+            Some(path) => std::fs::read(&path)
+                .with_context(|| format!("Failed to read logo: {:?}", path))
+                .and_then(|data| match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("svg") => Self::rasterize_svg(&data, height),
+                    _ => Self::scale_raster(&data, height),
+                }),
+            None => {
+                let (_, light, dark) = BUNDLED_LOGOS.iter().find(|(name, _, _)| *name == key)?;
+                let svg = if variant == LogoVariant::Dark { dark } else { light };
+                Self::rasterize_svg(svg.as_bytes(), height)
+            }
+        };
+
+        match result {
+            Ok(logo) => Some(logo),
+            Err(e) => {
+                println!("⚠️ Failed to render {} logo: {}", key, e);
+                None
+            }
+        }
+    }
+
+    /// 相机厂商对应的Logo文件名，如 "FUJIFILM" → "fujifilm"、"Phase One A/S" → "phase-one"
+    pub fn logo_key(make: &str) -> String {
+        let brand = CameraNames::brand(CameraNames::clean(make).as_str());
+        brand.to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+    }
+
+    /// 用户Logo文件夹中的Logo：先找 `<品牌>-light` / `<品牌>-dark`，再找 `<品牌>`
+    fn user_logo(key: &str, variant: LogoVariant) -> Option<PathBuf> {
+        let directory = AppPaths::subdir(USER_LOGO_DIR)?;
+        let suffix = if variant == LogoVariant::Dark { "dark" } else { "light" };
+        let names = [format!("{}-{}", key, suffix), key.to_string()];
+
+        names
+            .iter()
+            .flat_map(|name| USER_LOGO_EXTENSIONS.iter().map(move |ext| format!("{}.{}", name, ext)))
+            .map(|file_name| directory.join(file_name))
+            .find(|path| path.is_file())
+    }
+
+    /// 按高度等比栅格化SVG
+    pub fn rasterize_svg(data: &[u8], height: u32) -> Result<RgbaImage> {
+        // Logo中的文字使用字体注册表中的字体
+        let options = usvg::Options { fontdb: FontRegistry::shared().database(), ..Default::default() };
+        let tree = usvg::Tree::from_data(data, &options).context("Failed to parse SVG")?;
+
+        let size = tree.size();
+        let scale = height as f32 / size.height();
+        let width = (size.width() * scale).ceil().max(1.0) as u32;
+        let mut pixmap = Pixmap::new(width, height).context("Invalid logo size")?;
+        resvg::render(&tree, Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+        // tiny-skia使用预乘alpha
+        let pixels = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        RgbaImage::from_raw(width, height, pixels).context("Failed to convert logo pixels")
+    }
+
+    /// 按高度等比缩放位图Logo
+    fn scale_raster(data: &[u8], height: u32) -> Result<RgbaImage> {
+        let logo = image::load_from_memory(data).context("Failed to decode logo")?.to_rgba8();
+        let width = ((logo.width() as f32 * height as f32 / logo.height() as f32).round() as u32).max(1);
+        Ok(image::imageops::resize(&logo, width, height, image::imageops::FilterType::Lanczos3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logo_key() {
+        assert_eq!(BrandLogoService::logo_key("FUJIFILM"), "fujifilm");
+        assert_eq!(BrandLogoService::logo_key("NIKON CORPORATION"), "nikon");
+        assert_eq!(BrandLogoService::logo_key("Phase One A/S"), "phase-one");
+        assert_eq!(BrandLogoService::logo_key("Canon\0"), "canon");
+    }
+
+    #[test]
+    fn test_render_bundled_variants() {
+        let light = BrandLogoService::render("Canon", LogoVariant::Light, 40).unwrap();
+        let dark = BrandLogoService::render("Canon", LogoVariant::Dark, 40).unwrap();
+        // 内置Logo为 120x40
+        assert_eq!(light.dimensions(), (120, 40));
+        assert_eq!(dark.dimensions(), (120, 40));
+        assert_eq!(light.get_pixel(10, 10).0, [255, 255, 255, 255]);
+        assert_eq!(dark.get_pixel(10, 10).0, [0x1a, 0x1a, 0x1a, 255]);
+        // 品牌文字使用Canon红
+        assert!(light.pixels().any(|pixel| pixel.0[0] > 200 && pixel.0[1] < 60));
+
+        assert!(BrandLogoService::render("Leica", LogoVariant::Light, 40).is_none());
+    }
+}
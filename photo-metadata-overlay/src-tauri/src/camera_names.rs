@@ -0,0 +1,199 @@
+use crate::types::*;
+
+/// 厂商名称（小写前缀）到品牌名的对照表
+const BRANDS: &[(&str, &str)] = &[
+    ("om digital solutions", "OM System"),
+    ("fuji photo film", "Fujifilm"),
+    ("konica minolta", "Konica Minolta"),
+    ("eastman kodak", "Kodak"),
+    ("asahi optical", "Pentax"),
+    ("ricoh imaging", "Ricoh"),
+    ("lg electronics", "LG"),
+    ("seiko epson", "Epson"),
+    ("hmd global", "Nokia"),
+    ("phase one", "Phase One"),
+    ("blackmagic", "Blackmagic"),
+    ("hasselblad", "Hasselblad"),
+    ("panasonic", "Panasonic"),
+    ("fujifilm", "Fujifilm"),
+    ("motorola", "Motorola"),
+    ("olympus", "Olympus"),
+    ("samsung", "Samsung"),
+    ("minolta", "Konica Minolta"),
+    ("oneplus", "OnePlus"),
+    ("huawei", "Huawei"),
+    ("xiaomi", "Xiaomi"),
+    ("google", "Google"),
+    ("pentax", "Pentax"),
+    ("mamiya", "Mamiya"),
+    ("nikon", "Nikon"),
+    ("canon", "Canon"),
+    ("leica", "Leica"),
+    ("sigma", "Sigma"),
+    ("kodak", "Kodak"),
+    ("apple", "Apple"),
+    ("ricoh", "Ricoh"),
+    ("casio", "Casio"),
+    ("gopro", "GoPro"),
+    ("zeiss", "Zeiss"),
+    ("sony", "Sony"),
+    ("oppo", "OPPO"),
+    ("vivo", "vivo"),
+    ("dji", "DJI"),
+];
+
+/// 未知厂商名称中去掉的公司后缀
+const CORPORATE_SUFFIXES: &[&str] = &[
+    " corporation",
+    " corp.",
+    " corp",
+    " co., ltd.",
+    " co.,ltd.",
+    " co., ltd",
+    " co.,ltd",
+    ", ltd.",
+    " ltd.",
+    " inc.",
+    " inc",
+    " imaging",
+    " gmbh",
+    " ag",
+];
+
+/// 相机品牌与型号名称规范化
+pub struct CameraNames;
+
+impl CameraNames {
+    /// 规范化品牌与型号，原始值保存在 `raw_make` / `raw_model`
+    pub fn normalize(camera: &mut CameraInfo) {
+        if camera.raw_make.is_none() {
+            camera.raw_make = camera.make.clone();
+        }
+        if camera.raw_model.is_none() {
+            camera.raw_model = camera.model.clone();
+        }
+
+        let make = camera.raw_make.as_deref().map(Self::clean).filter(|make| !make.is_empty());
+        let model = camera.raw_model.as_deref().map(Self::clean).filter(|model| !model.is_empty());
+
+        let mut brand = make.as_deref().map(Self::brand);
+        // 理光旗下的宾得机型厂商写作 RICOH IMAGING，型号带 PENTAX 前缀
+        if let Some(model) = &model {
+            if Self::starts_with_word(model, "pentax") {
+                brand = Some("Pentax".to_string());
+            }
+        }
+
+        camera.model = model.map(|model| Self::model(&model, brand.as_deref(), make.as_deref()));
+        camera.make = brand;
+    }
+
+    /// 去掉EXIF字符串中的NUL、控制字符和首尾空白
+    pub fn clean(text: &str) -> String {
+        let text: String = text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// 厂商名称对应的品牌
+    pub fn brand(make: &str) -> String {
+        let lower = make.to_lowercase();
+        if let Some((_, brand)) = BRANDS.iter().find(|(prefix, _)| lower.starts_with(prefix)) {
+            return brand.to_string();
+        }
+
+        let mut brand = make.to_string();
+        while let Some(suffix) = CORPORATE_SUFFIXES
+            .iter()
+            .find(|suffix| brand.to_lowercase().ends_with(*suffix) && brand.len() > suffix.len())
+        {
+            brand.truncate(brand.len() - suffix.len());
+            brand = brand.trim_end_matches([',', ' ']).to_string();
+        }
+        brand
+    }
+
+    /// 去掉型号中重复的品牌前缀，并修正个别厂商的写法
+    fn model(model: &str, brand: Option<&str>, make: Option<&str>) -> String {
+        let mut result = model.to_string();
+
+        for prefix in [make, brand].into_iter().flatten() {
+            if Self::starts_with_word(&result, prefix) && result.len() > prefix.len() {
+                result = result[prefix.len()..].trim_start().to_string();
+            }
+        }
+
+        // 尼康二代、三代机型在EXIF中写作 "Z 6_2"、"Z 9_3"
+        if brand == Some("Nikon") {
+            for (suffix, generation) in [("_2", "II"), ("_3", "III")] {
+                if let Some(base) = result.strip_suffix(suffix) {
+                    result = format!("{}{}", base, generation);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// 不区分大小写地判断文本是否以指定单词开头（其后为空白或结尾）
+    fn starts_with_word(text: &str, word: &str) -> bool {
+        text.get(..word.len()).is_some_and(|head| head.eq_ignore_ascii_case(word))
+            && text[word.len()..].chars().next().is_none_or(char::is_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(make: &str, model: &str) -> (Option<String>, Option<String>) {
+        let mut camera = CameraInfo {
+            make: Some(make.to_string()),
+            model: Some(model.to_string()),
+            ..Default::default()
+        };
+        CameraNames::normalize(&mut camera);
+        assert_eq!(camera.raw_make.as_deref(), Some(make));
+        assert_eq!(camera.raw_model.as_deref(), Some(model));
+        (camera.make, camera.model)
+    }
+
+    #[test]
+    fn test_normalize_known_brands() {
+        let cases = [
+            ("NIKON CORPORATION", "NIKON Z 6_2", "Nikon", "Z 6II"),
+            ("Canon", "Canon EOS R5", "Canon", "EOS R5"),
+            ("OLYMPUS IMAGING CORP.  ", "E-M5", "Olympus", "E-M5"),
+            ("OM Digital Solutions", "OM-1", "OM System", "OM-1"),
+            ("FUJIFILM", "X-T5\0\0\0", "Fujifilm", "X-T5"),
+            ("SONY", "ILCE-7M4", "Sony", "ILCE-7M4"),
+            ("LEICA CAMERA AG", "LEICA Q2", "Leica", "Q2"),
+            ("RICOH IMAGING COMPANY, LTD.", "PENTAX K-1 Mark II", "Pentax", "K-1 Mark II"),
+            ("RICOH IMAGING COMPANY, LTD.", "RICOH GR III", "Ricoh", "GR III"),
+            ("Apple", "iPhone 15 Pro", "Apple", "iPhone 15 Pro"),
+        ];
+
+        for (make, model, brand, display_model) in cases {
+            assert_eq!(
+                normalize(make, model),
+                (Some(brand.to_string()), Some(display_model.to_string())),
+                "{} / {}",
+                make,
+                model
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_brand_and_junk() {
+        assert_eq!(CameraNames::brand("Acme Optical Co., Ltd."), "Acme Optical");
+        assert_eq!(CameraNames::clean("  EOS\0 R6 \0\0"), "EOS R6");
+
+        // 型号只有品牌名时保留原样，不会变成空字符串
+        let hasselblad = Some("Hasselblad".to_string());
+        assert_eq!(normalize("Hasselblad", "Hasselblad"), (hasselblad.clone(), hasselblad));
+
+        let mut camera = CameraInfo { make: Some("\0\0".to_string()), ..Default::default() };
+        CameraNames::normalize(&mut camera);
+        assert_eq!(camera.make, None);
+    }
+}
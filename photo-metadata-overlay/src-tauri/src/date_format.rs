@@ -0,0 +1,417 @@
+use crate::types::*;
+
+/// 支持的格式标记，同一字母的长标记需排在短标记之前
+const TOKENS: &[&str] = &[
+    "YYYY", "YY", "MMMM", "MMM", "MM", "M", "DD", "D", "HH", "H", "hh", "h", "mm", "ss", "SSS", "A", "Z",
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// 拍摄时间格式化器
+pub struct DateFormatter;
+
+impl DateFormatter {
+    /// 是否配置了自定义日期格式
+    pub fn is_configured(settings: &DateFormatSettings) -> bool {
+        settings.pattern.as_deref().is_some_and(|p| !p.is_empty())
+            || settings.locale.as_deref().is_some_and(|l| !l.is_empty())
+    }
+
+    /// 按设置格式化拍摄时间，未指定模式时使用语言区域的默认模式
+    pub fn format(timestamp: &CaptureTimestamp, settings: &DateFormatSettings) -> String {
+        let locale = settings.locale.as_deref().unwrap_or("");
+        let pattern = match settings.pattern.as_deref() {
+            Some(pattern) if !pattern.is_empty() => pattern,
+            _ => Self::default_pattern(locale),
+        };
+
+        Self::format_with_pattern(timestamp, pattern, locale)
+    }
+
+    /// 由Unix时间戳（秒）生成UTC时间
+    pub fn from_unix_seconds(seconds: i64, source: TimestampSource) -> CaptureTimestamp {
+        let days = seconds.div_euclid(86_400);
+        let second_of_day = seconds.rem_euclid(86_400);
+
+        // 公历日期换算（Howard Hinnant 的 civil_from_days 算法）
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        CaptureTimestamp {
+            year: year.clamp(0, 9999) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (second_of_day / 3600) as u8,
+            minute: (second_of_day % 3600 / 60) as u8,
+            second: (second_of_day % 60) as u8,
+            nanosecond: None,
+            offset_minutes: Some(0),
+            source,
+        }
+    }
+
+    /// 将时间整体前后移动若干秒（用于修正相机时钟），时区偏移与来源保持不变
+    /// 移动后超出0-9999年时返回None
+    pub fn shift(timestamp: &CaptureTimestamp, seconds: i64) -> Option<CaptureTimestamp> {
+        let target = Self::local_seconds(timestamp).checked_add(seconds)?;
+        let shifted = Self::from_unix_seconds(target, timestamp.source);
+        // from_unix_seconds 会把年份截断到0-9999
+        if Self::local_seconds(&shifted) != target {
+            return None;
+        }
+        Some(CaptureTimestamp {
+            nanosecond: timestamp.nanosecond,
+            offset_minutes: timestamp.offset_minutes,
+            ..shifted
+        })
+    }
+
+    /// 按UTC换算的Unix时间戳（秒），没有时区偏移时视为UTC
+    pub fn to_unix_seconds(timestamp: &CaptureTimestamp) -> i64 {
+        Self::local_seconds(timestamp) - i64::from(timestamp.offset_minutes.unwrap_or(0)) * 60
+    }
+
+    /// 不考虑时区偏移，把当地时间当作UTC得到的秒数
+    pub fn local_seconds(timestamp: &CaptureTimestamp) -> i64 {
+        // 公历日期换算为天数（civil_from_days 的逆运算）
+        let year = i64::from(timestamp.year) - i64::from(timestamp.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month_index = (i64::from(timestamp.month) + 9) % 12;
+        let day_of_year = (153 * month_index + 2) / 5 + i64::from(timestamp.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        days * 86_400
+            + i64::from(timestamp.hour) * 3600
+            + i64::from(timestamp.minute) * 60
+            + i64::from(timestamp.second)
+    }
+
+    /// 解析EXIF形式的时间字符串 "YYYY:MM:DD HH:MM:SS"
+    pub fn parse_exif_string(text: &str, source: TimestampSource) -> Option<CaptureTimestamp> {
+        let (date, time) = text.trim().split_once(' ')?;
+        Self::parse_iso8601(&format!("{}T{}", date.replace(':', "-"), time), source)
+    }
+
+    /// EXIF形式的时间字符串 "YYYY:MM:DD HH:MM:SS"
+    pub fn to_exif_string(timestamp: &CaptureTimestamp) -> String {
+        Self::format_with_pattern(timestamp, "YYYY:MM:DD HH:mm:ss", "")
+    }
+
+    /// ISO 8601形式的时间字符串，有时区偏移时附带偏移
+    pub fn to_iso8601(timestamp: &CaptureTimestamp) -> String {
+        Self::format_with_pattern(timestamp, "YYYY-MM-DD[T]HH:mm:ssZ", "")
+    }
+
+    /// 解析XMP使用的ISO 8601时间，允许省略时间部分（如 "2024-05-03"）
+    pub fn parse_iso8601(text: &str, source: TimestampSource) -> Option<CaptureTimestamp> {
+        let text = text.trim();
+        let (date, time) = match text.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+
+        let mut date_parts = date.split('-');
+        let year = date_parts.next()?.parse().ok()?;
+        let month = date_parts.next().map_or(Some(1), |part| part.parse().ok())?;
+        let day = date_parts.next().map_or(Some(1), |part| part.parse().ok())?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let mut timestamp = CaptureTimestamp {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            nanosecond: None,
+            offset_minutes: None,
+            source,
+        };
+
+        let Some(time) = time else {
+            return Some(timestamp);
+        };
+
+        // 拆出时区部分：Z、+HH:MM 或 -HH:MM
+        let (clock, offset) = match time.find(['Z', '+', '-']) {
+            Some(index) => (&time[..index], Some(&time[index..])),
+            None => (time, None),
+        };
+
+        let (clock, fraction) = match clock.split_once('.') {
+            Some((clock, fraction)) => (clock, Some(fraction)),
+            None => (clock, None),
+        };
+
+        let mut clock_parts = clock.split(':');
+        timestamp.hour = clock_parts.next()?.parse().ok()?;
+        timestamp.minute = clock_parts.next().map_or(Some(0), |part| part.parse().ok())?;
+        timestamp.second = clock_parts.next().map_or(Some(0), |part| part.parse().ok())?;
+        if timestamp.hour > 23 || timestamp.minute > 59 || timestamp.second > 60 {
+            return None;
+        }
+
+        if let Some(fraction) = fraction.filter(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit())) {
+            let digits = &fraction[..fraction.len().min(9)];
+            let value: u32 = digits.parse().ok()?;
+            timestamp.nanosecond = Some(value * 10u32.pow(9 - digits.len() as u32));
+        }
+
+        timestamp.offset_minutes = match offset {
+            Some("Z") => Some(0),
+            Some(offset) => Some(Self::parse_offset(offset)?),
+            None => None,
+        };
+
+        Some(timestamp)
+    }
+
+    /// 解析时区偏移 ±HH、±HHMM 或 ±HH:MM，返回分钟数
+    fn parse_offset(offset: &str) -> Option<i16> {
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let digits = offset.get(1..)?;
+        let (hours, minutes) = match digits.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if digits.len() == 4 => digits.split_at(2),
+            None => (digits, "0"),
+        };
+        if hours.is_empty() || hours.len() > 2 || minutes.len() > 2 {
+            return None;
+        }
+        if !hours.bytes().chain(minutes.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let hours: i32 = hours.parse().ok()?;
+        let minutes: i32 = minutes.parse().ok()?;
+        if hours > 14 || minutes >= 60 {
+            return None;
+        }
+        Some((sign * (hours * 60 + minutes)) as i16)
+    }
+
+    /// 语言区域对应的默认模式
+    fn default_pattern(locale: &str) -> &'static str {
+        if Self::is_cjk(locale) {
+            "YYYY年M月D日 HH:mm"
+        } else if locale.starts_with("en") {
+            "MMM D, YYYY"
+        } else {
+            "YYYY-MM-DD HH:mm:ss"
+        }
+    }
+
+    fn is_cjk(locale: &str) -> bool {
+        locale.starts_with("zh") || locale.starts_with("ja")
+    }
+
+    /// 按模式逐个替换标记，方括号中的内容原样输出
+    fn format_with_pattern(timestamp: &CaptureTimestamp, pattern: &str, locale: &str) -> String {
+        let mut output = String::new();
+        let mut rest = pattern;
+
+        while let Some(ch) = rest.chars().next() {
+            if ch == '[' {
+                if let Some(end) = rest.find(']') {
+                    output.push_str(&rest[1..end]);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+
+            if let Some(token) = TOKENS.iter().find(|token| rest.starts_with(**token)) {
+                output.push_str(&Self::format_token(timestamp, token, locale));
+                rest = &rest[token.len()..];
+            } else {
+                output.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+
+        output
+    }
+
+    fn format_token(timestamp: &CaptureTimestamp, token: &str, locale: &str) -> String {
+        let month_index = (timestamp.month.clamp(1, 12) - 1) as usize;
+        let hour12 = match timestamp.hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+
+        match token {
+            "YYYY" => format!("{:04}", timestamp.year),
+            "YY" => format!("{:02}", timestamp.year % 100),
+            "MMMM" if Self::is_cjk(locale) => format!("{}月", timestamp.month),
+            "MMMM" => MONTH_NAMES[month_index].to_string(),
+            "MMM" if Self::is_cjk(locale) => format!("{}月", timestamp.month),
+            "MMM" => MONTH_NAMES[month_index][..3].to_string(),
+            "MM" => format!("{:02}", timestamp.month),
+            "M" => timestamp.month.to_string(),
+            "DD" => format!("{:02}", timestamp.day),
+            "D" => timestamp.day.to_string(),
+            "HH" => format!("{:02}", timestamp.hour),
+            "H" => timestamp.hour.to_string(),
+            "hh" => format!("{:02}", hour12),
+            "h" => hour12.to_string(),
+            "mm" => format!("{:02}", timestamp.minute),
+            "ss" => format!("{:02}", timestamp.second),
+            "SSS" => format!("{:03}", timestamp.nanosecond.unwrap_or(0) / 1_000_000),
+            "A" => {
+                let is_pm = timestamp.hour >= 12;
+                let (am, pm) = if locale.starts_with("ja") {
+                    ("午前", "午後")
+                } else if locale.starts_with("zh") {
+                    ("上午", "下午")
+                } else {
+                    ("AM", "PM")
+                };
+                if is_pm { pm } else { am }.to_string()
+            }
+            "Z" => match timestamp.offset_minutes {
+                Some(offset) => {
+                    let sign = if offset < 0 { '-' } else { '+' };
+                    let offset = offset.unsigned_abs();
+                    format!("{}{:02}:{:02}", sign, offset / 60, offset % 60)
+                }
+                None => String::new(),
+            },
+            _ => token.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> CaptureTimestamp {
+        CaptureTimestamp {
+            year: 2024,
+            month: 5,
+            day: 3,
+            hour: 14,
+            minute: 2,
+            second: 11,
+            nanosecond: Some(120_000_000),
+            offset_minutes: Some(540),
+            source: TimestampSource::DateTimeOriginal,
+        }
+    }
+
+    fn settings(pattern: Option<&str>, locale: Option<&str>) -> DateFormatSettings {
+        DateFormatSettings {
+            pattern: pattern.map(str::to_string),
+            locale: locale.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_locale_default_patterns() {
+        assert_eq!(DateFormatter::format(&sample(), &settings(None, Some("zh-CN"))), "2024年5月3日 14:02");
+        assert_eq!(DateFormatter::format(&sample(), &settings(None, Some("en-US"))), "May 3, 2024");
+        assert_eq!(DateFormatter::format(&sample(), &settings(None, None)), "2024-05-03 14:02:11");
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let custom = settings(Some("YYYY/MM/DD hh:mm:ss.SSS A Z [at] MMMM"), Some("en"));
+        assert_eq!(DateFormatter::format(&sample(), &custom), "2024/05/03 02:02:11.120 PM +09:00 at May");
+
+        let chinese = settings(Some("YY年MMM D日 A h点"), Some("zh-CN"));
+        assert_eq!(DateFormatter::format(&sample(), &chinese), "24年5月 3日 下午 2点");
+
+        let japanese = settings(Some("M月D日 A h時"), Some("ja-JP"));
+        assert_eq!(DateFormatter::format(&sample(), &japanese), "5月3日 午後 2時");
+    }
+
+    #[test]
+    fn test_from_unix_seconds() {
+        // 2024-05-03T14:02:11Z
+        let timestamp = DateFormatter::from_unix_seconds(1_714_744_931, TimestampSource::FileModified);
+        assert_eq!(
+            DateFormatter::format_with_pattern(&timestamp, "YYYY-MM-DDTHH:mm:ssZ", ""),
+            "2024-05-03T14:02:11+00:00"
+        );
+
+        let leap_day = DateFormatter::from_unix_seconds(951_782_400, TimestampSource::FileModified);
+        assert_eq!((leap_day.year, leap_day.month, leap_day.day), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_iso8601_round_trip() {
+        let parsed = DateFormatter::parse_iso8601("2024-05-03T14:02:11.12+09:00", TimestampSource::Xmp).unwrap();
+        assert_eq!((parsed.hour, parsed.second, parsed.nanosecond, parsed.offset_minutes), (14, 11, Some(120_000_000), Some(540)));
+        assert_eq!(DateFormatter::to_iso8601(&parsed), "2024-05-03T14:02:11+09:00");
+        assert_eq!(DateFormatter::to_exif_string(&parsed), "2024:05:03 14:02:11");
+
+        let date_only = DateFormatter::parse_iso8601("2024-05-03", TimestampSource::Xmp).unwrap();
+        assert_eq!((date_only.day, date_only.hour, date_only.offset_minutes), (3, 0, None));
+
+        let negative = DateFormatter::parse_iso8601("2024-05-03T08:30-05:30", TimestampSource::Xmp).unwrap();
+        assert_eq!(negative.offset_minutes, Some(-330));
+
+        assert!(DateFormatter::parse_iso8601("2024-13-01", TimestampSource::Xmp).is_none());
+    }
+
+    #[test]
+    fn test_iso8601_offsets() {
+        let offset = |text: &str| {
+            DateFormatter::parse_iso8601(&format!("2024-05-03T14:02:11{}", text), TimestampSource::Xmp)
+                .map(|timestamp| timestamp.offset_minutes)
+        };
+
+        assert_eq!(offset("+0900"), Some(Some(540)));
+        assert_eq!(offset("+09:00"), Some(Some(540)));
+        assert_eq!(offset("+09"), Some(Some(540)));
+        assert_eq!(offset("-0530"), Some(Some(-330)));
+        assert_eq!(offset("Z"), Some(Some(0)));
+
+        assert_eq!(offset("+1500"), None);
+        assert_eq!(offset("+09:60"), None);
+        assert_eq!(offset("+9x:00"), None);
+        assert_eq!(offset("+"), None);
+        assert_eq!(offset("+090000"), None);
+    }
+
+    #[test]
+    fn test_shift_and_exif_string() {
+        let parsed = DateFormatter::parse_exif_string("2024:02:28 23:30:00", TimestampSource::DateTimeOriginal).unwrap();
+        assert_eq!((parsed.year, parsed.month, parsed.day, parsed.hour), (2024, 2, 28, 23));
+
+        // 跨过闰日和年末
+        let shifted = DateFormatter::shift(&parsed, 2 * 3600).unwrap();
+        assert_eq!(DateFormatter::to_exif_string(&shifted), "2024:02:29 01:30:00");
+        assert_eq!(shifted.source, TimestampSource::DateTimeOriginal);
+        assert_eq!(shifted.offset_minutes, None);
+
+        let new_year = DateFormatter::parse_exif_string("2023:12:31 23:59:59", TimestampSource::Xmp).unwrap();
+        assert_eq!(DateFormatter::to_exif_string(&DateFormatter::shift(&new_year, 1).unwrap()), "2024:01:01 00:00:00");
+        assert_eq!(DateFormatter::to_exif_string(&DateFormatter::shift(&new_year, -86_400 * 365).unwrap()), "2022:12:31 23:59:59");
+
+        // 超出可表示的年份或溢出
+        assert!(DateFormatter::shift(&new_year, 8000 * 366 * 86_400).is_none());
+        assert!(DateFormatter::shift(&new_year, i64::MAX).is_none());
+        assert!(DateFormatter::shift(&new_year, i64::MIN).is_none());
+    }
+
+    #[test]
+    fn test_is_configured() {
+        assert!(!DateFormatter::is_configured(&settings(None, None)));
+        assert!(!DateFormatter::is_configured(&settings(Some(""), None)));
+        assert!(DateFormatter::is_configured(&settings(None, Some("en-US"))));
+    }
+}
@@ -0,0 +1,278 @@
+use crate::exif_service::ExifService;
+use crate::focal_length::FocalLengthService;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+
+/// 35mm全画幅的弥散圆直径（毫米），其他画幅按等效系数缩小
+const FULL_FRAME_CIRCLE_OF_CONFUSION_MM: f64 = 0.03;
+
+/// 每次重新计算的字段
+const DERIVED_FIELDS: &[&str] = &[
+    "settings.ev100",
+    "settings.hyperfocal_distance",
+    "settings.dof_near",
+    "settings.dof_far",
+];
+
+/// 由拍摄参数计算的派生值：曝光值（EV100）、超焦距与景深
+pub struct DerivedMetrics;
+
+impl DerivedMetrics {
+    /// 同步显示文本与数值，补全等效焦距并计算派生值，来源记为 `Computed`
+    /// 之前计算出的值会先清除，保证覆盖了拍摄参数后重新计算
+    pub fn apply_to_metadata(metadata: &mut PhotoMetadata) {
+        let sources = metadata.field_sources.clone();
+        let computed = |key: &str| sources.get(key) == Some(&MetadataSource::Computed);
+        let settings = &mut metadata.settings;
+        for key in DERIVED_FIELDS.iter().copied().filter(|key| computed(key)) {
+            match key {
+                "settings.ev100" => settings.ev100 = None,
+                "settings.hyperfocal_distance" => settings.hyperfocal_distance = None,
+                "settings.dof_near" => settings.dof_near = None,
+                _ => settings.dof_far = None,
+            }
+        }
+
+        let before = MetadataFields::flatten(metadata);
+
+        // 文本与数值不一致时以文本为准，除非数值是手动覆盖的而文本不是
+        let number_wins = |text_key: &str, number_key: &str| {
+            sources.get(number_key) == Some(&MetadataSource::Override) && sources.get(text_key) != Some(&MetadataSource::Override)
+        };
+        let settings = &mut metadata.settings;
+        Self::sync(
+            &mut settings.aperture,
+            &mut settings.f_number,
+            number_wins("settings.aperture", "settings.f_number"),
+            ExifService::aperture_text,
+            ExifService::display_number,
+        );
+        Self::sync(
+            &mut settings.shutter_speed,
+            &mut settings.exposure_time,
+            number_wins("settings.shutter_speed", "settings.exposure_time"),
+            ExifService::shutter_speed_text,
+            Self::parse_shutter_speed,
+        );
+        Self::sync(
+            &mut settings.focal_length,
+            &mut settings.focal_length_mm,
+            number_wins("settings.focal_length", "settings.focal_length_mm"),
+            ExifService::focal_length_text,
+            ExifService::display_number,
+        );
+        Self::sync(
+            &mut settings.focal_length_35mm,
+            &mut settings.focal_length_35mm_mm,
+            number_wins("settings.focal_length_35mm", "settings.focal_length_35mm_mm"),
+            ExifService::focal_length_text,
+            ExifService::display_number,
+        );
+        Self::sync(
+            &mut settings.exposure_bias,
+            &mut settings.exposure_bias_ev,
+            number_wins("settings.exposure_bias", "settings.exposure_bias_ev"),
+            ExifService::exposure_bias_text,
+            ExifService::display_number,
+        );
+
+        FocalLengthService::apply_to_metadata(metadata);
+        Self::compute(&mut metadata.settings);
+        MetadataFields::record_sources(metadata, &before, MetadataSource::Computed);
+    }
+
+    /// 计算缺失的曝光值、超焦距与景深
+    pub fn compute(settings: &mut CameraSettings) {
+        if settings.ev100.is_none() {
+            if let (Some(f_number), Some(exposure_time), Some(iso)) = (settings.f_number, settings.exposure_time, settings.iso) {
+                settings.ev100 = Self::ev100(f_number, exposure_time, iso).map(|ev| Self::round(ev, 100.0));
+            }
+        }
+
+        let (Some(focal_length), Some(f_number), Some(crop_factor)) =
+            (settings.focal_length_mm, settings.f_number, settings.crop_factor)
+        else {
+            return;
+        };
+        let circle_of_confusion = FULL_FRAME_CIRCLE_OF_CONFUSION_MM / crop_factor;
+        let Some(hyperfocal) = Self::hyperfocal_distance(focal_length, f_number, circle_of_confusion) else {
+            return;
+        };
+
+        if settings.hyperfocal_distance.is_none() {
+            settings.hyperfocal_distance = Some(Self::round(hyperfocal, 1000.0));
+        }
+
+        if settings.dof_near.is_none() && settings.dof_far.is_none() {
+            if let Some(distance) = settings.subject_distance {
+                let (near, far) = Self::depth_of_field(focal_length, hyperfocal, distance);
+                settings.dof_near = Some(Self::round(near, 1000.0));
+                settings.dof_far = far.map(|far| Self::round(far, 1000.0));
+            }
+        }
+    }
+
+    /// ISO 100 下的曝光值：EV100 = log2(N² / t) − log2(ISO / 100)
+    pub fn ev100(f_number: f64, exposure_time: f64, iso: u32) -> Option<f64> {
+        if f_number <= 0.0 || exposure_time <= 0.0 || iso == 0 {
+            return None;
+        }
+        Some((f_number * f_number / exposure_time).log2() - (iso as f64 / 100.0).log2())
+    }
+
+    /// 超焦距（米）：H = f² / (N·c) + f，焦距与弥散圆单位为毫米
+    pub fn hyperfocal_distance(focal_length_mm: f64, f_number: f64, circle_of_confusion_mm: f64) -> Option<f64> {
+        if focal_length_mm <= 0.0 || f_number <= 0.0 || circle_of_confusion_mm <= 0.0 {
+            return None;
+        }
+        Some((focal_length_mm * focal_length_mm / (f_number * circle_of_confusion_mm) + focal_length_mm) / 1000.0)
+    }
+
+    /// 景深的近点与远点（米），对焦在超焦距以外时远点为None（无穷远）
+    pub fn depth_of_field(focal_length_mm: f64, hyperfocal_m: f64, distance_m: f64) -> (f64, Option<f64>) {
+        let focal_length = focal_length_mm / 1000.0;
+        let near = distance_m * (hyperfocal_m - focal_length) / (hyperfocal_m + distance_m - 2.0 * focal_length);
+        let far = (distance_m < hyperfocal_m)
+            .then(|| distance_m * (hyperfocal_m - focal_length) / (hyperfocal_m - distance_m));
+        (near, far)
+    }
+
+    /// 叠加层中的曝光值，如 "EV 13.0"
+    pub fn ev_text(ev100: f64) -> String {
+        format!("EV {:.1}", ev100)
+    }
+
+    /// 叠加层中的超焦距，如 "Hyperfocal 10.5 m"
+    pub fn hyperfocal_text(hyperfocal: f64) -> String {
+        format!("Hyperfocal {}", Self::distance_text(hyperfocal))
+    }
+
+    /// 叠加层中的景深，如 "DoF 2.34–4.18 m (1.85 m)" 或 "DoF 6.86 m–∞"
+    pub fn depth_of_field_text(near: f64, far: Option<f64>) -> String {
+        match far {
+            Some(far) => format!(
+                "DoF {}–{} ({})",
+                Self::distance_number(near),
+                Self::distance_text(far),
+                Self::distance_text(far - near)
+            ),
+            None => format!("DoF {}–∞", Self::distance_text(near)),
+        }
+    }
+
+    /// 距离文本，距离越远保留的小数越少
+    fn distance_text(meters: f64) -> String {
+        format!("{} m", Self::distance_number(meters))
+    }
+
+    fn distance_number(meters: f64) -> String {
+        if meters < 10.0 {
+            format!("{:.2}", meters)
+        } else if meters < 100.0 {
+            format!("{:.1}", meters)
+        } else {
+            format!("{:.0}", meters)
+        }
+    }
+
+    /// 显示文本与数值互相补全；两者都有但不一致时，按 `number_wins` 决定以哪一方为准
+    fn sync(
+        text: &mut Option<String>,
+        number: &mut Option<f64>,
+        number_wins: bool,
+        format: fn(f64) -> String,
+        parse: fn(&str) -> Option<f64>,
+    ) {
+        match (text.as_deref(), *number) {
+            (None, Some(value)) => *text = Some(format(value)),
+            (Some(display), None) => *number = parse(display),
+            (Some(display), Some(value)) if format(value) != display => {
+                if number_wins {
+                    *text = Some(format(value));
+                } else {
+                    *number = parse(display);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 快门速度文本转换为秒（"1/250" → 0.004，"2s" → 2）
+    fn parse_shutter_speed(text: &str) -> Option<f64> {
+        let text = text.trim().trim_end_matches(['s', '"']);
+        let seconds = match text.split_once('/') {
+            Some((numerator, denominator)) => numerator.trim().parse::<f64>().ok()? / denominator.trim().parse::<f64>().ok()?,
+            None => text.parse().ok()?,
+        };
+        (seconds.is_finite() && seconds > 0.0).then_some(seconds)
+    }
+
+    fn round(value: f64, scale: f64) -> f64 {
+        (value * scale).round() / scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exposure_and_depth_of_field() {
+        assert!((DerivedMetrics::ev100(8.0, 1.0 / 125.0, 100).unwrap() - 12.966).abs() < 0.001);
+        assert!((DerivedMetrics::ev100(8.0, 1.0 / 125.0, 400).unwrap() - 10.966).abs() < 0.001);
+        assert_eq!(DerivedMetrics::ev100(8.0, 0.0, 100), None);
+
+        // 全画幅 50mm f/8，对焦3米
+        let mut settings = CameraSettings {
+            f_number: Some(8.0),
+            exposure_time: Some(1.0 / 125.0),
+            iso: Some(100),
+            focal_length_mm: Some(50.0),
+            crop_factor: Some(1.0),
+            subject_distance: Some(3.0),
+            ..Default::default()
+        };
+        DerivedMetrics::compute(&mut settings);
+        assert_eq!(settings.ev100, Some(12.97));
+        assert_eq!(settings.hyperfocal_distance, Some(10.467));
+        assert_eq!((settings.dof_near, settings.dof_far), (Some(2.338), Some(4.185)));
+        assert_eq!(DerivedMetrics::depth_of_field_text(2.338, Some(4.185)), "DoF 2.34–4.18 m (1.85 m)");
+
+        // 对焦在超焦距以外，远点到无穷远
+        let (near, far) = DerivedMetrics::depth_of_field(50.0, 10.467, 20.0);
+        assert_eq!(far, None);
+        assert_eq!(DerivedMetrics::depth_of_field_text(near, far), "DoF 6.86 m–∞");
+    }
+
+    #[test]
+    fn test_apply_syncs_text_and_numbers() {
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("FUJIFILM".to_string());
+        metadata.camera.model = Some("X-T5".to_string());
+        metadata.settings.aperture = Some("f/2.8".to_string());
+        metadata.settings.shutter_speed = Some("1/250".to_string());
+        metadata.settings.focal_length = Some("23mm".to_string());
+        metadata.settings.iso = Some(400);
+        DerivedMetrics::apply_to_metadata(&mut metadata);
+
+        let settings = &metadata.settings;
+        assert_eq!((settings.f_number, settings.exposure_time, settings.focal_length_mm), (Some(2.8), Some(0.004), Some(23.0)));
+        assert_eq!(settings.focal_length_35mm.as_deref(), Some("35mm"));
+        assert_eq!(settings.focal_length_35mm_mm, Some(35.0));
+        assert_eq!(settings.ev100, Some(8.94));
+        assert!(settings.hyperfocal_distance.is_some());
+        assert_eq!(metadata.field_sources.get("settings.ev100"), Some(&MetadataSource::Computed));
+
+        // 修改光圈文本后，数值与派生值随之更新
+        metadata.settings.aperture = Some("f/8.0".to_string());
+        DerivedMetrics::apply_to_metadata(&mut metadata);
+        assert_eq!(metadata.settings.f_number, Some(8.0));
+        assert_eq!(metadata.settings.ev100, Some(11.97));
+
+        // 修改焦距后，计算出的等效焦距文本与数值一起更新
+        metadata.settings.focal_length = Some("56mm".to_string());
+        DerivedMetrics::apply_to_metadata(&mut metadata);
+        assert_eq!(metadata.settings.focal_length_35mm.as_deref(), Some("84mm"));
+        assert_eq!(metadata.settings.focal_length_35mm_mm, Some(84.0));
+    }
+}
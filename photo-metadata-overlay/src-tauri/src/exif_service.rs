@@ -1,4 +1,17 @@
+use crate::camera_names::CameraNames;
+use crate::date_format::DateFormatter;
+use crate::derived_metrics::DerivedMetrics;
+use crate::film_roll::FilmRollService;
+use crate::focal_length::FocalLengthService;
+use crate::geocoder::ReverseGeocoder;
+use crate::iptc_service::IptcService;
+use crate::maker_note::MakerNoteDecoder;
+use crate::metadata_fields::MetadataFields;
+use crate::metadata_override::MetadataOverrideService;
+use crate::metadata_writer::MetadataWriter;
+use crate::raw_service::{RawService, RAW_EXTENSIONS};
 use crate::types::*;
+use crate::xmp_service::XmpService;
 use anyhow::{Context, Result};
 use exif::{In, Tag, Value};
 use std::fs::File;
@@ -9,7 +22,151 @@ pub struct ExifService;
 
 impl ExifService {
     /// 从图片文件中提取EXIF元数据
+    /// 没有EXIF的图片（截图、扫描件、经聊天软件传输的图片）返回只含文件信息的部分元数据
+    /// 字段优先级：手动覆盖 > XMP边车 > 内嵌XMP(IPTC Core) > IPTC-IIM > 内嵌EXIF > MakerNote > 文件信息
     pub fn extract_metadata<P: AsRef<Path>>(file_path: P) -> Result<PhotoMetadata> {
+        Self::extract_metadata_with_roll(file_path, None)
+    }
+
+    /// 提取元数据，并在应用边车中保存的手动覆盖之前应用胶片卷记录表
+    /// 记录表的优先级介于文件信息与手动覆盖之间
+    pub fn extract_metadata_with_roll<P: AsRef<Path>>(
+        file_path: P,
+        roll_override: Option<&MetadataOverride>,
+    ) -> Result<PhotoMetadata> {
+        let path = file_path.as_ref();
+        let file_metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to open file: {:?}", path))?;
+
+        let mut metadata = PhotoMetadata::default();
+
+        match Self::read_exif_sources(path) {
+            Ok(exif_sources) => {
+                let before = MetadataFields::flatten(&metadata);
+                for exif_data in &exif_sources {
+                    Self::populate_metadata(&mut metadata, exif_data);
+                }
+                MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Exif);
+
+                let before = MetadataFields::flatten(&metadata);
+                for exif_data in &exif_sources {
+                    Self::apply_maker_note(&mut metadata, exif_data);
+                }
+                MetadataFields::record_sources(&mut metadata, &before, MetadataSource::MakerNote);
+            }
+            Err(e) => {
+                println!("ℹ️ No EXIF data in {:?}: {}, falling back to file information", path, e);
+            }
+        }
+
+        // 内嵌的IPTC-IIM与XMP中的IPTC Core
+        let embedded = MetadataWriter::read_source_metadata(path);
+        if let Some(iim) = &embedded.iptc {
+            let before = MetadataFields::flatten(&metadata);
+            IptcService::apply_to_metadata(&mut metadata, &IptcService::parse_iim(iim));
+            MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Iptc);
+        }
+        if let Some(xmp) = &embedded.xmp {
+            match XmpService::parse(&String::from_utf8_lossy(xmp)) {
+                Ok(packet) => {
+                    let before = MetadataFields::flatten(&metadata);
+                    XmpService::apply_iptc_core(&mut metadata, &packet);
+                    MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Xmp);
+                }
+                Err(e) => {
+                    println!("⚠️ Ignoring invalid embedded XMP in {:?}: {}", path, e);
+                }
+            }
+        }
+
+        // XMP边车中的字段优先于内嵌数据（通常是在Lightroom等软件中修正过的值）
+        let mut saved_override = None;
+        if let Some(sidecar_path) = XmpService::find_sidecar(path) {
+            match XmpService::read_sidecar(&sidecar_path) {
+                Ok(packet) => {
+                    let before = MetadataFields::flatten(&metadata);
+                    XmpService::apply_to_metadata(&mut metadata, &packet);
+                    MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Xmp);
+                    saved_override = MetadataOverrideService::from_packet(&packet);
+                }
+                Err(e) => {
+                    println!("⚠️ Ignoring unreadable XMP sidecar {:?}: {}", sidecar_path, e);
+                }
+            }
+        }
+
+        let before = MetadataFields::flatten(&metadata);
+        Self::apply_file_fallback(&mut metadata, path, &file_metadata);
+        MetadataFields::record_sources(&mut metadata, &before, MetadataSource::File);
+
+        let mut metadata = FilmRollService::applied(metadata, roll_override)?;
+
+        // 用户保存的手动覆盖最后应用，不会被文件信息等兜底值替换
+        if let Some(saved_override) = &saved_override {
+            if let Err(e) = MetadataOverrideService::apply(&mut metadata, saved_override) {
+                println!("⚠️ Ignoring saved metadata override for {:?}: {}", path, e);
+            }
+        }
+
+        // 品牌与型号换成通用写法，原始值保留在 raw_make / raw_model，来源与原字段相同
+        CameraNames::normalize(&mut metadata.camera);
+        for (field, raw_field) in [("camera.make", "camera.raw_make"), ("camera.model", "camera.raw_model")] {
+            if let Some(source) = metadata.field_sources.get(field).copied() {
+                metadata.field_sources.insert(raw_field.to_string(), source);
+            }
+        }
+
+        // 等效焦距、曝光值、景深等由其他字段计算的值
+        DerivedMetrics::apply_to_metadata(&mut metadata);
+
+        // 有坐标时从离线地名库补全地址
+        let before = MetadataFields::flatten(&metadata);
+        ReverseGeocoder::apply_to_metadata(&mut metadata);
+        MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Gazetteer);
+
+        Ok(metadata)
+    }
+
+    /// 补充文件名、像素尺寸和修改时间；没有拍摄时间时以文件修改时间代替
+    fn apply_file_fallback(metadata: &mut PhotoMetadata, path: &Path, file_metadata: &std::fs::Metadata) {
+        let (width, height) = match image::image_dimensions(path) {
+            Ok((width, height)) => (Some(width), Some(height)),
+            Err(_) => (None, None),
+        };
+
+        let modified = file_metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| DateFormatter::from_unix_seconds(duration.as_secs() as i64, TimestampSource::FileModified));
+
+        metadata.file = Some(FileInfo {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            file_size: file_metadata.len(),
+            width,
+            height,
+            modified: modified.as_ref().map(DateFormatter::to_iso8601),
+        });
+
+        if metadata.timestamp.is_none() && metadata.capture_time.is_none() {
+            if let Some(modified) = modified {
+                // 与EXIF相同的 "YYYY:MM:DD HH:MM:SS" 形式，前端无需区分来源
+                metadata.timestamp = Some(DateFormatter::to_exif_string(&modified));
+                metadata.capture_time = Some(modified);
+            }
+        }
+    }
+
+    /// 读取文件中的EXIF数据
+    /// 普通图片只有一个来源，部分RAW格式（如CR3）会拆分成多个来源
+    fn read_exif_sources<P: AsRef<Path>>(file_path: P) -> Result<Vec<exif::Exif>> {
+        if RawService::is_raw_file(&file_path) {
+            return RawService::read_exif(&file_path);
+        }
+
         let file = File::open(&file_path)
             .with_context(|| format!("Failed to open file: {:?}", file_path.as_ref()))?;
         
@@ -20,21 +177,11 @@ impl ExifService {
             .read_from_container(&mut buf_reader)
             .with_context(|| "Failed to read EXIF data")?;
 
-        let mut metadata = PhotoMetadata {
-            camera: CameraInfo {
-                make: None,
-                model: None,
-            },
-            settings: CameraSettings {
-                aperture: None,
-                shutter_speed: None,
-                iso: None,
-                focal_length: None,
-            },
-            timestamp: None,
-            location: None,
-        };
+        Ok(vec![exif_data])
+    }
 
+    /// 将一个EXIF来源中存在的字段写入元数据，缺失的字段保持不变
+    fn populate_metadata(metadata: &mut PhotoMetadata, exif_data: &exif::Exif) {
         // 提取相机信息
         if let Some(field) = exif_data.get_field(Tag::Make, In::PRIMARY) {
             metadata.camera.make = Self::field_to_string(field);
@@ -44,13 +191,28 @@ impl ExifService {
             metadata.camera.model = Self::field_to_string(field);
         }
 
+        // 提取镜头信息
+        if let Some(field) = exif_data.get_field(Tag::LensMake, In::PRIMARY) {
+            metadata.camera.lens_make = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::LensModel, In::PRIMARY) {
+            metadata.camera.lens_model = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::BodySerialNumber, In::PRIMARY) {
+            metadata.camera.serial_number = Self::field_to_string(field);
+        }
+
         // 提取拍摄设置
         if let Some(field) = exif_data.get_field(Tag::FNumber, In::PRIMARY) {
             metadata.settings.aperture = Self::format_aperture(field);
+            metadata.settings.f_number = Self::field_to_f64(field);
         }
 
         if let Some(field) = exif_data.get_field(Tag::ExposureTime, In::PRIMARY) {
             metadata.settings.shutter_speed = Self::format_shutter_speed(field);
+            metadata.settings.exposure_time = Self::field_to_f64(field);
         }
 
         if let Some(field) = exif_data.get_field(Tag::PhotographicSensitivity, In::PRIMARY) {
@@ -59,20 +221,124 @@ impl ExifService {
 
         if let Some(field) = exif_data.get_field(Tag::FocalLength, In::PRIMARY) {
             metadata.settings.focal_length = Self::format_focal_length(field);
+            metadata.settings.focal_length_mm = Self::field_to_f64(field);
+        }
+
+        // 对焦距离（米），0表示未知，0xFFFFFFFF/1表示无穷远
+        if let Some(field) = exif_data.get_field(Tag::SubjectDistance, In::PRIMARY) {
+            metadata.settings.subject_distance = Self::field_to_f64(field).filter(|distance| *distance > 0.0);
+        }
+
+        // 0表示未知
+        if let Some(field) = exif_data.get_field(Tag::FocalLengthIn35mmFilm, In::PRIMARY) {
+            metadata.settings.focal_length_35mm_mm = Self::field_to_u32(field)
+                .filter(|&focal_length| focal_length > 0)
+                .map(f64::from);
+            metadata.settings.focal_length_35mm = metadata.settings.focal_length_35mm_mm.map(Self::focal_length_text);
+        }
+
+        if let Some(crop_factor) = Self::focal_plane_crop_factor(exif_data) {
+            metadata.settings.crop_factor = Some(crop_factor);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::ExposureBiasValue, In::PRIMARY) {
+            metadata.settings.exposure_bias = Self::format_exposure_bias(field);
+            metadata.settings.exposure_bias_ev = Self::field_to_f64(field);
         }
 
-        // 提取拍摄时间
-        if let Some(field) = exif_data.get_field(Tag::DateTime, In::PRIMARY) {
+        // 枚举类标签使用kamadak-exif提供的可读描述
+        if let Some(field) = exif_data.get_field(Tag::Flash, In::PRIMARY) {
+            metadata.settings.flash = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::MeteringMode, In::PRIMARY) {
+            metadata.settings.metering_mode = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::ExposureProgram, In::PRIMARY) {
+            metadata.settings.exposure_program = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::WhiteBalance, In::PRIMARY) {
+            metadata.settings.white_balance = Self::field_to_string(field);
+        }
+
+        // 提取拍摄时间（优先原始拍摄时间）
+        if let Some((raw, capture_time)) = Self::extract_capture_time(exif_data) {
+            metadata.timestamp = Some(raw);
+            metadata.capture_time = Some(capture_time);
+        } else if let Some(field) = exif_data.get_field(Tag::DateTime, In::PRIMARY) {
             metadata.timestamp = Self::field_to_string(field);
         }
 
+        // 提取方向信息
+        if let Some(field) = exif_data.get_field(Tag::Orientation, In::PRIMARY) {
+            metadata.orientation = Self::field_to_u32(field).map(|v| v as u16);
+        }
+
         // 提取GPS信息
-        metadata.location = Self::extract_gps_info(&exif_data);
+        if let Some(location) = Self::extract_gps_info(exif_data) {
+            metadata.location = Some(location);
+        }
 
-        Ok(metadata)
+        // 提取作者与版权信息
+        if let Some(field) = exif_data.get_field(Tag::Artist, In::PRIMARY) {
+            metadata.artist = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::Copyright, In::PRIMARY) {
+            metadata.copyright = Self::field_to_string(field);
+        }
+
+        if let Some(field) = exif_data.get_field(Tag::Software, In::PRIMARY) {
+            metadata.software = Self::field_to_string(field);
+        }
+    }
+
+    /// 解码厂商MakerNote，补充EXIF标准标签中没有的字段
+    fn apply_maker_note(metadata: &mut PhotoMetadata, exif_data: &exif::Exif) {
+        let Some(field) = exif_data.get_field(Tag::MakerNote, In::PRIMARY) else {
+            return;
+        };
+        let Value::Undefined(_, offset) = &field.value else {
+            return;
+        };
+        let make = metadata.camera.make.clone().unwrap_or_default();
+        let Some(info) = MakerNoteDecoder::decode(&make, exif_data.buf(), *offset as usize, exif_data.little_endian()) else {
+            return;
+        };
+
+        if info.film_simulation.is_some() {
+            metadata.settings.film_simulation = info.film_simulation;
+        }
+        if info.shutter_count.is_some() {
+            metadata.camera.shutter_count = info.shutter_count;
+        }
+        if metadata.camera.lens_model.is_none() {
+            metadata.camera.lens_model = info.lens_model;
+        }
+        if metadata.camera.serial_number.is_none() {
+            metadata.camera.serial_number = info.serial_number;
+        }
+    }
+
+    /// 读取EXIF方向标签（1-8），缺失或无法读取时返回1
+    pub fn read_orientation<P: AsRef<Path>>(file_path: P) -> u16 {
+        let orientation = Self::read_exif_sources(&file_path).ok().and_then(|sources| {
+            sources.iter().find_map(|exif_data| {
+                let field = exif_data.get_field(Tag::Orientation, In::PRIMARY)?;
+                Self::field_to_u32(field)
+            })
+        });
+
+        match orientation {
+            Some(value @ 1..=8) => value as u16,
+            _ => 1,
+        }
     }
 
-    /// 验证图片文件格式
+    /// 按扩展名快速筛选图片文件
+    /// 只用于列目录等场景的预筛选，文件内容的检查见 `ImageInspector`
     pub fn validate_image_file<P: AsRef<Path>>(file_path: P) -> bool {
         let path = file_path.as_ref();
         
@@ -80,21 +346,21 @@ impl ExifService {
         if let Some(extension) = path.extension() {
             let ext = extension.to_string_lossy().to_lowercase();
             matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "tiff" | "tif")
+                || RAW_EXTENSIONS.contains(&ext.as_str())
         } else {
             false
         }
     }
 
     /// 将EXIF字段转换为字符串
+    /// 部分相机用NUL或空格把定长字段补满，这里一并去掉
     fn field_to_string(field: &exif::Field) -> Option<String> {
         match &field.value {
-            Value::Ascii(vec) => {
-                if let Some(bytes) = vec.first() {
-                    String::from_utf8(bytes.to_vec()).ok()
-                } else {
-                    None
-                }
-            }
+            Value::Ascii(vec) => vec
+                .first()
+                .map(|bytes| String::from_utf8_lossy(bytes))
+                .map(|text| text.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+                .filter(|text| !text.is_empty()),
             _ => Some(field.display_value().to_string()),
         }
     }
@@ -108,16 +374,58 @@ impl ExifService {
         }
     }
 
+    /// 将EXIF有理数字段转换为f64
+    fn field_to_f64(field: &exif::Field) -> Option<f64> {
+        match &field.value {
+            Value::Rational(vec) => vec.first().filter(|rational| rational.denom != 0).map(|rational| rational.to_f64()),
+            Value::SRational(vec) => vec.first().filter(|rational| rational.denom != 0).map(|rational| rational.to_f64()),
+            _ => None,
+        }
+    }
+
+    /// 光圈值的显示文本，如 "f/2.8"
+    pub fn aperture_text(f_number: f64) -> String {
+        format!("f/{:.1}", f_number)
+    }
+
+    /// 快门速度的显示文本，如 "1/250" 或 "2s"
+    pub fn shutter_speed_text(exposure_time: f64) -> String {
+        if exposure_time >= 1.0 {
+            format!("{}s", exposure_time as u32)
+        } else {
+            format!("1/{}", (1.0_f64 / exposure_time).round() as u32)
+        }
+    }
+
+    /// 焦距的显示文本，如 "50mm"
+    pub fn focal_length_text(focal_length: f64) -> String {
+        format!("{:.0}mm", focal_length)
+    }
+
+    /// 从显示文本中取出数值（"f/2.8" → 2.8，"+0.7 EV" → 0.7，"50mm" → 50）
+    pub fn display_number(text: &str) -> Option<f64> {
+        let start = text.find(|c: char| c.is_ascii_digit() || c == '-' || c == '+')?;
+        let number: String = text[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+            .collect();
+        number.parse().ok()
+    }
+
+    /// 曝光补偿的显示文本，如 "+0.7 EV"
+    pub fn exposure_bias_text(bias: f64) -> String {
+        if bias.abs() < 0.05 {
+            "0 EV".to_string()
+        } else {
+            format!("{:+.1} EV", bias)
+        }
+    }
+
     /// 格式化光圈值
     fn format_aperture(field: &exif::Field) -> Option<String> {
         match &field.value {
             Value::Rational(vec) => {
-                if let Some(rational) = vec.first() {
-                    let f_number = rational.num as f64 / rational.denom as f64;
-                    Some(format!("f/{:.1}", f_number))
-                } else {
-                    None
-                }
+                vec.first().map(|rational| Self::aperture_text(rational.num as f64 / rational.denom as f64))
             }
             _ => None,
         }
@@ -127,16 +435,7 @@ impl ExifService {
     fn format_shutter_speed(field: &exif::Field) -> Option<String> {
         match &field.value {
             Value::Rational(vec) => {
-                if let Some(rational) = vec.first() {
-                    let exposure_time = rational.num as f64 / rational.denom as f64;
-                    if exposure_time >= 1.0 {
-                        Some(format!("{}s", exposure_time as u32))
-                    } else {
-                        Some(format!("1/{}", (1.0_f64 / exposure_time).round() as u32))
-                    }
-                } else {
-                    None
-                }
+                vec.first().map(|rational| Self::shutter_speed_text(rational.num as f64 / rational.denom as f64))
             }
             _ => None,
         }
@@ -146,18 +445,95 @@ impl ExifService {
     fn format_focal_length(field: &exif::Field) -> Option<String> {
         match &field.value {
             Value::Rational(vec) => {
-                if let Some(rational) = vec.first() {
-                    let focal_length = rational.num as f64 / rational.denom as f64;
-                    Some(format!("{:.0}mm", focal_length))
-                } else {
-                    None
+                vec.first().map(|rational| Self::focal_length_text(rational.num as f64 / rational.denom as f64))
+            }
+            _ => None,
+        }
+    }
+
+    /// 格式化曝光补偿
+    fn format_exposure_bias(field: &exif::Field) -> Option<String> {
+        match &field.value {
+            Value::SRational(vec) => {
+                let rational = vec.first()?;
+                if rational.denom == 0 {
+                    return None;
                 }
+                Some(Self::exposure_bias_text(rational.num as f64 / rational.denom as f64))
             }
             _ => None,
         }
     }
 
+    /// 提取拍摄时间
+    /// 按 DateTimeOriginal → DateTimeDigitized → DateTime 的顺序选取，
+    /// 并合并对应的 SubSecTime* 与 OffsetTime* 标签
+    fn extract_capture_time(exif_data: &exif::Exif) -> Option<(String, CaptureTimestamp)> {
+        let candidates = [
+            (Tag::DateTimeOriginal, Tag::SubSecTimeOriginal, Tag::OffsetTimeOriginal, TimestampSource::DateTimeOriginal),
+            (Tag::DateTimeDigitized, Tag::SubSecTimeDigitized, Tag::OffsetTimeDigitized, TimestampSource::DateTimeDigitized),
+            (Tag::DateTime, Tag::SubSecTime, Tag::OffsetTime, TimestampSource::DateTime),
+        ];
+
+        candidates.into_iter().find_map(|(date_tag, subsec_tag, offset_tag, source)| {
+            let raw = Self::field_to_string(exif_data.get_field(date_tag, In::PRIMARY)?)?;
+            let mut date_time = exif::DateTime::from_ascii(raw.as_bytes()).ok()?;
+
+            // 部分相机在未设置时钟时写入全零日期
+            if !(1..=12).contains(&date_time.month) || !(1..=31).contains(&date_time.day) {
+                return None;
+            }
+
+            if let Some(subsec) = exif_data.get_field(subsec_tag, In::PRIMARY).and_then(Self::field_to_string) {
+                let _ = date_time.parse_subsec(subsec.trim().as_bytes());
+            }
+
+            if let Some(offset) = exif_data.get_field(offset_tag, In::PRIMARY).and_then(Self::field_to_string) {
+                let _ = date_time.parse_offset(offset.as_bytes());
+            }
+
+            Some((
+                raw,
+                CaptureTimestamp {
+                    year: date_time.year,
+                    month: date_time.month,
+                    day: date_time.day,
+                    hour: date_time.hour,
+                    minute: date_time.minute,
+                    second: date_time.second,
+                    nanosecond: date_time.nanosecond,
+                    offset_minutes: date_time.offset,
+                    source,
+                },
+            ))
+        })
+    }
+
+    /// 由焦平面分辨率与像素尺寸推算传感器尺寸，进而得到等效系数
+    fn focal_plane_crop_factor(exif_data: &exif::Exif) -> Option<f64> {
+        let get = |tag| exif_data.get_field(tag, In::PRIMARY);
+        let width = Self::field_to_u32(get(Tag::PixelXDimension)?)?;
+        let height = Self::field_to_u32(get(Tag::PixelYDimension)?)?;
+        let x_resolution = Self::field_to_f64(get(Tag::FocalPlaneXResolution)?)?;
+        let y_resolution = Self::field_to_f64(get(Tag::FocalPlaneYResolution)?)?;
+
+        // 单位：2 英寸（默认）、3 厘米、4 毫米、5 微米
+        let unit_mm = match get(Tag::FocalPlaneResolutionUnit).and_then(Self::field_to_u32).unwrap_or(2) {
+            2 => 25.4,
+            3 => 10.0,
+            4 => 1.0,
+            5 => 0.001,
+            _ => return None,
+        };
+
+        FocalLengthService::focal_plane_crop_factor(
+            width as f64 / x_resolution * unit_mm,
+            height as f64 / y_resolution * unit_mm,
+        )
+    }
+
     /// 提取GPS信息
+    /// 经纬度缺失时整块忽略；海拔、方向、速度、卫星时间和大地基准各自可选
     fn extract_gps_info(exif_data: &exif::Exif) -> Option<LocationInfo> {
         let lat_ref = exif_data.get_field(Tag::GPSLatitudeRef, In::PRIMARY)?;
         let lat = exif_data.get_field(Tag::GPSLatitude, In::PRIMARY)?;
@@ -167,10 +543,78 @@ impl ExifService {
         let latitude = Self::parse_gps_coordinate(lat, lat_ref)?;
         let longitude = Self::parse_gps_coordinate(lon, lon_ref)?;
 
+        let field = |tag| exif_data.get_field(tag, In::PRIMARY);
+        let text = |tag| field(tag).and_then(Self::field_to_string);
+
+        // AltitudeRef 为1表示低于海平面
+        let altitude = field(Tag::GPSAltitude).and_then(Self::field_to_f64).map(|altitude| {
+            match field(Tag::GPSAltitudeRef).map(|field| &field.value) {
+                Some(Value::Byte(bytes)) if bytes.first() == Some(&1) => -altitude,
+                _ => altitude,
+            }
+        });
+
+        // 速度统一换算为 km/h，SpeedRef：K = km/h，M = mph，N = 节
+        let speed = field(Tag::GPSSpeed).and_then(Self::field_to_f64).map(|speed| {
+            match text(Tag::GPSSpeedRef).as_deref() {
+                Some("M") => speed * 1.609_344,
+                Some("N") => speed * 1.852,
+                _ => speed,
+            }
+        });
+
         Some(LocationInfo {
             latitude,
             longitude,
-            address: None, // 地址解析需要额外的地理编码服务
+            address: None, // 由离线地名库补全
+            altitude,
+            image_direction: field(Tag::GPSImgDirection).and_then(Self::field_to_f64),
+            image_direction_ref: text(Tag::GPSImgDirectionRef),
+            speed,
+            gps_time: Self::extract_gps_time(exif_data),
+            map_datum: text(Tag::GPSMapDatum),
+            place: None,
+        })
+    }
+
+    /// 由 GPSDateStamp（"YYYY:MM:DD"）和 GPSTimeStamp（时、分、秒三个有理数）组合出UTC时间
+    fn extract_gps_time(exif_data: &exif::Exif) -> Option<CaptureTimestamp> {
+        let date = Self::field_to_string(exif_data.get_field(Tag::GPSDateStamp, In::PRIMARY)?)?;
+        let mut date_parts = date.split(':').map(|part| part.trim().parse::<u16>().ok());
+        let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+
+        let Value::Rational(time) = &exif_data.get_field(Tag::GPSTimeStamp, In::PRIMARY)?.value else {
+            return None;
+        };
+        let [hour, minute, second] = time.as_slice() else {
+            return None;
+        };
+        // 时、分必须为整数，秒允许小数（含闰秒60）
+        let whole = |value: &exif::Rational| (value.denom != 0 && value.num.is_multiple_of(value.denom)).then(|| value.num / value.denom);
+        let (hour, minute) = (whole(hour)?, whole(minute)?);
+        let second = second.to_f64();
+
+        // 任一字段超出范围时视为无效，避免截断或饱和转换得到错误的时间
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || !(0.0..61.0).contains(&second)
+        {
+            return None;
+        }
+        let nanosecond = ((second.fract() * 1e9).round() as u32).min(999_999_999);
+
+        Some(CaptureTimestamp {
+            year,
+            month: month as u8,
+            day: day as u8,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second.floor() as u8,
+            nanosecond: (nanosecond > 0).then_some(nanosecond),
+            offset_minutes: Some(0),
+            source: TimestampSource::Gps,
         })
     }
 
@@ -205,7 +649,6 @@ impl ExifService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_validate_image_file() {
@@ -216,4 +659,156 @@ mod tests {
         assert!(!ExifService::validate_image_file("test.txt"));
         assert!(!ExifService::validate_image_file("test"));
     }
+
+    #[test]
+    fn test_validate_raw_file() {
+        for name in ["a.dng", "a.CR2", "a.cr3", "a.nef", "a.ARW", "a.raf", "a.orf"] {
+            assert!(ExifService::validate_image_file(name), "{}", name);
+        }
+        assert!(!ExifService::validate_image_file("a.rw2"));
+    }
+
+    #[test]
+    fn test_extract_metadata_without_exif() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("screenshot.png");
+        image::RgbImage::new(12, 8).save(&path).unwrap();
+
+        let metadata = ExifService::extract_metadata(&path).unwrap();
+        let file = metadata.file.as_ref().unwrap();
+        assert_eq!(file.file_name, "screenshot.png");
+        assert_eq!((file.width, file.height), (Some(12), Some(8)));
+        assert!(metadata.camera.make.is_none());
+
+        let capture_time = metadata.capture_time.as_ref().unwrap();
+        assert_eq!(capture_time.source, TimestampSource::FileModified);
+        assert_eq!(metadata.field_sources.get("timestamp"), Some(&MetadataSource::File));
+        assert_eq!(metadata.field_sources.get("file"), Some(&MetadataSource::File));
+
+        assert!(ExifService::extract_metadata(temp_dir.path().join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_extract_gps_info() {
+        let rationals = |values: &[(u32, u32)]| {
+            Value::Rational(values.iter().map(|&(num, denom)| exif::Rational { num, denom }).collect())
+        };
+        let ascii = |text: &str| Value::Ascii(vec![text.as_bytes().to_vec()]);
+        let fields = [
+            (Tag::GPSLatitudeRef, ascii("S")),
+            (Tag::GPSLatitude, rationals(&[(33, 1), (51, 1), (3054, 100)])),
+            (Tag::GPSLongitudeRef, ascii("E")),
+            (Tag::GPSLongitude, rationals(&[(151, 1), (12, 1), (5, 1)])),
+            (Tag::GPSAltitudeRef, Value::Byte(vec![1])),
+            (Tag::GPSAltitude, rationals(&[(35, 10)])),
+            (Tag::GPSImgDirectionRef, ascii("T")),
+            (Tag::GPSImgDirection, rationals(&[(9050, 100)])),
+            (Tag::GPSSpeedRef, ascii("N")),
+            (Tag::GPSSpeed, rationals(&[(10, 1)])),
+            (Tag::GPSDateStamp, ascii("2024:01:31")),
+            (Tag::GPSTimeStamp, rationals(&[(23, 1), (59, 1), (595, 10)])),
+            (Tag::GPSMapDatum, ascii("WGS-84")),
+        ];
+
+        let mut writer = exif::experimental::Writer::new();
+        let fields: Vec<exif::Field> = fields
+            .into_iter()
+            .map(|(tag, value)| exif::Field { tag, ifd_num: In::PRIMARY, value })
+            .collect();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let exif_data = exif::Reader::new().read_raw(tiff.into_inner()).unwrap();
+
+        let location = ExifService::extract_gps_info(&exif_data).unwrap();
+        assert!((location.latitude + 33.858_483).abs() < 1e-6);
+        assert_eq!(location.altitude, Some(-3.5));
+        assert_eq!(location.image_direction, Some(90.5));
+        assert_eq!(location.image_direction_ref.as_deref(), Some("T"));
+        assert!((location.speed.unwrap() - 18.52).abs() < 1e-9);
+        assert_eq!(location.map_datum.as_deref(), Some("WGS-84"));
+
+        let gps_time = location.gps_time.unwrap();
+        assert_eq!(DateFormatter::to_iso8601(&gps_time), "2024-01-31T23:59:59+00:00");
+        assert_eq!(gps_time.nanosecond, Some(500_000_000));
+        assert_eq!(gps_time.source, TimestampSource::Gps);
+
+        // 超出范围的日期或时间不产生GPS时间
+        for (date, time) in [
+            ("2024:13:01", rationals(&[(12, 1), (0, 1), (0, 1)])),
+            ("2024:01:32", rationals(&[(12, 1), (0, 1), (0, 1)])),
+            ("2024:01:31", rationals(&[(24, 1), (0, 1), (0, 1)])),
+            ("2024:01:31", rationals(&[(12, 1), (300, 1), (0, 1)])),
+            ("2024:01:31", rationals(&[(12, 1), (0, 1), (61, 1)])),
+            ("2024:01:31", rationals(&[(25, 2), (0, 1), (0, 1)])),
+        ] {
+            let fields = [
+                exif::Field { tag: Tag::GPSDateStamp, ifd_num: In::PRIMARY, value: ascii(date) },
+                exif::Field { tag: Tag::GPSTimeStamp, ifd_num: In::PRIMARY, value: time },
+            ];
+            let mut writer = exif::experimental::Writer::new();
+            for field in &fields {
+                writer.push_field(field);
+            }
+            let mut tiff = std::io::Cursor::new(Vec::new());
+            writer.write(&mut tiff, false).unwrap();
+            let exif_data = exif::Reader::new().read_raw(tiff.into_inner()).unwrap();
+            assert!(ExifService::extract_gps_time(&exif_data).is_none(), "{}", date);
+        }
+    }
+
+    #[test]
+    fn test_extract_fujifilm_maker_note() {
+        // 富士MakerNote：头部 + 小端IFD（偏移以MakerNote开头为基准），只含FilmMode=Classic Neg.
+        let mut maker_note = b"FUJIFILM\x0C\0\0\0".to_vec();
+        maker_note.extend_from_slice(&[1, 0, 0x01, 0x14, 3, 0, 1, 0, 0, 0, 0x00, 0x08, 0, 0, 0, 0, 0, 0]);
+
+        let fields = [
+            exif::Field { tag: Tag::Make, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"FUJIFILM \0\0".to_vec()]) },
+            exif::Field { tag: Tag::MakerNote, ifd_num: In::PRIMARY, value: Value::Undefined(maker_note, 0) },
+        ];
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, true).unwrap();
+
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageOutputFormat::Jpeg(90))
+            .unwrap();
+        let source = crate::metadata_writer::SourceMetadata { exif: Some(tiff.into_inner()), ..Default::default() };
+        let jpeg = MetadataWriter::write_metadata(jpeg, image::ImageFormat::Jpeg, &source, 4, 4).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("DSCF0001.jpg");
+        std::fs::write(&path, jpeg).unwrap();
+
+        let metadata = ExifService::extract_metadata(&path).unwrap();
+        assert_eq!(metadata.settings.film_simulation.as_deref(), Some("Classic Neg."));
+        assert_eq!(metadata.field_sources.get("settings.film_simulation"), Some(&MetadataSource::MakerNote));
+        assert_eq!(metadata.field_sources.get("camera.make"), Some(&MetadataSource::Exif));
+
+        // 补位的空白与NUL被去掉，品牌规范化后仍保留原始写法
+        assert_eq!(metadata.camera.make.as_deref(), Some("Fujifilm"));
+        assert_eq!(metadata.camera.raw_make.as_deref(), Some("FUJIFILM"));
+        assert_eq!(metadata.field_sources.get("camera.raw_make"), Some(&MetadataSource::Exif));
+    }
+
+    #[test]
+    fn test_format_exposure_bias() {
+        let field = |num: i32, denom: i32| exif::Field {
+            tag: Tag::ExposureBiasValue,
+            ifd_num: In::PRIMARY,
+            value: Value::SRational(vec![exif::SRational { num, denom }]),
+        };
+
+        assert_eq!(ExifService::format_exposure_bias(&field(2, 3)), Some("+0.7 EV".to_string()));
+        assert_eq!(ExifService::format_exposure_bias(&field(-1, 3)), Some("-0.3 EV".to_string()));
+        assert_eq!(ExifService::format_exposure_bias(&field(0, 1)), Some("0 EV".to_string()));
+        assert_eq!(ExifService::format_exposure_bias(&field(1, 0)), None);
+    }
 }
\ No newline at end of file
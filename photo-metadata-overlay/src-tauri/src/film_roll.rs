@@ -0,0 +1,579 @@
+use crate::date_format::DateFormatter;
+use crate::exif_service::ExifService;
+use crate::metadata_override::MetadataOverrideService;
+use crate::types::*;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// CSV表头的别名，统一为 `FilmRoll` / `FilmFrame` 的字段名
+const COLUMN_ALIASES: &[(&str, &str)] = &[
+    ("camera_make", "make"),
+    ("camera_model", "model"),
+    ("camera", "model"),
+    ("film", "film_stock"),
+    ("stock", "film_stock"),
+    ("frame_number", "frame"),
+    ("filename", "file"),
+    ("file_name", "file"),
+    ("f_number", "aperture"),
+    ("f_stop", "aperture"),
+    ("shutter", "shutter_speed"),
+    ("exposure", "shutter_speed"),
+    ("focal", "focal_length"),
+    ("datetime", "date"),
+    ("date_time", "date"),
+    ("note", "notes"),
+    ("comment", "notes"),
+];
+
+const KNOWN_COLUMNS: &[&str] = &[
+    "roll", "make", "model", "film_stock", "iso", "mapping", "file_pattern",
+    "frame", "file", "aperture", "shutter_speed", "lens", "focal_length", "date", "notes",
+];
+
+/// 文件名模式中的元素
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PatternToken {
+    Literal(char),
+    AnyRun, // *
+    AnyOne, // ?
+    Frame,  // {frame}
+}
+
+/// 胶片卷记录表
+/// 读取CSV或TOML格式的记录表，并将每一帧转换为对应扫描文件的元数据覆盖
+pub struct FilmRollService;
+
+impl FilmRollService {
+    /// 读取记录表，`.toml` 按TOML解析，其余按CSV解析
+    pub fn load<P: AsRef<Path>>(sheet_path: P) -> Result<FilmRoll> {
+        let sheet_path = sheet_path.as_ref();
+        let text = std::fs::read_to_string(sheet_path)
+            .with_context(|| format!("Failed to read roll sheet: {}", sheet_path.display()))?;
+
+        let is_toml = sheet_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"));
+        let roll = if is_toml { Self::parse_toml(&text)? } else { Self::parse_csv(&text)? };
+
+        println!("🎞️ Loaded roll sheet {} ({} frames)", sheet_path.display(), roll.frames.len());
+        Ok(roll)
+    }
+
+    /// 解析TOML记录表，卷级别字段在顶层，每一帧为一个 `[[frames]]`
+    pub fn parse_toml(text: &str) -> Result<FilmRoll> {
+        toml::from_str(text).context("Invalid TOML roll sheet")
+    }
+
+    /// 解析CSV记录表
+    /// 第一行为表头，每行一帧；卷级别的列（make、film_stock、iso等）取第一个非空值
+    /// 支持逗号、分号和制表符分隔，以 `#` 开头的行为注释
+    pub fn parse_csv(text: &str) -> Result<FilmRoll> {
+        let text = text.trim_start_matches('\u{feff}');
+        let delimiter = Self::detect_delimiter(text);
+
+        let mut rows = Self::csv_rows(text, delimiter)?
+            .into_iter()
+            .filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+            .filter(|row| !row[0].trim_start().starts_with('#'));
+
+        let header: Vec<String> = rows
+            .next()
+            .context("Roll sheet is empty")?
+            .iter()
+            .map(|name| Self::column_name(name))
+            .collect();
+        for column in header.iter().filter(|column| !KNOWN_COLUMNS.contains(&column.as_str())) {
+            println!("⚠️ Ignoring unknown roll sheet column: {}", column);
+        }
+
+        let mut roll = FilmRoll::default();
+        for (index, row) in rows.enumerate() {
+            let row_number = index + 1;
+            let mut frame = FilmFrame::default();
+
+            for (column, cell) in header.iter().zip(&row) {
+                let cell = cell.trim();
+                if cell.is_empty() {
+                    continue;
+                }
+                let text = || Some(cell.to_string());
+
+                match column.as_str() {
+                    "roll" => roll.roll = roll.roll.take().or_else(text),
+                    "make" => roll.make = roll.make.take().or_else(text),
+                    "model" => roll.model = roll.model.take().or_else(text),
+                    "film_stock" => roll.film_stock = roll.film_stock.take().or_else(text),
+                    "file_pattern" => roll.file_pattern = roll.file_pattern.take().or_else(text),
+                    "iso" if roll.iso.is_none() => {
+                        roll.iso = Some(
+                            cell.parse()
+                                .with_context(|| format!("Invalid ISO on row {}: {:?}", row_number, cell))?,
+                        );
+                    }
+                    "mapping" => roll.mapping = Self::parse_mapping(cell)?,
+                    "frame" => {
+                        frame.frame = Some(
+                            cell.trim_start_matches('#')
+                                .parse()
+                                .with_context(|| format!("Invalid frame number on row {}: {:?}", row_number, cell))?,
+                        );
+                    }
+                    "file" => frame.file = text(),
+                    "aperture" => frame.aperture = text(),
+                    "shutter_speed" => frame.shutter_speed = text(),
+                    "lens" => frame.lens = text(),
+                    "focal_length" => frame.focal_length = text(),
+                    "date" => frame.date = text(),
+                    "notes" => frame.notes = text(),
+                    _ => {}
+                }
+            }
+
+            roll.frames.push(frame);
+        }
+
+        Ok(roll)
+    }
+
+    /// 将记录表转换为每个文件的元数据覆盖
+    /// 没有对应帧的文件只得到卷级别的信息
+    pub fn overrides(roll: &FilmRoll, image_paths: &[String]) -> Result<HashMap<String, MetadataOverride>> {
+        Self::frame_overrides(roll, image_paths)
+            .into_iter()
+            .map(|(image_path, frame_override)| {
+                let frame_override = frame_override.with_context(|| format!("Roll sheet frame for {}", image_path))?;
+                Ok((image_path, frame_override))
+            })
+            .collect()
+    }
+
+    /// 与 `overrides` 相同，但每个文件单独返回结果，某一帧的单元格无效时不影响其他文件
+    pub fn frame_overrides(roll: &FilmRoll, image_paths: &[String]) -> HashMap<String, Result<MetadataOverride>> {
+        let frames = Self::match_frames(roll, image_paths);
+
+        image_paths
+            .iter()
+            .map(|image_path| {
+                let frame = frames.get(image_path.as_str()).copied();
+                if frame.is_none() && !roll.frames.is_empty() {
+                    println!("⚠️ No roll sheet frame for {}", image_path);
+                }
+                (image_path.clone(), Self::frame_override(roll, frame))
+            })
+            .collect()
+    }
+
+    /// 应用记录表得到的覆盖，字段来源记为 `FilmRoll`
+    pub fn applied(mut metadata: PhotoMetadata, roll_override: Option<&MetadataOverride>) -> Result<PhotoMetadata> {
+        if let Some(roll_override) = roll_override {
+            MetadataOverrideService::apply_with_source(&mut metadata, roll_override, MetadataSource::FilmRoll)?;
+        }
+        Ok(metadata)
+    }
+
+    /// 将帧对应到扫描文件，返回 文件路径 → 帧
+    /// 指定了 `file` 的帧总是对应到同名文件，其余的帧按映射方式对应
+    pub fn match_frames<'a>(roll: &'a FilmRoll, image_paths: &'a [String]) -> HashMap<&'a str, &'a FilmFrame> {
+        let mut matched = HashMap::new();
+        let mut used_frames = HashSet::new();
+
+        for (index, frame) in roll.frames.iter().enumerate() {
+            let Some(file) = frame.file.as_deref() else { continue };
+            let target = image_paths.iter().find(|path| {
+                let name = Self::file_name(path);
+                name.eq_ignore_ascii_case(file) || Self::file_stem(path).eq_ignore_ascii_case(file)
+            });
+            match target {
+                Some(path) if !matched.contains_key(path.as_str()) => {
+                    matched.insert(path.as_str(), frame);
+                    used_frames.insert(index);
+                }
+                _ => println!("⚠️ Roll sheet file not found in batch: {}", file),
+            }
+        }
+
+        let mut remaining_paths: Vec<&String> =
+            image_paths.iter().filter(|path| !matched.contains_key(path.as_str())).collect();
+        let remaining_frames = roll
+            .frames
+            .iter()
+            .enumerate()
+            .filter(|(index, frame)| !used_frames.contains(index) && frame.file.is_none())
+            .map(|(_, frame)| frame);
+
+        match roll.mapping {
+            FrameMapping::Order => {
+                remaining_paths.sort_by_cached_key(|path| Self::natural_key(Self::file_name(path)));
+                for (path, frame) in remaining_paths.into_iter().zip(remaining_frames) {
+                    matched.insert(path.as_str(), frame);
+                }
+            }
+            FrameMapping::Pattern => {
+                let pattern = roll.file_pattern.as_deref().map(Self::parse_pattern);
+                let by_number: HashMap<u32, &FilmFrame> = remaining_frames
+                    .filter_map(|frame| frame.frame.map(|number| (number, frame)))
+                    .collect();
+
+                for path in remaining_paths {
+                    let number = match &pattern {
+                        Some(pattern) => Self::match_pattern(pattern, &Self::file_name(path).chars().collect::<Vec<_>>()),
+                        None => Self::last_number(Self::file_stem(path)),
+                    };
+                    if let Some(frame) = number.and_then(|number| by_number.get(&number)) {
+                        matched.insert(path.as_str(), *frame);
+                    }
+                }
+            }
+        }
+
+        matched
+    }
+
+    /// 一帧（或仅卷级别信息）对应的覆盖
+    fn frame_override(roll: &FilmRoll, frame: Option<&FilmFrame>) -> Result<MetadataOverride> {
+        let mut fields = BTreeMap::new();
+        let frame_text = |get: fn(&FilmFrame) -> &Option<String>| frame.and_then(|frame| get(frame).clone());
+
+        Self::insert(&mut fields, "camera.make", roll.make.clone());
+        Self::insert(&mut fields, "camera.model", roll.model.clone());
+        Self::insert(&mut fields, "camera.lens_model", frame_text(|frame| &frame.lens).or_else(|| roll.lens.clone()));
+        Self::insert(&mut fields, "settings.iso", roll.iso);
+        Self::insert(&mut fields, "settings.aperture", frame_text(|frame| &frame.aperture).map(|text| Self::aperture_text(&text)));
+        Self::insert(&mut fields, "settings.shutter_speed", frame_text(|frame| &frame.shutter_speed).map(|text| Self::shutter_speed_text(&text)));
+        Self::insert(&mut fields, "settings.focal_length", frame_text(|frame| &frame.focal_length).map(|text| Self::focal_length_text(&text)));
+        Self::insert(&mut fields, "film.stock", roll.film_stock.clone());
+        Self::insert(&mut fields, "film.roll", roll.roll.clone());
+        Self::insert(&mut fields, "film.frame", frame.and_then(|frame| frame.frame));
+        Self::insert(&mut fields, "film.notes", frame_text(|frame| &frame.notes));
+
+        if let Some(date) = frame_text(|frame| &frame.date) {
+            let capture_time = Self::parse_date(&date)
+                .with_context(|| format!("Invalid date in roll sheet: {:?}", date))?;
+            fields.insert("timestamp".to_string(), Value::from(DateFormatter::to_exif_string(&capture_time)));
+        }
+
+        Ok(MetadataOverride { fields, time_offset_seconds: None })
+    }
+
+    fn insert<T: Into<Value>>(fields: &mut BTreeMap<String, Value>, key: &str, value: Option<T>) {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), value.into());
+        }
+    }
+
+    /// 光圈 "8"、"f8"、"f/8" → "f/8.0"
+    fn aperture_text(text: &str) -> String {
+        let number = text.trim_start_matches(['f', 'F']).trim_start_matches('/');
+        match number.parse::<f64>() {
+            Ok(f_number) if f_number > 0.0 => ExifService::aperture_text(f_number),
+            _ => text.to_string(),
+        }
+    }
+
+    /// 快门 "1/250" 原样保留，"2s" 为秒，单独的数字按快门转盘的写法视为倒数（"250" → "1/250"）
+    fn shutter_speed_text(text: &str) -> String {
+        if let Some(seconds) = text.strip_suffix(['s', '"']).and_then(|seconds| seconds.trim().parse::<f64>().ok()) {
+            return ExifService::shutter_speed_text(seconds);
+        }
+        match text.parse::<f64>() {
+            Ok(denominator) if denominator >= 1.0 => ExifService::shutter_speed_text(1.0 / denominator),
+            _ => text.to_string(),
+        }
+    }
+
+    /// 焦距 "50" → "50mm"
+    fn focal_length_text(text: &str) -> String {
+        match text.trim_end_matches("mm").trim().parse::<f64>() {
+            Ok(focal_length) => ExifService::focal_length_text(focal_length),
+            Err(_) => text.to_string(),
+        }
+    }
+
+    /// 日期 "2024-05-01"、"2024-05-01 14:30"、"2024-05-01T14:30" 或EXIF格式
+    fn parse_date(text: &str) -> Option<CaptureTimestamp> {
+        DateFormatter::parse_exif_string(text, TimestampSource::Override)
+            .or_else(|| DateFormatter::parse_iso8601(&text.trim().replacen(' ', "T", 1), TimestampSource::Override))
+    }
+
+    fn parse_mapping(text: &str) -> Result<FrameMapping> {
+        match text.to_lowercase().as_str() {
+            "order" => Ok(FrameMapping::Order),
+            "pattern" => Ok(FrameMapping::Pattern),
+            _ => anyhow::bail!("Unknown frame mapping: {:?}", text),
+        }
+    }
+
+    /// 统一表头写法：小写，空格和连字符换成下划线，再换成标准名
+    fn column_name(name: &str) -> String {
+        let name = name.trim().to_lowercase().replace([' ', '-'], "_");
+        COLUMN_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name, |(_, column)| column.to_string())
+    }
+
+    /// 按表头行中出现最多的分隔符判断，默认逗号
+    fn detect_delimiter(text: &str) -> char {
+        let header = text.lines().find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#')).unwrap_or("");
+        [',', ';', '\t']
+            .into_iter()
+            .map(|delimiter| (header.matches(delimiter).count(), delimiter))
+            .filter(|(count, _)| *count > 0)
+            .max_by_key(|(count, _)| *count)
+            .map_or(',', |(_, delimiter)| delimiter)
+    }
+
+    /// 拆分CSV的行与单元格，支持双引号包裹（其中可以包含分隔符、换行和 "" 转义）
+    fn csv_rows(text: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut cell = String::new();
+        let mut in_quotes = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        cell.push('"');
+                        chars.next();
+                    }
+                    '"' => in_quotes = false,
+                    _ => cell.push(c),
+                }
+                continue;
+            }
+
+            match c {
+                '"' if cell.trim().is_empty() => {
+                    cell.clear();
+                    in_quotes = true;
+                }
+                '\r' => {}
+                '\n' => {
+                    row.push(std::mem::take(&mut cell));
+                    rows.push(std::mem::take(&mut row));
+                }
+                c if c == delimiter => row.push(std::mem::take(&mut cell)),
+                _ => cell.push(c),
+            }
+        }
+
+        if in_quotes {
+            anyhow::bail!("Unterminated quoted field in roll sheet");
+        }
+        if !cell.is_empty() || !row.is_empty() {
+            row.push(cell);
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    fn parse_pattern(pattern: &str) -> Vec<PatternToken> {
+        let mut tokens = Vec::new();
+        let mut rest = pattern;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("{frame}") {
+                tokens.push(PatternToken::Frame);
+                rest = after;
+                continue;
+            }
+            tokens.push(match c {
+                '*' => PatternToken::AnyRun,
+                '?' => PatternToken::AnyOne,
+                c => PatternToken::Literal(c.to_ascii_lowercase()),
+            });
+            rest = &rest[c.len_utf8()..];
+        }
+        tokens
+    }
+
+    /// 用模式匹配文件名（不区分大小写），返回 `{frame}` 位置的帧号
+    fn match_pattern(pattern: &[PatternToken], name: &[char]) -> Option<u32> {
+        let mut frame = None;
+        Self::match_tokens(pattern, name, &mut frame).then_some(frame).flatten()
+    }
+
+    fn match_tokens(pattern: &[PatternToken], name: &[char], frame: &mut Option<u32>) -> bool {
+        let Some((token, rest)) = pattern.split_first() else {
+            return name.is_empty();
+        };
+
+        match token {
+            PatternToken::Literal(c) => {
+                name.first().is_some_and(|n| n.to_ascii_lowercase() == *c) && Self::match_tokens(rest, &name[1..], frame)
+            }
+            PatternToken::AnyOne => !name.is_empty() && Self::match_tokens(rest, &name[1..], frame),
+            PatternToken::AnyRun => (0..=name.len()).any(|skip| Self::match_tokens(rest, &name[skip..], frame)),
+            PatternToken::Frame => {
+                let digits = name.iter().take_while(|c| c.is_ascii_digit()).count();
+                (1..=digits).rev().any(|length| {
+                    let matched = Self::match_tokens(rest, &name[length..], frame);
+                    if matched && frame.is_none() {
+                        *frame = name[..length].iter().collect::<String>().parse().ok();
+                    }
+                    matched
+                })
+            }
+        }
+    }
+
+    /// 文件名中最后一组数字，如 "scan_012" → 12
+    fn last_number(stem: &str) -> Option<u32> {
+        let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+        let start = stem[..end].rfind(|c: char| !c.is_ascii_digit()).map_or(0, |index| index + 1);
+        stem[start..end].parse().ok()
+    }
+
+    /// 自然排序用的键，数字按数值比较（"scan2" 排在 "scan10" 之前）
+    fn natural_key(name: &str) -> Vec<(String, u64)> {
+        let mut key = Vec::new();
+        let mut text = String::new();
+        let mut chars = name.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if !c.is_ascii_digit() {
+                text.extend(c.to_lowercase());
+                continue;
+            }
+            let mut number = c.to_digit(10).unwrap_or(0) as u64;
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                number = number.saturating_mul(10).saturating_add(digit as u64);
+                chars.next();
+            }
+            key.push((std::mem::take(&mut text), number));
+        }
+        key.push((text, 0));
+        key
+    }
+
+    fn file_name(path: &str) -> &str {
+        Path::new(path).file_name().and_then(|name| name.to_str()).unwrap_or(path)
+    }
+
+    fn file_stem(path: &str) -> &str {
+        Path::new(path).file_stem().and_then(|stem| stem.to_str()).unwrap_or(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| format!("/scans/{}", name)).collect()
+    }
+
+    #[test]
+    fn test_parse_csv_sheet() {
+        let sheet = "\u{feff}# Roll 12, pushed one stop\r\n\
+            Frame;Camera Make;Camera;Film;ISO;Aperture;Shutter;Lens;Date;Notes\r\n\
+            1;Nikon;FM2;Kodak Tri-X 400;800;8;250;Nikkor 50mm f/1.4;2024-05-01 14:30;\"Harbour; low tide\"\r\n\
+            \r\n\
+            2;;;;;f/2.8;1/60;;2024-05-01;\"Cafe \"\"Blue\"\"\nsecond line\"\r\n";
+
+        let roll = FilmRollService::parse_csv(sheet).unwrap();
+        assert_eq!(roll.make.as_deref(), Some("Nikon"));
+        assert_eq!(roll.model.as_deref(), Some("FM2"));
+        assert_eq!(roll.film_stock.as_deref(), Some("Kodak Tri-X 400"));
+        assert_eq!(roll.iso, Some(800));
+        assert_eq!(roll.frames.len(), 2);
+        assert_eq!(roll.frames[0].notes.as_deref(), Some("Harbour; low tide"));
+        assert_eq!(roll.frames[1].notes.as_deref(), Some("Cafe \"Blue\"\nsecond line"));
+        assert_eq!(roll.frames[1].shutter_speed.as_deref(), Some("1/60"));
+
+        assert!(FilmRollService::parse_csv("frame,iso\n1,fast\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_toml_and_match_by_pattern() {
+        let roll = FilmRollService::parse_toml(
+            r#"
+            film = "Fujifilm Velvia 50"
+            iso = 50
+            mapping = "Pattern"
+            file_pattern = "R7_F{frame}*"
+
+            [[frames]]
+            frame = 3
+            aperture = "f/11"
+
+            [[frames]]
+            frame = 12
+            shutter = "1/125"
+
+            [[frames]]
+            frame = 36
+            file = "extra.tif"
+            "#,
+        )
+        .unwrap();
+
+        let image_paths = paths(&["R7_F03_v2.tif", "r7_f12.tif", "R7_F99.tif", "extra.tif", "other.jpg"]);
+        let frames = FilmRollService::match_frames(&roll, &image_paths);
+        assert_eq!(frames[image_paths[0].as_str()].frame, Some(3));
+        assert_eq!(frames[image_paths[1].as_str()].frame, Some(12));
+        assert_eq!(frames[image_paths[3].as_str()].frame, Some(36));
+        assert!(!frames.contains_key(image_paths[2].as_str()));
+        assert!(!frames.contains_key(image_paths[4].as_str()));
+    }
+
+    #[test]
+    fn test_order_mapping_and_overrides() {
+        let roll = FilmRoll {
+            make: Some("Leica".to_string()),
+            model: Some("M6".to_string()),
+            film_stock: Some("Kodak Portra 400".to_string()),
+            iso: Some(400),
+            lens: Some("Summicron 35mm f/2".to_string()),
+            frames: vec![
+                FilmFrame { frame: Some(1), aperture: Some("5.6".to_string()), date: Some("2024-05-01".to_string()), ..Default::default() },
+                FilmFrame { frame: Some(2), focal_length: Some("35".to_string()), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+
+        let image_paths = paths(&["scan10.tif", "scan2.tif", "scan1.tif"]);
+        let overrides = FilmRollService::overrides(&roll, &image_paths).unwrap();
+
+        let first = &overrides[&image_paths[2]].fields;
+        assert_eq!(first["film.frame"], Value::from(1));
+        assert_eq!(first["settings.aperture"], Value::from("f/5.6"));
+        assert_eq!(first["timestamp"], Value::from("2024:05:01 00:00:00"));
+        assert_eq!(overrides[&image_paths[1]].fields["settings.focal_length"], Value::from("35mm"));
+
+        // 多出的文件只得到卷级别的信息
+        let extra = &overrides[&image_paths[0]].fields;
+        assert!(!extra.contains_key("film.frame"));
+        assert_eq!(extra["film.stock"], Value::from("Kodak Portra 400"));
+
+        let metadata = FilmRollService::applied(PhotoMetadata::default(), overrides.get(&image_paths[2])).unwrap();
+        assert_eq!(metadata.film.as_ref().and_then(|film| film.stock.as_deref()), Some("Kodak Portra 400"));
+        assert_eq!(metadata.camera.lens_model.as_deref(), Some("Summicron 35mm f/2"));
+        assert_eq!(metadata.field_sources.get("film.stock"), Some(&MetadataSource::FilmRoll));
+        assert_eq!(metadata.capture_time.as_ref().map(|time| time.day), Some(1));
+
+        // 单元格无效时只有对应的文件失败
+        let mut roll = roll;
+        roll.frames[1].date = Some("sometime in May".to_string());
+        let frame_overrides = FilmRollService::frame_overrides(&roll, &image_paths);
+        assert!(frame_overrides[&image_paths[1]].is_err());
+        assert!(frame_overrides[&image_paths[0]].is_ok());
+        assert!(frame_overrides[&image_paths[2]].is_ok());
+        assert!(FilmRollService::overrides(&roll, &image_paths).is_err());
+    }
+
+    #[test]
+    fn test_value_normalization() {
+        assert_eq!(FilmRollService::shutter_speed_text("250"), "1/250");
+        assert_eq!(FilmRollService::shutter_speed_text("2s"), "2s");
+        assert_eq!(FilmRollService::shutter_speed_text("B"), "B");
+        assert_eq!(FilmRollService::aperture_text("f16"), "f/16.0");
+        assert!(FilmRollService::parse_date("2024:05:01 09:15:00").is_some());
+        assert!(FilmRollService::parse_date("yesterday").is_none());
+    }
+}
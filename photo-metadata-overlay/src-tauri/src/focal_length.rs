@@ -0,0 +1,241 @@
+use crate::camera_names::CameraNames;
+use crate::exif_service::ExifService;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+
+/// 35mm全画幅（36×24mm）的对角线长度
+const FULL_FRAME_DIAGONAL_MM: f64 = 43.266_615;
+
+/// 常见机型的等效系数：（品牌, 型号前缀, 系数）
+/// 取最长的匹配前缀；前缀以数字结尾时，其后不能紧跟数字（"EOS R1" 不匹配 "EOS R10"）
+const SENSOR_FORMATS: &[(&str, &str, f64)] = &[
+    ("Fujifilm", "X-", 1.5),
+    ("Fujifilm", "X100", 1.5),
+    ("Fujifilm", "XF10", 1.5),
+    ("Fujifilm", "GFX", 0.79),
+    ("Canon", "EOS", 1.6),
+    ("Canon", "EOS R", 1.0),
+    ("Canon", "EOS Rebel", 1.6),
+    ("Canon", "EOS R7", 1.6),
+    ("Canon", "EOS R10", 1.6),
+    ("Canon", "EOS R50", 1.6),
+    ("Canon", "EOS R100", 1.6),
+    ("Canon", "EOS 5D", 1.0),
+    ("Canon", "EOS 6D", 1.0),
+    ("Canon", "EOS-1D", 1.0),
+    ("Canon", "PowerShot G7 X", 2.7),
+    ("Canon", "PowerShot G5 X", 2.7),
+    ("Canon", "PowerShot G1 X", 1.85),
+    ("Nikon", "Z", 1.0),
+    ("Nikon", "Z 50", 1.5),
+    ("Nikon", "Z 30", 1.5),
+    ("Nikon", "Z fc", 1.5),
+    ("Nikon", "D", 1.5),
+    ("Nikon", "D3", 1.0),
+    ("Nikon", "D4", 1.0),
+    ("Nikon", "D5", 1.0),
+    ("Nikon", "D6", 1.0),
+    ("Nikon", "D600", 1.0),
+    ("Nikon", "D610", 1.0),
+    ("Nikon", "D700", 1.0),
+    ("Nikon", "D750", 1.0),
+    ("Nikon", "D780", 1.0),
+    ("Nikon", "D800", 1.0),
+    ("Nikon", "D810", 1.0),
+    ("Nikon", "D850", 1.0),
+    ("Nikon", "Df", 1.0),
+    ("Sony", "ILCE-1", 1.0),
+    ("Sony", "ILCE-7", 1.0),
+    ("Sony", "ILCE-9", 1.0),
+    ("Sony", "ILCE-6000", 1.5),
+    ("Sony", "ILCE-6100", 1.5),
+    ("Sony", "ILCE-6300", 1.5),
+    ("Sony", "ILCE-6400", 1.5),
+    ("Sony", "ILCE-6500", 1.5),
+    ("Sony", "ILCE-6600", 1.5),
+    ("Sony", "ILCE-6700", 1.5),
+    ("Sony", "ZV-E10", 1.5),
+    ("Sony", "ZV-E1", 1.0),
+    ("Sony", "DSC-RX100", 2.7),
+    ("Sony", "DSC-RX1", 1.0),
+    ("Olympus", "", 2.0),
+    ("OM System", "", 2.0),
+    ("Panasonic", "DC-G", 2.0),
+    ("Panasonic", "DMC-G", 2.0),
+    ("Panasonic", "DC-S", 1.0),
+    ("Leica", "Q", 1.0),
+    ("Leica", "M", 1.0),
+    ("Leica", "SL", 1.0),
+    ("Leica", "CL", 1.5),
+    ("Ricoh", "GR", 1.5),
+    ("Pentax", "K", 1.5),
+    ("Pentax", "K-1", 1.0),
+    ("Hasselblad", "X1D", 0.79),
+    ("Hasselblad", "X2D", 0.79),
+    ("Hasselblad", "907X", 0.79),
+    ("Sigma", "fp", 1.0),
+];
+
+/// 焦距与35mm等效焦距
+pub struct FocalLengthService;
+
+impl FocalLengthService {
+    /// 补全等效系数与等效焦距，来源记为 `Computed`
+    /// 之前计算出的值会先清除，保证覆盖了焦距或机型后重新计算
+    pub fn apply_to_metadata(metadata: &mut PhotoMetadata) {
+        let computed = |metadata: &PhotoMetadata, key: &str| metadata.field_sources.get(key) == Some(&MetadataSource::Computed);
+        if computed(metadata, "settings.crop_factor") {
+            metadata.settings.crop_factor = None;
+        }
+        if computed(metadata, "settings.focal_length_35mm") {
+            metadata.settings.focal_length_35mm = None;
+        }
+        if computed(metadata, "settings.focal_length_35mm_mm") {
+            metadata.settings.focal_length_35mm_mm = None;
+        }
+
+        let before = MetadataFields::flatten(metadata);
+        Self::complete(&mut metadata.settings, &metadata.camera);
+        MetadataFields::record_sources(metadata, &before, MetadataSource::Computed);
+    }
+
+    /// 按 EXIF等效焦距标签 → 焦平面分辨率 → 机型表 的顺序补全缺失的值
+    /// 使用数值形式的焦距，等效焦距的文本与数值一起补全
+    pub fn complete(settings: &mut CameraSettings, camera: &CameraInfo) {
+        let focal_length = settings.focal_length_mm.filter(|f| *f > 0.0);
+        let focal_length_35mm = settings.focal_length_35mm_mm;
+
+        if settings.crop_factor.is_none() {
+            settings.crop_factor = match (focal_length, focal_length_35mm) {
+                // 等效焦距标签为整数，系数保留两位小数即可
+                (Some(focal_length), Some(focal_length_35mm)) => {
+                    Some((focal_length_35mm / focal_length * 100.0).round() / 100.0)
+                }
+                _ => Self::camera_crop_factor(camera),
+            };
+        }
+
+        if focal_length_35mm.is_none() {
+            if let (Some(focal_length), Some(crop_factor)) = (focal_length, settings.crop_factor) {
+                let focal_length_35mm = (focal_length * crop_factor).round();
+                settings.focal_length_35mm = Some(ExifService::focal_length_text(focal_length_35mm));
+                settings.focal_length_35mm_mm = Some(focal_length_35mm);
+            }
+        }
+    }
+
+    /// 由传感器尺寸（毫米）计算等效系数，明显不合理的值返回None
+    pub fn focal_plane_crop_factor(sensor_width_mm: f64, sensor_height_mm: f64) -> Option<f64> {
+        let diagonal = sensor_width_mm.hypot(sensor_height_mm);
+        let crop_factor = (FULL_FRAME_DIAGONAL_MM / diagonal * 100.0).round() / 100.0;
+        (0.3..=15.0).contains(&crop_factor).then_some(crop_factor)
+    }
+
+    /// 从机型表中查找等效系数
+    pub fn camera_crop_factor(camera: &CameraInfo) -> Option<f64> {
+        let mut camera = camera.clone();
+        CameraNames::normalize(&mut camera);
+        let (brand, model) = (camera.make?, camera.model?);
+
+        SENSOR_FORMATS
+            .iter()
+            .filter(|(format_brand, prefix, _)| *format_brand == brand && Self::matches_prefix(&model, prefix))
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(_, _, crop_factor)| *crop_factor)
+    }
+
+    fn matches_prefix(model: &str, prefix: &str) -> bool {
+        let Some(head) = model.get(..prefix.len()) else {
+            return false;
+        };
+        let ends_with_digit = prefix.ends_with(|c: char| c.is_ascii_digit());
+        head.eq_ignore_ascii_case(prefix)
+            && !(ends_with_digit && model[prefix.len()..].starts_with(|c: char| c.is_ascii_digit()))
+    }
+
+    /// 叠加层中的焦距文本，如 "23mm (35mm eq. 35mm)"
+    /// 不知道等效焦距或与实际焦距相同时只显示实际焦距
+    pub fn display_text(settings: &CameraSettings, format: FocalLengthFormat) -> Option<String> {
+        let actual = settings.focal_length.as_deref();
+        let equivalent = settings.focal_length_35mm.as_deref().filter(|equivalent| Some(*equivalent) != actual);
+
+        match (format, actual, equivalent) {
+            (FocalLengthFormat::Both, Some(actual), Some(equivalent)) => Some(format!("{} (35mm eq. {})", actual, equivalent)),
+            (FocalLengthFormat::Equivalent, _, Some(equivalent)) => Some(format!("{} (eq.)", equivalent)),
+            (_, Some(actual), _) => Some(actual.to_string()),
+            (_, None, equivalent) => equivalent.map(|equivalent| format!("{} (eq.)", equivalent)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(make: &str, model: &str) -> CameraInfo {
+        CameraInfo { make: Some(make.to_string()), model: Some(model.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_camera_crop_factor() {
+        let cases = [
+            ("FUJIFILM", "X-T5", Some(1.5)),
+            ("FUJIFILM", "X100VI", Some(1.5)),
+            ("FUJIFILM", "GFX100S", Some(0.79)),
+            ("Canon", "Canon EOS R5", Some(1.0)),
+            ("Canon", "Canon EOS R10", Some(1.6)),
+            ("Canon", "Canon EOS 90D", Some(1.6)),
+            ("NIKON CORPORATION", "NIKON Z 6_2", Some(1.0)),
+            ("NIKON CORPORATION", "NIKON Z 50", Some(1.5)),
+            ("NIKON CORPORATION", "NIKON D5", Some(1.0)),
+            ("NIKON CORPORATION", "NIKON D5600", Some(1.5)),
+            ("SONY", "ILCE-7M4", Some(1.0)),
+            ("SONY", "ILCE-6400", Some(1.5)),
+            ("OM Digital Solutions", "OM-1", Some(2.0)),
+            ("Apple", "iPhone 15 Pro", None),
+        ];
+
+        for (make, model, crop_factor) in cases {
+            assert_eq!(FocalLengthService::camera_crop_factor(&camera(make, model)), crop_factor, "{} {}", make, model);
+        }
+    }
+
+    #[test]
+    fn test_complete_and_display() {
+        // 没有等效焦距标签时由机型表推算
+        let mut settings = CameraSettings {
+            focal_length: Some("23mm".to_string()),
+            focal_length_mm: Some(23.0),
+            ..Default::default()
+        };
+        FocalLengthService::complete(&mut settings, &camera("FUJIFILM", "X-T5"));
+        assert_eq!(settings.focal_length_35mm.as_deref(), Some("35mm"));
+        assert_eq!(settings.focal_length_35mm_mm, Some(35.0));
+        assert_eq!(FocalLengthService::display_text(&settings, FocalLengthFormat::Both).as_deref(), Some("23mm (35mm eq. 35mm)"));
+        assert_eq!(FocalLengthService::display_text(&settings, FocalLengthFormat::Equivalent).as_deref(), Some("35mm (eq.)"));
+        assert_eq!(FocalLengthService::display_text(&settings, FocalLengthFormat::Actual).as_deref(), Some("23mm"));
+
+        // 有标签时由标签反推系数
+        let mut settings = CameraSettings {
+            focal_length: Some("6mm".to_string()),
+            focal_length_mm: Some(6.0),
+            focal_length_35mm: Some("24mm".to_string()),
+            focal_length_35mm_mm: Some(24.0),
+            ..Default::default()
+        };
+        FocalLengthService::complete(&mut settings, &camera("Apple", "iPhone 15 Pro"));
+        assert_eq!(settings.crop_factor, Some(4.0));
+
+        // 全画幅只显示一次
+        let settings = CameraSettings {
+            focal_length: Some("50mm".to_string()),
+            focal_length_35mm: Some("50mm".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(FocalLengthService::display_text(&settings, FocalLengthFormat::Both).as_deref(), Some("50mm"));
+
+        // 23.5×15.6mm 的APS-C传感器
+        assert_eq!(FocalLengthService::focal_plane_crop_factor(23.5, 15.6), Some(1.53));
+        assert_eq!(FocalLengthService::focal_plane_crop_factor(0.01, 0.01), None);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::app_paths::AppPaths;
+use crate::text_layout::FontChain;
+use crate::types::*;
+use anyhow::{Context, Result};
+use fontdb::{Database, Family, Query, Source, Stretch, Style, Weight, ID};
+use rusttype::Font;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Instant;
+
+/// 内置字体
+const EMBEDDED_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+/// 测试用字体，与内置字体一起组成不依赖系统字体的索引
+#[cfg(test)]
+pub const TEST_FONTS: &[&[u8]] = &[
+    include_bytes!("../tests/fixtures/fonts/DejaVuSans-Bold.ttf"),
+    include_bytes!("../tests/fixtures/fonts/DejaVuSans-Oblique.ttf"),
+    include_bytes!("../tests/fixtures/fonts/DejaVuSansMono.ttf"),
+];
+
+/// 用户字体文件夹（应用数据目录下）
+const USER_FONT_DIR: &str = "fonts";
+
+/// 可导入的字体格式
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// 请求的字体都不存在时依次尝试的字体，同时用作 sans-serif / serif 的默认字体
+const FALLBACK_FAMILIES: &[&str] = &[
+    "DejaVu Sans",
+    "Liberation Sans",
+    "Arial",
+    "Helvetica",
+    "Noto Sans",
+    "Segoe UI",
+    "PingFang SC",
+    "Microsoft YaHei",
+];
+
+/// 逐字回退使用的字体：先中日韩字体，再符号字体，最后是覆盖较广的西文字体
+const GLYPH_FALLBACK_FAMILIES: &[&str] = &[
+    "Noto Sans CJK SC",
+    "Noto Sans SC",
+    "Source Han Sans SC",
+    "PingFang SC",
+    "Hiragino Sans GB",
+    "Microsoft YaHei",
+    "WenQuanYi Micro Hei",
+    "WenQuanYi Zen Hei",
+    "Noto Sans CJK JP",
+    "Hiragino Sans",
+    "Yu Gothic",
+    "Noto Sans CJK KR",
+    "Apple SD Gothic Neo",
+    "Malgun Gothic",
+    "Noto Sans Symbols",
+    "Noto Sans Symbols 2",
+    "Segoe UI Symbol",
+    "Apple Symbols",
+    "DejaVu Sans",
+];
+
+/// 字体注册表
+/// 索引系统字体、内置字体与用户字体文件夹，按名称、字重和样式选择字体，解析后的字体按字形缓存
+pub struct FontRegistry {
+    database: RwLock<Arc<Database>>,
+    fonts: Mutex<HashMap<ID, Font<'static>>>,
+    user_dir: Option<PathBuf>,
+}
+
+impl FontRegistry {
+    /// 全局实例，首次使用时建立索引
+    pub fn shared() -> &'static FontRegistry {
+        static REGISTRY: OnceLock<FontRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| Self::load(AppPaths::subdir(USER_FONT_DIR)))
+    }
+
+    /// 建立字体索引，`user_dir` 为用户字体文件夹
+    pub fn load(user_dir: Option<PathBuf>) -> Self {
+        let start_time = Instant::now();
+        let mut database = Database::new();
+        database.load_system_fonts();
+        let registry = Self::with_database(database, user_dir);
+
+        println!("🔤 Indexed {} font faces in {}ms", registry.database().len(), start_time.elapsed().as_millis());
+        registry
+    }
+
+    /// 只索引内置字体、给定的字体数据与用户字体文件夹，不读取系统字体
+    #[cfg(test)]
+    pub fn with_fonts(fonts: &[&[u8]], user_dir: Option<PathBuf>) -> Self {
+        let mut database = Database::new();
+        for data in fonts {
+            database.load_font_data(data.to_vec());
+        }
+        Self::with_database(database, user_dir)
+    }
+
+    /// 在已有索引中加入内置字体与用户字体文件夹
+    fn with_database(mut database: Database, user_dir: Option<PathBuf>) -> Self {
+        // 不是有效字体时fontdb会忽略
+        database.load_font_data(EMBEDDED_FONT.to_vec());
+        if let Some(user_dir) = user_dir.as_ref().filter(|dir| dir.is_dir()) {
+            database.load_fonts_dir(user_dir);
+        }
+        Self::set_generic_families(&mut database);
+
+        Self { database: RwLock::new(Arc::new(database)), fonts: Mutex::new(HashMap::new()), user_dir }
+    }
+
+    /// 字体索引（与SVG渲染共用）
+    pub fn database(&self) -> Arc<Database> {
+        self.database.read().unwrap().clone()
+    }
+
+    /// 按名称、字重和样式选择单个字体，找不到时依次使用后备字体与任意可用字体
+    #[cfg(test)]
+    pub fn find(&self, family: &str, weight: &FontWeight, style: FontStyle) -> Result<Font<'static>> {
+        let database = self.database();
+        let id = Self::select(&database, family, weight, style).context("No font available")?;
+        self.font(&database, id)
+    }
+
+    /// 叠加文本使用的字体回退链：所选字体、用户导入的字体、中日韩与符号字体
+    /// 回退字体使用相同的字重与样式，系统中没有的字体跳过
+    pub fn resolve_chain(&self, font_settings: &FontSettings) -> Result<FontChain> {
+        let database = self.database();
+        let primary = Self::select(&database, &font_settings.family, &font_settings.weight, font_settings.style)
+            .context("No font available")?;
+
+        let mut user_families: Vec<&str> = database
+            .faces()
+            .filter(|face| self.is_user_font(&face.source))
+            .filter_map(|face| face.families.first().map(|(family, _)| family.as_str()))
+            .collect();
+        user_families.dedup();
+
+        let mut ids = vec![primary];
+        for family in user_families.into_iter().chain(GLYPH_FALLBACK_FAMILIES.iter().copied()) {
+            let query = Query {
+                families: &[Family::Name(family)],
+                ..Self::query(&[], &font_settings.weight, font_settings.style)
+            };
+            if let Some(id) = database.query(&query).filter(|id| !ids.contains(id)) {
+                ids.push(id);
+            }
+        }
+
+        let mut fonts = vec![self.font(&database, primary)?];
+        for id in &ids[1..] {
+            match self.font(&database, *id) {
+                Ok(font) => fonts.push(font),
+                Err(e) => println!("⚠️ Skipping fallback font: {}", e),
+            }
+        }
+        Ok(FontChain::new(fonts))
+    }
+
+    /// 解析字形，结果按字形缓存
+    fn font(&self, database: &Database, id: ID) -> Result<Font<'static>> {
+        if let Some(font) = self.fonts.lock().unwrap().get(&id) {
+            return Ok(font.clone());
+        }
+        let font = database
+            .with_face_data(id, |data, index| Font::try_from_vec_and_index(data.to_vec(), index))
+            .flatten()
+            .with_context(|| format!("Failed to parse font: {:?}", database.face(id).map(|face| &face.post_script_name)))?;
+        self.fonts.lock().unwrap().insert(id, font.clone());
+        Ok(font)
+    }
+
+    /// 选择字形：同一家族中字重与样式最接近者（CSS字体匹配规则）
+    pub fn select(database: &Database, family: &str, weight: &FontWeight, style: FontStyle) -> Option<ID> {
+        let requested = Self::parse_families(database, family);
+        let families: Vec<Family> = requested
+            .iter()
+            .map(|family| match family.as_str() {
+                "serif" => Family::Serif,
+                "sans-serif" | "system-ui" => Family::SansSerif,
+                "monospace" => Family::Monospace,
+                "cursive" => Family::Cursive,
+                "fantasy" => Family::Fantasy,
+                name => Family::Name(name),
+            })
+            .chain(FALLBACK_FAMILIES.iter().map(|name| Family::Name(name)))
+            .collect();
+
+        database
+            .query(&Self::query(&families, weight, style))
+            .or_else(|| database.faces().next().map(|face| face.id))
+    }
+
+    fn query<'a>(families: &'a [Family<'a>], weight: &FontWeight, style: FontStyle) -> Query<'a> {
+        Query {
+            families,
+            weight: match weight {
+                FontWeight::Normal => Weight::NORMAL,
+                FontWeight::Bold => Weight::BOLD,
+            },
+            stretch: Stretch::Normal,
+            style: match style {
+                FontStyle::Normal => Style::Normal,
+                FontStyle::Italic => Style::Italic,
+            },
+        }
+    }
+
+    /// 拆分逗号分隔的字体列表，去掉引号，并按索引中的写法修正大小写
+    fn parse_families(database: &Database, family: &str) -> Vec<String> {
+        family
+            .split(',')
+            .map(|name| name.trim().trim_matches(['"', '\'']).trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                database
+                    .faces()
+                    .flat_map(|face| face.families.iter())
+                    .find(|(family, _)| family.eq_ignore_ascii_case(name))
+                    .map(|(family, _)| family.clone())
+                    .unwrap_or_else(|| name.to_lowercase())
+            })
+            .collect()
+    }
+
+    /// 系统没有 fontdb 默认的 Arial / Times New Roman 时，通用字体改用第一个可用的后备字体
+    fn set_generic_families(database: &mut Database) {
+        let has_family = |name: &str| database.faces().any(|face| face.families.iter().any(|(family, _)| family == name));
+        let Some(fallback) = FALLBACK_FAMILIES.iter().find(|family| has_family(family)) else {
+            return;
+        };
+        let (has_arial, has_times) = (has_family("Arial"), has_family("Times New Roman"));
+        if !has_arial {
+            database.set_sans_serif_family(*fallback);
+        }
+        if !has_times {
+            database.set_serif_family(*fallback);
+        }
+    }
+
+    /// 所有字体家族，按名称排序
+    pub fn families(&self) -> Vec<FontFamilyInfo> {
+        let database = self.database();
+        let mut families: BTreeMap<String, FontFamilyInfo> = BTreeMap::new();
+
+        for face in database.faces() {
+            let Some((name, _)) = face.families.first() else {
+                continue;
+            };
+            let info = families
+                .entry(name.clone())
+                .or_insert_with(|| FontFamilyInfo { family: name.clone(), ..Default::default() });
+            if !info.weights.contains(&face.weight.0) {
+                info.weights.push(face.weight.0);
+            }
+            info.has_italic |= face.style != Style::Normal;
+            info.user_font |= self.is_user_font(&face.source);
+        }
+
+        families
+            .into_values()
+            .map(|mut info| {
+                info.weights.sort_unstable();
+                info
+            })
+            .collect()
+    }
+
+    fn is_user_font(&self, source: &Source) -> bool {
+        let path = match source {
+            Source::File(path) | Source::SharedFile(path, _) => path,
+            Source::Binary(_) => return false,
+        };
+        self.user_dir.as_ref().is_some_and(|user_dir| path.starts_with(user_dir))
+    }
+
+    /// 将字体文件复制到用户字体文件夹并加入索引，返回其中的字体家族
+    pub fn import(&self, font_path: &Path) -> Result<Vec<String>> {
+        let user_dir = self.user_dir.as_ref().context("App data directory is not available")?;
+        let is_font = font_path
+            .extension()
+            .is_some_and(|ext| FONT_EXTENSIONS.iter().any(|font_ext| ext.eq_ignore_ascii_case(font_ext)));
+        if !is_font {
+            anyhow::bail!("Unsupported font file: {:?}", font_path);
+        }
+
+        std::fs::create_dir_all(user_dir).with_context(|| format!("Failed to create font folder: {:?}", user_dir))?;
+        let file_name = font_path.file_name().context("Invalid font path")?;
+        let target = user_dir.join(file_name);
+        std::fs::copy(font_path, &target).with_context(|| format!("Failed to copy font: {:?}", font_path))?;
+
+        let mut database = self.database.write().unwrap();
+        let ids = Arc::make_mut(&mut database).load_font_source(Source::File(target.clone()));
+        if ids.is_empty() {
+            let _ = std::fs::remove_file(&target);
+            anyhow::bail!("Not a valid font file: {:?}", font_path);
+        }
+
+        let mut families: Vec<String> = ids
+            .iter()
+            .filter_map(|id| database.face(*id)?.families.first().map(|(family, _)| family.clone()))
+            .collect();
+        families.dedup();
+        println!("🔤 Imported font {:?}: {}", file_name, families.join(", "));
+        Ok(families)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_weight_style_and_fallback() {
+        let registry = FontRegistry::with_fonts(TEST_FONTS, None);
+        let database = registry.database();
+        let face = |family: &str, weight: FontWeight, style: FontStyle| {
+            let id = FontRegistry::select(&database, family, &weight, style).unwrap();
+            database.face(id).unwrap().clone()
+        };
+
+        // 不区分大小写，并在同一家族中选择粗体
+        let bold = face("'dejavu sans', sans-serif", FontWeight::Bold, FontStyle::Normal);
+        assert_eq!(bold.families[0].0, "DejaVu Sans");
+        assert_eq!(bold.weight, Weight::BOLD);
+
+        // 不存在的字体回退到后备字体
+        let fallback = face("No Such Font", FontWeight::Normal, FontStyle::Italic);
+        assert_eq!(fallback.families[0].0, "DejaVu Sans");
+        assert_ne!(fallback.style, Style::Normal);
+
+        // 解析后的字体按字形缓存
+        registry.find("DejaVu Sans", &FontWeight::Bold, FontStyle::Normal).unwrap();
+        registry.find("DejaVu Sans", &FontWeight::Bold, FontStyle::Normal).unwrap();
+        assert_eq!(registry.fonts.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_import_user_font() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let user_dir = temp_dir.path().join("fonts");
+        let registry = FontRegistry::with_fonts(&[], Some(user_dir.clone()));
+        assert!(registry.families().iter().all(|info| info.family != "DejaVu Sans Mono"));
+
+        let font_path = temp_dir.path().join("MyMono.ttf");
+        std::fs::write(&font_path, TEST_FONTS[2]).unwrap();
+
+        assert_eq!(registry.import(&font_path).unwrap(), vec!["DejaVu Sans Mono"]);
+        assert!(user_dir.join("MyMono.ttf").is_file());
+        let info = registry.families().into_iter().find(|info| info.family == "DejaVu Sans Mono").unwrap();
+        assert!(info.user_font);
+
+        // 不是字体的文件不会留在用户字体文件夹中
+        let fake = temp_dir.path().join("fake.ttf");
+        std::fs::write(&fake, "not a font").unwrap();
+        assert!(registry.import(&fake).is_err());
+        assert!(!user_dir.join("fake.ttf").exists());
+    }
+}
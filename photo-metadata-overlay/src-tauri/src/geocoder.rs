@@ -0,0 +1,346 @@
+use crate::app_paths::AppPaths;
+use crate::types::*;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// 内置城市表（没有打包GeoNames数据时使用）与国家名称表
+const BUNDLED_CITIES: &str = include_str!("../assets/gazetteer/cities.tsv");
+const COUNTRY_NAMES: &str = include_str!("../assets/gazetteer/countries.tsv");
+
+/// 应用数据目录中可选的GeoNames导出文件，按密度从高到低尝试
+const GEONAMES_FILES: &[&str] = &["cities500.txt", "cities1000.txt", "cities5000.txt", "cities15000.txt"];
+const GEONAMES_ADMIN1_FILE: &str = "admin1CodesASCII.txt";
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// 超过该距离的最近城市不再视为拍摄地点
+const MAX_DISTANCE_KM: f64 = 50.0;
+
+/// 地名库中的一个城市
+struct City {
+    name: String,
+    region: Option<String>,
+    country_code: String,
+}
+
+/// k-d树节点：城市在单位球面上的三维坐标及其下标
+/// 使用三维坐标可以避免经度在±180°处的断裂和两极附近的变形
+#[derive(Clone, Copy)]
+struct KdEntry {
+    point: [f64; 3],
+    city: usize,
+}
+
+/// 离线反向地理编码
+/// 照片位置不会发送到任何在线服务，只在本地地名库中查找最近的城市
+pub struct ReverseGeocoder {
+    cities: Vec<City>,
+    countries: HashMap<String, String>,
+    /// 按k-d树顺序排列的节点（每个切片的中点为该子树的根）
+    tree: Vec<KdEntry>,
+}
+
+impl ReverseGeocoder {
+    /// 全局实例，首次使用时加载
+    /// 应用数据目录的gazetteer文件夹中有GeoNames导出文件时使用它，否则使用内置地名库
+    pub fn shared() -> &'static ReverseGeocoder {
+        static GEOCODER: OnceLock<ReverseGeocoder> = OnceLock::new();
+        GEOCODER.get_or_init(|| Self::load_geonames().unwrap_or_else(Self::bundled))
+    }
+
+    /// 内置地名库：打包了GeoNames数据（`npm run fetch:geonames`）时使用它，否则使用内置城市表
+    pub fn bundled() -> Self {
+        #[cfg(bundled_geonames)]
+        match Self::bundled_geonames() {
+            Ok(geocoder) => return geocoder,
+            Err(e) => println!("⚠️ Failed to load bundled GeoNames gazetteer: {}, using bundled cities", e),
+        }
+        Self::city_table()
+    }
+
+    /// zlib压缩打包的GeoNames cities15000
+    #[cfg(bundled_geonames)]
+    fn bundled_geonames() -> anyhow::Result<Self> {
+        use std::io::Read;
+
+        const CITIES: &[u8] = include_bytes!("../assets/gazetteer/cities15000.txt.zlib");
+        const ADMIN1: &[u8] = include_bytes!("../assets/gazetteer/admin1CodesASCII.txt.zlib");
+        let inflate = |data: &[u8]| -> std::io::Result<String> {
+            let mut text = String::new();
+            flate2::read::ZlibDecoder::new(data).read_to_string(&mut text)?;
+            Ok(text)
+        };
+
+        Ok(Self::from_geonames(&inflate(CITIES)?, Some(&inflate(ADMIN1)?)))
+    }
+
+    /// 内置城市表，只收录主要城市与常见旅行目的地
+    fn city_table() -> Self {
+        let cities = Self::data_lines(BUNDLED_CITIES).filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            let [name, region, country_code, latitude, longitude] = columns.as_slice() else {
+                return None;
+            };
+            let city = City {
+                name: name.to_string(),
+                region: Some(region.to_string()).filter(|region| !region.is_empty()),
+                country_code: country_code.to_string(),
+            };
+            Some((city, latitude.parse().ok()?, longitude.parse().ok()?))
+        });
+        Self::build(cities.collect())
+    }
+
+    /// 解析GeoNames的 citiesNNN.txt，`admin1` 为可选的 admin1CodesASCII.txt 内容
+    pub fn from_geonames(cities: &str, admin1: Option<&str>) -> Self {
+        // admin1CodesASCII.txt：代码（"JP.22"）、名称、ASCII名称、geonameid
+        let regions: HashMap<&str, &str> = admin1
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split('\t');
+                Some((columns.next()?, columns.nth(1)?))
+            })
+            .collect();
+
+        // citiesNNN.txt：第2列为名称，第5、6列为经纬度，第9列为国家代码，第11列为一级行政区代码
+        let cities = cities.lines().filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 11 {
+                return None;
+            }
+            let country_code = columns[8];
+            let region = regions.get(format!("{}.{}", country_code, columns[10]).as_str());
+            let city = City {
+                name: columns[1].to_string(),
+                region: region.map(|region| region.to_string()),
+                country_code: country_code.to_string(),
+            };
+            Some((city, columns[4].parse().ok()?, columns[5].parse().ok()?))
+        });
+        Self::build(cities.collect())
+    }
+
+    /// 在应用数据目录中查找GeoNames导出文件
+    fn load_geonames() -> Option<Self> {
+        let directory = AppPaths::subdir("gazetteer")?;
+        let cities_path = GEONAMES_FILES
+            .iter()
+            .map(|name| directory.join(name))
+            .find(|path| path.is_file())?;
+
+        let cities = match std::fs::read_to_string(&cities_path) {
+            Ok(cities) => cities,
+            Err(e) => {
+                println!("⚠️ Failed to read gazetteer {:?}: {}, using bundled cities", cities_path, e);
+                return None;
+            }
+        };
+        let admin1 = std::fs::read_to_string(directory.join(GEONAMES_ADMIN1_FILE)).ok();
+
+        let geocoder = Self::from_geonames(&cities, admin1.as_deref());
+        println!("🗺️ Loaded {} places from {:?}", geocoder.cities.len(), cities_path);
+        Some(geocoder)
+    }
+
+    fn data_lines(text: &str) -> impl Iterator<Item = &str> {
+        text.lines().filter(|line| !line.is_empty() && !line.starts_with('#'))
+    }
+
+    fn build(entries: Vec<(City, f64, f64)>) -> Self {
+        let mut cities = Vec::with_capacity(entries.len());
+        let mut tree = Vec::with_capacity(entries.len());
+        for (city, latitude, longitude) in entries {
+            tree.push(KdEntry { point: Self::unit_vector(latitude, longitude), city: cities.len() });
+            cities.push(city);
+        }
+        Self::build_tree(&mut tree, 0);
+
+        let countries = Self::data_lines(COUNTRY_NAMES)
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(code, name)| (code.to_string(), name.to_string()))
+            .collect();
+
+        Self { cities, countries, tree }
+    }
+
+    /// 按坐标轴轮流取中位数，原地排成隐式的平衡k-d树
+    fn build_tree(entries: &mut [KdEntry], depth: usize) {
+        if entries.len() <= 1 {
+            return;
+        }
+        let axis = depth % 3;
+        let middle = entries.len() / 2;
+        entries.select_nth_unstable_by(middle, |a, b| a.point[axis].total_cmp(&b.point[axis]));
+
+        let (left, right) = entries.split_at_mut(middle);
+        Self::build_tree(left, depth + 1);
+        Self::build_tree(&mut right[1..], depth + 1);
+    }
+
+    fn unit_vector(latitude: f64, longitude: f64) -> [f64; 3] {
+        let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+        [
+            latitude.cos() * longitude.cos(),
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+        ]
+    }
+
+    fn squared_distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+    }
+
+    /// 在子树中查找最近的节点，`best` 为目前的（弦长平方，城市下标）
+    fn nearest(entries: &[KdEntry], depth: usize, target: &[f64; 3], best: &mut Option<(f64, usize)>) {
+        if entries.is_empty() {
+            return;
+        }
+        let middle = entries.len() / 2;
+        let entry = &entries[middle];
+
+        let distance = Self::squared_distance(&entry.point, target);
+        if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+            *best = Some((distance, entry.city));
+        }
+
+        let axis = depth % 3;
+        let difference = target[axis] - entry.point[axis];
+        let (near, far) = if difference < 0.0 {
+            (&entries[..middle], &entries[middle + 1..])
+        } else {
+            (&entries[middle + 1..], &entries[..middle])
+        };
+
+        Self::nearest(near, depth + 1, target, best);
+        // 分割面比当前最近点还近时，另一侧才可能有更近的点
+        if best.is_none_or(|(best_distance, _)| difference * difference < best_distance) {
+            Self::nearest(far, depth + 1, target, best);
+        }
+    }
+
+    /// 查找坐标附近的城市，超出 `MAX_DISTANCE_KM` 时返回None
+    pub fn lookup(&self, latitude: f64, longitude: f64) -> Option<PlaceInfo> {
+        if !latitude.is_finite() || !longitude.is_finite() {
+            return None;
+        }
+
+        let mut best = None;
+        Self::nearest(&self.tree, 0, &Self::unit_vector(latitude, longitude), &mut best);
+        let (chord_squared, index) = best?;
+
+        // 弦长换算为大圆距离
+        let distance_km = 2.0 * EARTH_RADIUS_KM * (chord_squared.sqrt() / 2.0).min(1.0).asin();
+        if distance_km > MAX_DISTANCE_KM {
+            return None;
+        }
+
+        let city = &self.cities[index];
+        Some(PlaceInfo {
+            city: city.name.clone(),
+            region: city.region.clone(),
+            country: self.countries.get(&city.country_code).cloned(),
+            country_code: city.country_code.clone(),
+            distance_km,
+        })
+    }
+
+    /// 地址文本，如 "Kyoto, Japan"；城市与国家同名时（如新加坡）只显示一次
+    pub fn address_text(place: &PlaceInfo) -> String {
+        match place.country.as_deref() {
+            Some(country) if country != place.city => format!("{}, {}", place.city, country),
+            _ => place.city.clone(),
+        }
+    }
+
+    /// 为带坐标但没有地址的元数据补全地点和地址
+    pub fn apply_to_metadata(metadata: &mut PhotoMetadata) {
+        let Some(location) = metadata.location.as_mut() else {
+            return;
+        };
+        if location.address.is_some() {
+            return;
+        }
+
+        if let Some(place) = Self::shared().lookup(location.latitude, location.longitude) {
+            location.address = Some(Self::address_text(&place));
+            location.place = Some(place);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_lookup() {
+        let geocoder = ReverseGeocoder::city_table();
+
+        // 清水寺
+        let place = geocoder.lookup(34.9949, 135.7850).unwrap();
+        assert_eq!(ReverseGeocoder::address_text(&place), "Kyoto, Japan");
+        assert!(place.distance_km < 5.0);
+
+        let place = geocoder.lookup(-18.14, 178.42).unwrap();
+        assert_eq!(ReverseGeocoder::address_text(&place), "Suva, Fiji");
+
+        let place = geocoder.lookup(1.29, 103.85).unwrap();
+        assert_eq!(ReverseGeocoder::address_text(&place), "Singapore");
+
+        // 太平洋中部附近没有城市
+        assert!(geocoder.lookup(0.0, -140.0).is_none());
+        assert!(geocoder.lookup(f64::NAN, 0.0).is_none());
+    }
+
+    #[cfg(bundled_geonames)]
+    #[test]
+    fn test_bundled_geonames() {
+        let geocoder = ReverseGeocoder::bundled();
+        assert!(geocoder.cities.len() > 20_000);
+
+        // 内置城市表中没有的城市
+        let place = geocoder.lookup(45.68, -111.04).unwrap();
+        assert_eq!(place.city, "Bozeman");
+        assert_eq!(place.region.as_deref(), Some("Montana"));
+    }
+
+    #[test]
+    fn test_nearest_matches_linear_scan() {
+        let geocoder = ReverseGeocoder::bundled();
+        for latitude in (-60..=70).step_by(7) {
+            for longitude in (-180..180).step_by(11) {
+                let target = ReverseGeocoder::unit_vector(latitude as f64, longitude as f64);
+                let mut best = None;
+                ReverseGeocoder::nearest(&geocoder.tree, 0, &target, &mut best);
+
+                let expected = geocoder
+                    .tree
+                    .iter()
+                    .map(|entry| ReverseGeocoder::squared_distance(&entry.point, &target))
+                    .fold(f64::INFINITY, f64::min);
+                assert_eq!(best.unwrap().0, expected, "{}, {}", latitude, longitude);
+            }
+        }
+    }
+
+    #[test]
+    fn test_geonames_format() {
+        let cities = "1850147\tTokyo\tTokyo\tTokio\t35.6895\t139.69171\tP\tPPLC\tJP\t\t40\t\t\t\t8336599\t\t44\tAsia/Tokyo\t2022-11-25\n\
+                      2198148\tWaiyevo\tWaiyevo\t\t-16.79\t179.98\tP\tPPL\tFJ\t\t3\t\t\t\t600\t\t10\tPacific/Fiji\t2022-11-25\n\
+                      1857910\tKyoto\tKyoto\t\t35.02107\t135.75385\tP\tPPLA\tJP\t\t22\t\t\t\t1459640\t\t50\tAsia/Tokyo\t2022-11-25\n";
+        let admin1 = "JP.22\tKyoto\tKyoto\t1857907\nJP.40\tTokyo\tTokyo\t1850144\n";
+        let geocoder = ReverseGeocoder::from_geonames(cities, Some(admin1));
+
+        let place = geocoder.lookup(35.0, 135.8).unwrap();
+        assert_eq!(place.city, "Kyoto");
+        assert_eq!(place.region.as_deref(), Some("Kyoto"));
+        assert_eq!(place.country.as_deref(), Some("Japan"));
+
+        // 跨越180°经线仍能找到最近的城市
+        let place = geocoder.lookup(-16.8, -179.98).unwrap();
+        assert_eq!(place.city, "Waiyevo");
+        assert_eq!(place.region, None);
+        assert!(place.distance_km < 5.0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::types::*;
+
+const FEET_PER_METER: f64 = 3.280_84;
+
+/// 16方位名称，从正北开始顺时针排列
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+    "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+];
+
+/// GPS信息格式化器
+pub struct GpsFormatter;
+
+impl GpsFormatter {
+    /// 经纬度文本，如 "35.68944°N, 139.69167°E" 或 "35°41'22"N, 139°41'30"E"
+    pub fn coordinates_text(location: &LocationInfo, format: CoordinateFormat) -> String {
+        format!(
+            "{}, {}",
+            Self::coordinate_text(location.latitude, 'N', 'S', format),
+            Self::coordinate_text(location.longitude, 'E', 'W', format)
+        )
+    }
+
+    /// 单个坐标分量，正数使用 `positive` 半球标记，负数使用 `negative`
+    pub fn coordinate_text(value: f64, positive: char, negative: char, format: CoordinateFormat) -> String {
+        let hemisphere = if value < 0.0 { negative } else { positive };
+        let value = value.abs();
+
+        match format {
+            CoordinateFormat::Decimal => format!("{:.5}°{}", value, hemisphere),
+            CoordinateFormat::Dms => {
+                // 先整体取整到秒，避免出现 59'60" 这样的进位错误
+                let total_seconds = (value * 3600.0).round() as u64;
+                format!(
+                    "{}°{}'{}\"{}",
+                    total_seconds / 3600,
+                    total_seconds % 3600 / 60,
+                    total_seconds % 60,
+                    hemisphere
+                )
+            }
+        }
+    }
+
+    /// 海拔文本，如 "1234 m" 或 "4050 ft"
+    pub fn altitude_text(meters: f64, unit: AltitudeUnit) -> String {
+        match unit {
+            AltitudeUnit::Meters => format!("{:.0} m", meters),
+            AltitudeUnit::Feet => format!("{:.0} ft", meters * FEET_PER_METER),
+        }
+    }
+
+    /// 拍摄方向文本，如 "123° ESE"
+    pub fn direction_text(degrees: f64) -> String {
+        let degrees = degrees.rem_euclid(360.0);
+        let index = ((degrees / 22.5).round() as usize) % COMPASS_POINTS.len();
+        format!("{:.0}° {}", degrees, COMPASS_POINTS[index])
+    }
+
+    /// 叠加层中的位置行：有地址时显示地址，否则显示坐标
+    pub fn location_text(location: &LocationInfo, settings: &LocationFormatSettings) -> String {
+        match location.address.as_deref() {
+            Some(address) if !address.is_empty() => address.to_string(),
+            _ => Self::coordinates_text(location, settings.coordinate_format),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinate_formats() {
+        let location = LocationInfo { latitude: 35.689_444, longitude: -139.691_667, ..Default::default() };
+
+        assert_eq!(GpsFormatter::coordinates_text(&location, CoordinateFormat::Decimal), "35.68944°N, 139.69167°W");
+        assert_eq!(GpsFormatter::coordinates_text(&location, CoordinateFormat::Dms), "35°41'22\"N, 139°41'30\"W");
+
+        // 59.9999秒进位到下一分
+        assert_eq!(GpsFormatter::coordinate_text(-0.999_999_9, 'N', 'S', CoordinateFormat::Dms), "1°0'0\"S");
+    }
+
+    #[test]
+    fn test_altitude_and_direction() {
+        assert_eq!(GpsFormatter::altitude_text(1234.4, AltitudeUnit::Meters), "1234 m");
+        assert_eq!(GpsFormatter::altitude_text(1234.4, AltitudeUnit::Feet), "4050 ft");
+        assert_eq!(GpsFormatter::altitude_text(-12.0, AltitudeUnit::Meters), "-12 m");
+
+        assert_eq!(GpsFormatter::direction_text(123.0), "123° ESE");
+        assert_eq!(GpsFormatter::direction_text(359.0), "359° N");
+    }
+}
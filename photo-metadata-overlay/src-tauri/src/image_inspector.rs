@@ -0,0 +1,559 @@
+use crate::metadata_writer::{MetadataWriter, EXIF_HEADER, ICC_HEADER, PNG_SIGNATURE};
+use crate::raw_service::{RawService, RAW_EXTENSIONS};
+use crate::tiff_reader::TiffReader;
+use crate::types::*;
+use anyhow::{Context, Result};
+use std::io::Cursor;
+use std::path::Path;
+
+const TAG_BITS_PER_SAMPLE: u16 = 0x0102;
+const TAG_PHOTOMETRIC: u16 = 0x0106;
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_LENGTH: u16 = 0x0101;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_SAMPLES_PER_PIXEL: u16 = 0x0115;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_TILE_OFFSETS: u16 = 0x0144;
+const TAG_TILE_BYTE_COUNTS: u16 = 0x0145;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_EXIF_IFD_POINTER: u16 = 0x8769;
+const TAG_ICC_PROFILE: u16 = 0x8773;
+
+/// TIFF光度解释中的CFA（拜耳阵列）与LinearRaw，用于定位RAW的传感器数据
+const PHOTOMETRIC_CFA: u32 = 32803;
+const PHOTOMETRIC_LINEAR_RAW: u32 = 34892;
+
+/// 防止损坏文件中的IFD循环引用
+const MAX_IFDS: usize = 64;
+
+/// TIFF中一个IFD的图像信息
+#[derive(Debug, Default)]
+struct TiffImage {
+    width: Option<u32>,
+    height: Option<u32>,
+    bits_per_sample: Option<u32>,
+    samples_per_pixel: Option<u32>,
+    photometric: Option<u32>,
+}
+
+/// 图片文件检查
+/// 按文件头识别真实格式并读取尺寸、颜色类型等信息，不解码像素数据
+pub struct ImageInspector;
+
+impl ImageInspector {
+    /// 检查图片文件，返回详细报告
+    pub fn inspect<P: AsRef<Path>>(file_path: P) -> Result<ImageFileReport> {
+        let path = file_path.as_ref();
+        let data = std::fs::read(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+        let format = Self::detect_format(&data, path);
+
+        let mut report = ImageFileReport {
+            file_path: path.to_string_lossy().to_string(),
+            file_size: data.len() as u64,
+            format,
+            extension_matches: Self::expected_format(path) == Some(format),
+            width: None,
+            height: None,
+            color_type: None,
+            bit_depth: None,
+            has_exif: false,
+            has_icc: false,
+            truncated: false,
+            valid: false,
+            problems: Vec::new(),
+        };
+
+        match format {
+            DetectedFormat::Jpeg => Self::inspect_jpeg(&data, &mut report),
+            DetectedFormat::Png => Self::inspect_png(&data, &mut report),
+            DetectedFormat::Tiff => {
+                if let Some(reader) = TiffReader::new(&data) {
+                    Self::inspect_tiff(&reader, &mut report, false);
+                }
+            }
+            DetectedFormat::Raw => Self::inspect_raw(&data, &mut report),
+            DetectedFormat::Heic | DetectedFormat::Avif => Self::inspect_isobmff(&data, &mut report),
+            DetectedFormat::WebP => Self::inspect_webp(&data, &mut report),
+            DetectedFormat::Gif => Self::inspect_gif(&data, &mut report),
+            DetectedFormat::Bmp => Self::inspect_bmp(&data, &mut report),
+            DetectedFormat::Unknown => {}
+        }
+
+        Self::check(&mut report);
+        Ok(report)
+    }
+
+    /// 能否交给处理流程，无法读取的文件视为无效
+    pub fn is_valid<P: AsRef<Path>>(file_path: P) -> bool {
+        Self::inspect(file_path).map(|report| report.valid).unwrap_or(false)
+    }
+
+    /// 按魔数识别格式
+    /// DNG、CR2、NEF、ARW的头部与TIFF相同，只能由扩展名区分
+    pub fn detect_format(data: &[u8], path: &Path) -> DetectedFormat {
+        if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            DetectedFormat::Jpeg
+        } else if data.starts_with(PNG_SIGNATURE) {
+            DetectedFormat::Png
+        } else if RawService::is_raw_data(data) {
+            DetectedFormat::Raw
+        } else if TiffReader::new(data).is_some() {
+            if RawService::is_raw_file(path) {
+                DetectedFormat::Raw
+            } else {
+                DetectedFormat::Tiff
+            }
+        } else if data.get(4..8) == Some(b"ftyp") {
+            Self::isobmff_format(data)
+        } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+            DetectedFormat::WebP
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            DetectedFormat::Gif
+        } else if data.starts_with(b"BM") && data.len() >= 30 {
+            DetectedFormat::Bmp
+        } else {
+            DetectedFormat::Unknown
+        }
+    }
+
+    /// 扩展名对应的格式
+    fn expected_format(path: &Path) -> Option<DetectedFormat> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        let format = match extension.as_str() {
+            "jpg" | "jpeg" | "jpe" => DetectedFormat::Jpeg,
+            "png" => DetectedFormat::Png,
+            "tif" | "tiff" => DetectedFormat::Tiff,
+            "heic" | "heif" => DetectedFormat::Heic,
+            "avif" => DetectedFormat::Avif,
+            "webp" => DetectedFormat::WebP,
+            "gif" => DetectedFormat::Gif,
+            "bmp" => DetectedFormat::Bmp,
+            ext if RAW_EXTENSIONS.contains(&ext) => DetectedFormat::Raw,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// 汇总问题并判断是否可以处理
+    fn check(report: &mut ImageFileReport) {
+        let supported = matches!(
+            report.format,
+            DetectedFormat::Jpeg | DetectedFormat::Png | DetectedFormat::Tiff | DetectedFormat::Raw
+        );
+
+        if report.format == DetectedFormat::Unknown {
+            report.problems.push("Unrecognized file content".to_string());
+        } else {
+            if !supported {
+                report.problems.push(format!("{:?} images are not supported", report.format));
+            }
+            if !report.extension_matches {
+                report.problems.push(format!("File extension does not match its {:?} content", report.format));
+            }
+        }
+        if report.truncated {
+            report.problems.push("File is truncated or incomplete".to_string());
+        }
+        if supported && (report.width.is_none() || report.height.is_none()) {
+            report.problems.push("Could not read image dimensions".to_string());
+        }
+
+        // 扩展名不一致只作提示，图片加载按内容识别格式
+        report.valid = supported && !report.truncated && report.width.is_some() && report.height.is_some();
+    }
+
+    /// JPEG：帧头（SOF）中的尺寸、精度与分量数，APP段中的EXIF与ICC
+    fn inspect_jpeg(data: &[u8], report: &mut ImageFileReport) {
+        let mut components = None;
+        let mut adobe_transform = None;
+        // 标记段之后应紧跟扫描开始（SOS）标记
+        let mut position = 2;
+
+        for (marker, payload) in MetadataWriter::jpeg_segments(data) {
+            position += 4 + payload.len();
+            match marker {
+                0xE1 if payload.starts_with(EXIF_HEADER) => report.has_exif = true,
+                0xE2 if payload.starts_with(ICC_HEADER) => report.has_icc = true,
+                0xEE if payload.starts_with(b"Adobe") => adobe_transform = payload.get(11).copied(),
+                // SOF0-SOF15，除去DHT（C4）、JPG（C8）与DAC（CC）
+                0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && payload.len() >= 6 => {
+                    report.bit_depth = Some(payload[0]);
+                    report.height = Some(u16::from_be_bytes([payload[1], payload[2]]) as u32).filter(|h| *h > 0);
+                    report.width = Some(u16::from_be_bytes([payload[3], payload[4]]) as u32).filter(|w| *w > 0);
+                    components = Some(payload[5]);
+                }
+                _ => {}
+            }
+        }
+
+        report.color_type = match (components, adobe_transform) {
+            (Some(1), _) => Some("Grayscale"),
+            (Some(3), _) => Some("RGB"),
+            (Some(4), Some(2)) => Some("YCCK"),
+            (Some(4), _) => Some("CMYK"),
+            _ => None,
+        }
+        .map(str::to_string);
+
+        // 熵编码数据中的0xFF都会填充为 FF 00，扫描开始之后出现的 FF D9 只能是结束标记
+        let has_scan = data.get(position..position + 2) == Some(&[0xFF, 0xDA]);
+        report.truncated = !has_scan || !data[position..].windows(2).any(|w| w == [0xFF, 0xD9]);
+    }
+
+    /// PNG：IHDR中的尺寸、位深与颜色类型
+    fn inspect_png(data: &[u8], report: &mut ImageFileReport) {
+        let chunks = MetadataWriter::png_chunks(data);
+
+        if let Some((_, ihdr)) = chunks.first().filter(|(chunk_type, payload)| chunk_type == b"IHDR" && payload.len() >= 13) {
+            report.width = Some(u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]));
+            report.height = Some(u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]));
+            report.bit_depth = Some(ihdr[8]);
+            report.color_type = match ihdr[9] {
+                0 => Some("Grayscale"),
+                2 => Some("RGB"),
+                3 => Some("Palette"),
+                4 => Some("GrayscaleAlpha"),
+                6 => Some("RGBA"),
+                _ => None,
+            }
+            .map(str::to_string);
+        }
+
+        report.has_exif = chunks.iter().any(|(chunk_type, _)| chunk_type == b"eXIf");
+        report.has_icc = chunks.iter().any(|(chunk_type, _)| chunk_type == b"iCCP");
+        // 块遍历在数据不完整处停止，读不到IEND说明文件被截断
+        report.truncated = !chunks.iter().any(|(chunk_type, _)| chunk_type == b"IEND");
+    }
+
+    /// TIFF：遍历所有IFD与SubIFD，检查条带/瓦片数据是否超出文件
+    /// `prefer_raw` 为true时报告CFA/LinearRaw图像的信息，否则报告第一幅图像
+    fn inspect_tiff(reader: &TiffReader, report: &mut ImageFileReport, prefer_raw: bool) {
+        let data_len = reader.data().len() as u64;
+        let mut images = Vec::new();
+        let mut pending: Vec<usize> = reader.first_ifd_offset().into_iter().collect();
+        let mut visited = Vec::new();
+
+        while let Some(offset) = pending.pop() {
+            if visited.contains(&offset) || visited.len() >= MAX_IFDS {
+                continue;
+            }
+            visited.push(offset);
+            let Some(entries) = reader.ifd_entries(offset) else {
+                report.truncated = true;
+                continue;
+            };
+
+            let mut image = TiffImage::default();
+            let (mut offsets, mut counts) = (Vec::new(), Vec::new());
+            for entry in &entries {
+                let first = || reader.entry_u32_values(entry).first().copied();
+                match entry.tag {
+                    TAG_IMAGE_WIDTH => image.width = first(),
+                    TAG_IMAGE_LENGTH => image.height = first(),
+                    TAG_BITS_PER_SAMPLE => image.bits_per_sample = first(),
+                    TAG_SAMPLES_PER_PIXEL => image.samples_per_pixel = first(),
+                    TAG_PHOTOMETRIC => image.photometric = first(),
+                    TAG_STRIP_OFFSETS | TAG_TILE_OFFSETS => offsets = reader.entry_u32_values(entry),
+                    TAG_STRIP_BYTE_COUNTS | TAG_TILE_BYTE_COUNTS => counts = reader.entry_u32_values(entry),
+                    TAG_SUB_IFDS => pending.extend(reader.entry_u32_values(entry).into_iter().map(|o| o as usize)),
+                    TAG_EXIF_IFD_POINTER => report.has_exif = true,
+                    TAG_ICC_PROFILE => report.has_icc = true,
+                    _ => {}
+                }
+            }
+
+            if offsets.iter().zip(&counts).any(|(offset, count)| *offset as u64 + *count as u64 > data_len) {
+                report.truncated = true;
+            }
+            images.push(image);
+
+            if let Some(next) = reader.next_ifd_offset(offset).filter(|next| *next != 0) {
+                pending.push(next);
+            }
+        }
+
+        let is_raw = |image: &&TiffImage| matches!(image.photometric, Some(PHOTOMETRIC_CFA | PHOTOMETRIC_LINEAR_RAW));
+        let image = if prefer_raw { images.iter().find(is_raw) } else { None }.or(images.first());
+        let Some(image) = image else {
+            return;
+        };
+
+        if !prefer_raw {
+            report.width = image.width;
+            report.height = image.height;
+        }
+        report.bit_depth = image.bits_per_sample.and_then(|bits| u8::try_from(bits).ok());
+        let samples = image.samples_per_pixel.unwrap_or(1);
+        report.color_type = match image.photometric {
+            Some(0 | 1) if samples >= 2 => Some("GrayscaleAlpha"),
+            Some(0 | 1) => Some("Grayscale"),
+            Some(2) if samples >= 4 => Some("RGBA"),
+            Some(2) => Some("RGB"),
+            Some(3) => Some("Palette"),
+            Some(5) => Some("CMYK"),
+            Some(6) => Some("YCbCr"),
+            Some(8) => Some("CIELab"),
+            Some(PHOTOMETRIC_CFA) => Some("CFA"),
+            Some(PHOTOMETRIC_LINEAR_RAW) => Some("LinearRaw"),
+            _ => None,
+        }
+        .map(str::to_string);
+    }
+
+    /// RAW：尺寸取内嵌预览（即实际渲染的图像），位深与颜色类型取传感器数据
+    fn inspect_raw(data: &[u8], report: &mut ImageFileReport) {
+        // ORF的头部魔数不同，但结构与TIFF相同
+        let reader = TiffReader::new(data).or_else(|| {
+            matches!(data.get(..4), Some(b"IIRO" | b"IIRS" | b"MMOR")).then(|| TiffReader::with_byte_order(data, data[0] == b'I'))
+        });
+
+        if let Some(reader) = reader {
+            Self::inspect_tiff(&reader, report, true);
+        } else if data.starts_with(b"FUJIFILMCCD-RAW") {
+            // RAF头部中的内嵌JPEG与CFA数据位置（大端）
+            let read = |position: usize| data.get(position..position + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64);
+            for (offset, length) in [(84, 88), (100, 104)] {
+                if let (Some(offset), Some(length)) = (read(offset), read(length)) {
+                    report.truncated |= offset + length > data.len() as u64;
+                }
+            }
+        } else {
+            report.truncated = Self::isobmff_truncated(data);
+        }
+
+        // 使用已读入的数据，不再重复读取文件
+        report.has_exif |= RawService::read_exif_data(data.to_vec()).is_ok_and(|sources| !sources.is_empty());
+
+        match RawService::preview_data(data) {
+            Some(preview) => {
+                let dimensions = image::io::Reader::new(Cursor::new(preview))
+                    .with_guessed_format()
+                    .ok()
+                    .and_then(|reader| reader.into_dimensions().ok());
+                if let Some((width, height)) = dimensions {
+                    report.width = Some(width);
+                    report.height = Some(height);
+                }
+            }
+            None => report.problems.push("RAW file has no usable embedded preview".to_string()),
+        }
+    }
+
+    /// 按ftyp中的主品牌与兼容品牌区分HEIC与AVIF
+    fn isobmff_format(data: &[u8]) -> DetectedFormat {
+        let size = data.get(..4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize).unwrap_or(0);
+        let Some(brands) = data.get(8..size.clamp(12, data.len().max(12))) else {
+            return DetectedFormat::Unknown;
+        };
+        let brands: Vec<&[u8]> = brands.chunks_exact(4).collect();
+
+        if brands.iter().any(|brand| matches!(*brand, b"avif" | b"avis")) {
+            DetectedFormat::Avif
+        } else if brands.iter().any(|brand| matches!(*brand, b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" | b"mif1" | b"msf1")) {
+            DetectedFormat::Heic
+        } else {
+            DetectedFormat::Unknown
+        }
+    }
+
+    /// HEIC/AVIF：ispe中的尺寸（取最大者，即主图像或网格）、pixi中的位深、colr中的ICC
+    fn inspect_isobmff(data: &[u8], report: &mut ImageFileReport) {
+        let read = |position: usize| data.get(position..position + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+        // 元数据位于文件开头的meta盒中
+        let head = &data[..data.len().min(1 << 20)];
+
+        let largest = Self::find_all(head, b"ispe")
+            .filter_map(|position| Some((read(position + 8)?, read(position + 12)?)))
+            .max_by_key(|(width, height)| *width as u64 * *height as u64);
+        if let Some((width, height)) = largest {
+            report.width = Some(width);
+            report.height = Some(height);
+        }
+
+        if let Some(position) = Self::find_all(head, b"pixi").next() {
+            let channels = head.get(position + 8).copied();
+            report.bit_depth = head.get(position + 9).copied();
+            report.color_type = match channels {
+                Some(1) => Some("Grayscale"),
+                Some(3) => Some("RGB"),
+                Some(4) => Some("RGBA"),
+                _ => None,
+            }
+            .map(str::to_string);
+        }
+
+        report.has_icc = Self::find_all(head, b"colr").any(|position| matches!(head.get(position + 4..position + 8), Some(b"prof" | b"rICC")));
+        // EXIF作为类型为 "Exif" 的条目存放，在infe中声明
+        report.has_exif = Self::find_all(head, b"infe").any(|position| head.get(position..position + 64).is_some_and(|infe| Self::find_all(infe, b"Exif").next().is_some()));
+        report.truncated = Self::isobmff_truncated(data);
+    }
+
+    /// 顶层盒的长度超出文件时视为截断
+    fn isobmff_truncated(data: &[u8]) -> bool {
+        let mut position = 0usize;
+        while position < data.len() {
+            let Some(header) = data.get(position..position + 8) else {
+                return true;
+            };
+            let size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => return false, // 盒延伸到文件末尾
+                1 => match data.get(position + 8..position + 16) {
+                    Some(large) => u64::from_be_bytes(large.try_into().unwrap_or_default()),
+                    None => return true,
+                },
+                size => size as u64,
+            };
+            if size < 8 {
+                return true;
+            }
+            position = match usize::try_from(position as u64 + size) {
+                Ok(next) => next,
+                Err(_) => return true,
+            };
+        }
+        position > data.len()
+    }
+
+    /// WebP：VP8X扩展头中的标志与画布尺寸，或VP8/VP8L位流头中的尺寸
+    fn inspect_webp(data: &[u8], report: &mut ImageFileReport) {
+        let riff_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        report.truncated = data.len() < riff_size + 8;
+        report.bit_depth = Some(8);
+        let mut alpha = false;
+
+        let mut position = 12;
+        while let Some(header) = data.get(position..position + 8) {
+            let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+            let payload = &data[position + 8..data.len().min(position + 8 + size)];
+            let read_u24 = |at: usize| payload.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]) + 1);
+
+            match &header[..4] {
+                b"VP8X" => {
+                    let flags = payload.first().copied().unwrap_or(0);
+                    report.has_icc |= flags & 0x20 != 0;
+                    alpha |= flags & 0x10 != 0;
+                    report.has_exif |= flags & 0x08 != 0;
+                    report.width = read_u24(4);
+                    report.height = read_u24(7);
+                }
+                b"VP8 " if report.width.is_none() && payload.len() >= 10 && payload[3..6] == [0x9D, 0x01, 0x2A] => {
+                    report.width = Some(u16::from_le_bytes([payload[6], payload[7]]) as u32 & 0x3FFF);
+                    report.height = Some(u16::from_le_bytes([payload[8], payload[9]]) as u32 & 0x3FFF);
+                }
+                b"VP8L" if payload.len() >= 5 && payload[0] == 0x2F => {
+                    let bits = u32::from_le_bytes([payload[1], payload[2], payload[3], payload[4]]);
+                    if report.width.is_none() {
+                        report.width = Some((bits & 0x3FFF) + 1);
+                        report.height = Some(((bits >> 14) & 0x3FFF) + 1);
+                    }
+                    alpha |= (bits >> 28) & 1 != 0;
+                }
+                b"ALPH" => alpha = true,
+                b"EXIF" => report.has_exif = true,
+                b"ICCP" => report.has_icc = true,
+                _ => {}
+            }
+            // 块按偶数字节对齐
+            position += 8 + size + (size & 1);
+        }
+
+        report.color_type = Some(if alpha { "RGBA" } else { "RGB" }.to_string());
+    }
+
+    /// GIF：逻辑屏幕尺寸与全局调色板位数，文件应以结束符0x3B结尾
+    fn inspect_gif(data: &[u8], report: &mut ImageFileReport) {
+        if data.len() >= 11 {
+            report.width = Some(u16::from_le_bytes([data[6], data[7]]) as u32);
+            report.height = Some(u16::from_le_bytes([data[8], data[9]]) as u32);
+            report.bit_depth = Some((data[10] & 0x07) + 1);
+        }
+        report.color_type = Some("Palette".to_string());
+        report.truncated = data.last() != Some(&0x3B);
+    }
+
+    /// BMP：信息头中的尺寸与每像素位数，文件头中声明的大小用于判断截断
+    fn inspect_bmp(data: &[u8], report: &mut ImageFileReport) {
+        let declared_size = u32::from_le_bytes([data[2], data[3], data[4], data[5]]) as usize;
+        report.truncated = data.len() < declared_size;
+        report.width = Some(i32::from_le_bytes([data[18], data[19], data[20], data[21]]).unsigned_abs());
+        // 高度为负表示自上而下存储
+        report.height = Some(i32::from_le_bytes([data[22], data[23], data[24], data[25]]).unsigned_abs());
+
+        let (color_type, bit_depth) = match u16::from_le_bytes([data[28], data[29]]) {
+            bits @ 1..=8 => ("Palette", bits as u8),
+            16 => ("RGB", 5),
+            24 => ("RGB", 8),
+            32 => ("RGBA", 8),
+            _ => return,
+        };
+        report.color_type = Some(color_type.to_string());
+        report.bit_depth = Some(bit_depth);
+    }
+
+    fn find_all<'a>(data: &'a [u8], pattern: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        data.windows(pattern.len()).enumerate().filter(move |(_, window)| *window == pattern).map(|(position, _)| position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_jpeg_and_truncation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("photo.jpg");
+        image::RgbImage::from_pixel(64, 48, image::Rgb([120, 80, 40])).save(&path).unwrap();
+
+        let report = ImageInspector::inspect(&path).unwrap();
+        assert_eq!(report.format, DetectedFormat::Jpeg);
+        assert_eq!((report.width, report.height), (Some(64), Some(48)));
+        assert_eq!((report.color_type.as_deref(), report.bit_depth), (Some("RGB"), Some(8)));
+        assert!(report.valid && !report.truncated && report.problems.is_empty());
+
+        // 截去结尾的一半数据
+        let data = std::fs::read(&path).unwrap();
+        let truncated_path = temp_dir.path().join("truncated.jpg");
+        std::fs::write(&truncated_path, &data[..data.len() / 2]).unwrap();
+        let report = ImageInspector::inspect(&truncated_path).unwrap();
+        assert!(report.truncated && !report.valid);
+        assert_eq!(report.width, Some(64));
+    }
+
+    #[test]
+    fn test_inspect_png() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("graphic.png");
+        image::RgbaImage::new(10, 7).save(&path).unwrap();
+
+        let report = ImageInspector::inspect(&path).unwrap();
+        assert_eq!(report.format, DetectedFormat::Png);
+        assert_eq!((report.width, report.height), (Some(10), Some(7)));
+        assert_eq!(report.color_type.as_deref(), Some("RGBA"));
+        assert!(report.valid && !report.has_exif && !report.has_icc);
+    }
+
+    #[test]
+    fn test_disguised_heic_is_rejected() {
+        // 改了扩展名的HEIC：ftyp + meta中的ispe
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0, 0, 0, 24]);
+        data.extend_from_slice(b"ftypheic\0\0\0\0mif1heic");
+        let mut meta = b"\0\0\0\x14ispe\0\0\0\0".to_vec();
+        meta.extend_from_slice(&4032u32.to_be_bytes());
+        meta.extend_from_slice(&3024u32.to_be_bytes());
+        data.extend_from_slice(&(meta.len() as u32 + 12).to_be_bytes());
+        data.extend_from_slice(b"meta\0\0\0\0");
+        data.extend_from_slice(&meta);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("IMG_0001.jpg");
+        std::fs::write(&path, &data).unwrap();
+
+        let report = ImageInspector::inspect(&path).unwrap();
+        assert_eq!(report.format, DetectedFormat::Heic);
+        assert_eq!((report.width, report.height), (Some(4032), Some(3024)));
+        assert!(!report.extension_matches && !report.truncated && !report.valid);
+        assert_eq!(report.problems.len(), 2);
+    }
+}
@@ -1,12 +1,30 @@
 use crate::types::*;
+use crate::brand_logo::BrandLogoService;
+use crate::date_format::DateFormatter;
+use crate::derived_metrics::DerivedMetrics;
+use crate::exif_service::ExifService;
+use crate::film_roll::FilmRollService;
+use crate::focal_length::FocalLengthService;
+use crate::font_registry::FontRegistry;
+use crate::gps_format::GpsFormatter;
+use crate::image_inspector::ImageInspector;
+use crate::iptc_service::IptcService;
+use crate::metadata_override::MetadataOverrideService;
+use crate::metadata_writer::{MetadataWriter, SourceMetadata};
+use crate::raw_service::RawService;
+use crate::text_layout::{FontChain, TextBlock, TextLayout};
 use anyhow::{Context, Result};
 use image::{DynamicImage, Rgba, RgbaImage, GenericImageView, ImageFormat};
-use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
+use imageproc::drawing::draw_filled_rect_mut;
 use imageproc::rect::Rect;
-use rusttype::{Font, Scale};
+use rusttype::Scale;
 use std::path::Path;
 use std::time::Instant;
 
+/// 品牌Logo高度（字号的倍数）与Logo和文本之间的间距，与前端预览一致
+const LOGO_HEIGHT_RATIO: f32 = 1.2;
+const LOGO_GAP: u32 = 8;
+
 pub struct ImageProcessingService;
 
 impl ImageProcessingService {
@@ -18,6 +36,7 @@ impl ImageProcessingService {
         frame_settings: FrameSettings,
         output_path: &str,
         quality: u8,
+        preserve_metadata: bool,
     ) -> Result<ProcessedImageInfo> {
         let start_time = Instant::now();
         
@@ -26,9 +45,8 @@ impl ImageProcessingService {
             .with_context(|| format!("Failed to get metadata for {}", input_path))?
             .len();
 
-        // 加载图片
-        let mut img = image::open(input_path)
-            .with_context(|| format!("Failed to open image: {}", input_path))?;
+        // 加载图片（按EXIF方向摆正）
+        let mut img = Self::load_image(input_path)?;
 
         // 应用元数据叠加（先应用叠加，避免被相框遮挡）
         img = Self::apply_overlay(img, &metadata, &overlay_settings)?;
@@ -38,8 +56,15 @@ impl ImageProcessingService {
             img = Self::apply_frame(img, &frame_settings)?;
         }
 
+        // 读取需要保留的源元数据
+        let source_metadata = if preserve_metadata {
+            MetadataWriter::read_source_metadata(input_path)
+        } else {
+            SourceMetadata::default()
+        };
+
         // 保存处理后的图片
-        Self::save_image(&img, output_path, &overlay_settings.display_items, quality)?;
+        Self::save_image(&img, output_path, &overlay_settings.display_items, quality, &source_metadata)?;
 
         // 获取处理后文件大小
         let processed_size = std::fs::metadata(output_path)
@@ -67,6 +92,12 @@ impl ImageProcessingService {
         let mut successful = Vec::new();
         let mut failed = Vec::new();
 
+        // 胶片卷记录表对应到各个扫描文件
+        let roll_overrides = match &settings.film_roll {
+            Some(roll) => FilmRollService::frame_overrides(roll, &image_paths),
+            None => Default::default(),
+        };
+
         for input_path in image_paths.iter() {
             // 生成输出文件名
             let input_file = Path::new(input_path);
@@ -81,8 +112,43 @@ impl ImageProcessingService {
             
             let output_path = format!("{}/{}_processed.{}", output_dir, file_stem, extension);
 
-            // 提取EXIF数据
-            match crate::exif_service::ExifService::extract_metadata(input_path) {
+            // 按文件内容检查，格式不受支持或已截断的文件不再处理
+            let problem = match ImageInspector::inspect(input_path) {
+                Ok(report) if !report.valid => Some((report.problems.join("; "), ErrorType::InvalidFormat)),
+                Ok(_) => None,
+                Err(e) => Some((e.to_string(), ErrorType::FileNotFound)),
+            };
+            if let Some((error_message, error_type)) = problem {
+                println!("⚠️ Skipping {}: {}", input_path, error_message);
+                failed.push(ProcessingError { file_path: input_path.clone(), error_message, error_type });
+                continue;
+            }
+
+            // 图片自己的覆盖优先于整批覆盖
+            let metadata_override = match (&settings.metadata_override, settings.image_overrides.get(input_path)) {
+                (Some(batch), Some(image)) => Some(MetadataOverrideService::merge(batch, image)),
+                (batch, image) => batch.clone().or_else(|| image.cloned()),
+            };
+
+            // 记录表中这一帧的单元格无效时只跳过这个文件
+            let roll_override = match roll_overrides.get(input_path) {
+                Some(Err(e)) => {
+                    println!("⚠️ Skipping {}: {}", input_path, e);
+                    failed.push(ProcessingError {
+                        file_path: input_path.clone(),
+                        error_message: e.to_string(),
+                        error_type: ErrorType::InvalidFormat,
+                    });
+                    continue;
+                }
+                Some(Ok(roll_override)) => Some(roll_override),
+                None => None,
+            };
+
+            // 提取EXIF数据，胶片卷记录表在边车中保存的覆盖之前应用，最后应用本次的手动覆盖
+            let metadata = ExifService::extract_metadata_with_roll(input_path, roll_override)
+                .and_then(|metadata| MetadataOverrideService::applied(metadata, metadata_override.as_ref()));
+            match metadata {
                 Ok(metadata) => {
                     // 处理图片
                     match Self::process_image(
@@ -92,6 +158,7 @@ impl ImageProcessingService {
                         settings.frame_settings.clone(),
                         &output_path,
                         settings.quality,
+                        settings.preserve_metadata,
                     ).await {
                         Ok(result) => successful.push(result),
                         Err(e) => failed.push(ProcessingError {
@@ -124,9 +191,21 @@ impl ImageProcessingService {
         image_path: &str,
         settings: PreviewSettings,
     ) -> Result<Vec<u8>> {
-        // 加载图片
-        let img = image::open(image_path)
-            .with_context(|| format!("Failed to open image: {}", image_path))?;
+        // 提取EXIF数据并应用尚未保存的覆盖
+        let metadata = ExifService::extract_metadata(image_path)?;
+        let metadata = MetadataOverrideService::applied(metadata, settings.metadata_override.as_ref())?;
+
+        Self::render_preview(image_path, &metadata, &settings)
+    }
+
+    /// 使用给定的元数据生成预览图片
+    pub fn render_preview(
+        image_path: &str,
+        metadata: &PhotoMetadata,
+        settings: &PreviewSettings,
+    ) -> Result<Vec<u8>> {
+        // 加载图片（按EXIF方向摆正）
+        let img = Self::load_image(image_path)?;
 
         // 缩放到预览尺寸
         let preview_img = img.resize(
@@ -135,11 +214,8 @@ impl ImageProcessingService {
             image::imageops::FilterType::Lanczos3,
         );
 
-        // 提取EXIF数据
-        let metadata = crate::exif_service::ExifService::extract_metadata(image_path)?;
-
         // 应用叠加效果（先应用叠加，避免被相框遮挡）
-        let mut processed_img = Self::apply_overlay(preview_img, &metadata, &settings.overlay_settings)?;
+        let mut processed_img = Self::apply_overlay(preview_img, metadata, &settings.overlay_settings)?;
 
         // 应用相框效果（后应用相框，确保叠加内容不被遮挡）
         if settings.frame_settings.enabled {
@@ -154,6 +230,36 @@ impl ImageProcessingService {
         Ok(buffer)
     }
 
+    /// 加载图片并按照EXIF方向标签摆正，保证叠加层绘制在正确的方向上
+    /// RAW文件使用其内嵌的最大JPEG预览作为渲染源
+    fn load_image(input_path: &str) -> Result<DynamicImage> {
+        let img = if RawService::is_raw_file(input_path) {
+            let preview = RawService::extract_preview(input_path)?;
+            image::load_from_memory(&preview)
+                .with_context(|| format!("Failed to decode RAW preview: {}", input_path))?
+        } else {
+            image::open(input_path)
+                .with_context(|| format!("Failed to open image: {}", input_path))?
+        };
+
+        let orientation = ExifService::read_orientation(input_path);
+        Ok(Self::apply_orientation(img, orientation))
+    }
+
+    /// 按EXIF方向标签（1-8）变换图片
+    fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+        match orientation {
+            2 => img.fliph(),
+            3 => img.rotate180(),
+            4 => img.flipv(),
+            5 => img.rotate90().fliph(),
+            6 => img.rotate90(),
+            7 => img.rotate270().fliph(),
+            8 => img.rotate270(),
+            _ => img,
+        }
+    }
+
     /// 应用相框效果
     fn apply_frame(img: DynamicImage, frame_settings: &FrameSettings) -> Result<DynamicImage> {
         let (width, height) = img.dimensions();
@@ -208,121 +314,128 @@ impl ImageProcessingService {
     ) -> Result<DynamicImage> {
         let mut img_rgba = img.to_rgba8();
         
-        // 生成要显示的文本
-        let overlay_text = Self::generate_overlay_text(metadata, &overlay_settings.display_items);
-        
-        if overlay_text.is_empty() {
+        // 自定义布局：每个元素单独定位与设置样式
+        if let (LayoutMode::Custom, Some(layout)) = (overlay_settings.layout_mode, &overlay_settings.custom_layout) {
+            Self::apply_custom_layout(&mut img_rgba, metadata, overlay_settings, layout)?;
             return Ok(DynamicImage::ImageRgba8(img_rgba));
         }
         
+        // 生成要显示的文本
+        let overlay_lines = Self::overlay_lines(metadata, overlay_settings);
+        let scale = Scale::uniform(overlay_settings.font.size);
+        let font_color = Self::parse_color(&overlay_settings.font.color, 1.0)?;
+        let padding = overlay_settings.background.padding as u32;
+        
         // 尝试加载字体
-        match Self::load_font() {
-            Ok(font) => {
-                // 字体加载成功，进行文本渲染
-                let scale = Scale::uniform(overlay_settings.font.size);
-                let font_color = Self::parse_color(&overlay_settings.font.color, 1.0)?;
-                
-                // 计算文本尺寸
-                let text_width = Self::calculate_text_width(&font, scale, &overlay_text);
-                let text_height = overlay_settings.font.size as u32;
-                
-                // 计算叠加位置
-                let (x, y) = Self::calculate_overlay_position(
-                    &overlay_settings.position,
-                    img_rgba.width(),
-                    img_rgba.height(),
-                    text_width,
-                    text_height,
-                    overlay_settings.background.padding as u32,
-                );
-                
-                // 绘制背景
-                if overlay_settings.background.opacity > 0.0 {
-                    let bg_color = Self::parse_color(
-                        &overlay_settings.background.color,
-                        overlay_settings.background.opacity,
-                    )?;
-                    
-                    let bg_rect = Rect::at(x as i32, y as i32).of_size(
-                        text_width + 2 * overlay_settings.background.padding as u32,
-                        text_height + 2 * overlay_settings.background.padding as u32,
-                    );
-                    
-                    draw_filled_rect_mut(&mut img_rgba, bg_rect, bg_color);
+        let font = if overlay_lines.is_empty() {
+            None
+        } else {
+            match Self::load_font(&overlay_settings.font) {
+                Ok(font) => Some(font),
+                Err(e) => {
+                    // 字体加载失败，记录错误但不中断处理
+                    println!("⚠️ Font loading failed: {}, continuing without text overlay", e);
+                    println!("📝 Text would be: {}", overlay_lines.join(" | "));
+                    None
                 }
-                
-                // 绘制文本
-                draw_text_mut(
-                    &mut img_rgba,
-                    font_color,
-                    (x + overlay_settings.background.padding as u32) as i32,
-                    (y + overlay_settings.background.padding as u32) as i32,
-                    scale,
-                    &font,
-                    &overlay_text,
-                );
-                
-                println!("✅ Successfully rendered text: {}", overlay_text);
             }
-            Err(e) => {
-                // 字体加载失败，记录错误但不中断处理
-                println!("⚠️ Font loading failed: {}, continuing without text overlay", e);
-                println!("📝 Text would be: {}", overlay_text);
+        };
+        
+        // 逐行测量并排版，最大宽度扣除两侧内边距
+        let block = match &font {
+            Some(font) => {
+                let max_width = overlay_settings
+                    .text_layout
+                    .max_width_percent
+                    .map(|percent| percent / 100.0 * img_rgba.width() as f32 - 2.0 * padding as f32);
+                TextLayout::layout(font, scale, &overlay_lines, &overlay_settings.text_layout, max_width)
             }
+            None => TextBlock::default(),
+        };
+        
+        let logo = if overlay_settings.display_items.brand_logo {
+            Self::render_logo(metadata, overlay_settings, overlay_settings.font.size)?
+        } else {
+            None
+        };
+        
+        if block.lines.is_empty() && logo.is_none() {
+            return Ok(DynamicImage::ImageRgba8(img_rgba));
         }
         
-        Ok(DynamicImage::ImageRgba8(img_rgba))
-    }
-    
-    /// 加载字体文件（带缓存优化）
-    fn load_font() -> Result<Font<'static>> {
-        use std::sync::OnceLock;
-        static FONT_CACHE: OnceLock<Option<Vec<u8>>> = OnceLock::new();
-        
-        // 使用静态缓存避免重复加载字体
-        let font_data = FONT_CACHE.get_or_init(|| {
-            // 方法1: 尝试加载内嵌字体
-            let embedded_font = include_bytes!("../assets/fonts/DejaVuSans.ttf");
-            if Font::try_from_bytes(embedded_font as &[u8]).is_some() {
-                return Some(embedded_font.to_vec());
-            }
-            
-            println!("DejaVu embedded font failed, trying system fonts...");
-            
-            // 方法2: 使用系统字体路径（Linux）
-            let system_font_paths = vec![
-                "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
-                "/usr/share/fonts/TTF/DejaVuSans.ttf",
-                "/System/Library/Fonts/Arial.ttf", // macOS
-                "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
-            ];
+        // Logo与文本块组合后的尺寸
+        let logo_size = logo.as_ref().map(|logo| logo.dimensions());
+        let ((content_width, content_height), logo_offset, text_offset) = Self::arrange_logo(
+            overlay_settings.logo.placement,
+            overlay_settings.text_layout.alignment,
+            logo_size,
+            &block,
+        );
+        
+        // 计算叠加位置
+        let (x, y) = Self::calculate_overlay_position(
+            &overlay_settings.position,
+            img_rgba.width(),
+            img_rgba.height(),
+            content_width,
+            content_height,
+            padding,
+        );
+        
+        // 绘制背景，尺寸由测量得到的文本块与Logo决定
+        if overlay_settings.background.opacity > 0.0 {
+            let bg_color = Self::parse_color(
+                &overlay_settings.background.color,
+                overlay_settings.background.opacity,
+            )?;
             
-            for font_path in system_font_paths {
-                if let Ok(font_data) = std::fs::read(font_path) {
-                    if Font::try_from_bytes(&font_data).is_some() {
-                        println!("Successfully cached system font: {}", font_path);
-                        return Some(font_data);
-                    }
-                }
-            }
+            let bg_rect = Rect::at(x as i32, y as i32).of_size(
+                content_width + 2 * padding,
+                content_height + 2 * padding,
+            );
             
-            None
-        });
+            draw_filled_rect_mut(&mut img_rgba, bg_rect, bg_color);
+        }
         
-        match font_data {
-            Some(data) => {
-                Font::try_from_bytes(data)
-                    .ok_or_else(|| anyhow::anyhow!("Failed to parse cached font data"))
-            }
-            None => Err(anyhow::anyhow!("No font available"))
+        if let Some(logo) = &logo {
+            image::imageops::overlay(
+                &mut img_rgba,
+                logo,
+                (x + padding + logo_offset.0) as i64,
+                (y + padding + logo_offset.1) as i64,
+            );
         }
+        
+        // 逐行绘制文本
+        if let Some(font) = &font {
+            TextLayout::draw(
+                &mut img_rgba,
+                &block,
+                (x + padding + text_offset.0) as i32,
+                (y + padding + text_offset.1) as i32,
+                font_color,
+                font,
+                scale,
+            );
+            
+            println!("✅ Successfully rendered {} lines of text", block.lines.len());
+        }
+        
+        Ok(DynamicImage::ImageRgba8(img_rgba))
+    }
+    
+    /// 按字体设置加载字体（名称、字重、样式）及其逐字回退链，解析后的字体由注册表缓存
+    fn load_font(font_settings: &FontSettings) -> Result<FontChain> {
+        FontRegistry::shared().resolve_chain(font_settings)
     }
 
-    /// 生成叠加文本
-    fn generate_overlay_text(metadata: &PhotoMetadata, display_items: &DisplayItems) -> String {
+    /// 生成叠加项目，按显示顺序排列
+    fn overlay_lines(metadata: &PhotoMetadata, overlay_settings: &OverlaySettings) -> Vec<String> {
+        let display_items = &overlay_settings.display_items;
         let mut lines = Vec::new();
         
         // 按照与前端相同的优先级顺序排列：brand, model, aperture, shutterSpeed, iso, timestamp, location
+        // 后端新增的扩展项目插入在相近的位置
         
         // 1. 相机品牌
         if display_items.brand {
@@ -338,40 +451,370 @@ impl ImageProcessingService {
             }
         }
         
-        // 3. 光圈
+        // 3. 镜头
+        if display_items.lens {
+            if let Some(lens) = &metadata.camera.lens_model {
+                lines.push(lens.clone());
+            }
+        }
+        
+        if display_items.focal_length {
+            if let Some(focal_length) = FocalLengthService::display_text(&metadata.settings, overlay_settings.focal_length_format) {
+                lines.push(focal_length);
+            }
+        }
+        
+        // 4. 光圈
         if display_items.aperture {
             if let Some(aperture) = &metadata.settings.aperture {
                 lines.push(aperture.clone());
             }
         }
         
-        // 4. 快门速度
+        // 5. 快门速度
         if display_items.shutter_speed {
             if let Some(shutter) = &metadata.settings.shutter_speed {
                 lines.push(shutter.clone());
             }
         }
         
-        // 5. ISO
+        // 6. ISO
         if display_items.iso {
             if let Some(iso) = metadata.settings.iso {
                 lines.push(format!("ISO {}", iso));
             }
         }
         
-        // 6. 时间戳
+        if display_items.ev {
+            if let Some(ev100) = metadata.settings.ev100 {
+                lines.push(DerivedMetrics::ev_text(ev100));
+            }
+        }
+        
+        if display_items.hyperfocal {
+            if let Some(hyperfocal) = metadata.settings.hyperfocal_distance {
+                lines.push(DerivedMetrics::hyperfocal_text(hyperfocal));
+            }
+        }
+        
+        if display_items.depth_of_field {
+            if let Some(near) = metadata.settings.dof_near {
+                lines.push(DerivedMetrics::depth_of_field_text(near, metadata.settings.dof_far));
+            }
+        }
+        
+        // 7. 曝光补偿
+        if display_items.exposure_bias {
+            if let Some(bias) = &metadata.settings.exposure_bias {
+                lines.push(bias.clone());
+            }
+        }
+        
+        // 8. 曝光程序、测光模式、白平衡、闪光灯
+        if display_items.exposure_program {
+            if let Some(program) = &metadata.settings.exposure_program {
+                lines.push(program.clone());
+            }
+        }
+        
+        if display_items.metering_mode {
+            if let Some(metering) = &metadata.settings.metering_mode {
+                lines.push(metering.clone());
+            }
+        }
+        
+        if display_items.white_balance {
+            if let Some(wb) = &metadata.settings.white_balance {
+                lines.push(format!("WB {}", wb));
+            }
+        }
+        
+        if display_items.flash {
+            if let Some(flash) = &metadata.settings.flash {
+                lines.push(flash.clone());
+            }
+        }
+        
+        if display_items.film_simulation {
+            if let Some(film_simulation) = &metadata.settings.film_simulation {
+                lines.push(film_simulation.clone());
+            }
+        }
+        
+        if display_items.film_stock {
+            if let Some(stock) = metadata.film.as_ref().and_then(|film| film.stock.as_ref()) {
+                lines.push(stock.clone());
+            }
+        }
+        
+        // 9. 时间戳
         if display_items.timestamp {
-            if let Some(timestamp) = &metadata.timestamp {
-                lines.push(timestamp.clone());
+            if let Some(timestamp) = Self::timestamp_text(metadata, &overlay_settings.date_format) {
+                lines.push(timestamp);
+            }
+        }
+        
+        // 10. 位置信息（如果有的话）
+        if let Some(location) = &metadata.location {
+            let location_format = &overlay_settings.location_format;
+            if display_items.location {
+                lines.push(GpsFormatter::location_text(location, location_format));
+            }
+            
+            if display_items.altitude {
+                if let Some(altitude) = location.altitude {
+                    lines.push(GpsFormatter::altitude_text(altitude, location_format.altitude_unit));
+                }
+            }
+            
+            if display_items.image_direction {
+                if let Some(direction) = location.image_direction {
+                    lines.push(GpsFormatter::direction_text(direction));
+                }
+            }
+        }
+        
+        // 11. 作者、版权与软件
+        if display_items.artist {
+            if let Some(artist) = &metadata.artist {
+                lines.push(artist.clone());
             }
         }
         
-        // 7. 位置信息（如果有的话）
-        // TODO: 添加位置信息支持
+        if display_items.copyright {
+            if let Some(copyright) = &metadata.copyright {
+                lines.push(copyright.clone());
+            }
+        }
+        
+        if display_items.software {
+            if let Some(software) = &metadata.software {
+                lines.push(software.clone());
+            }
+        }
+        
+        // 12. IPTC标题、说明、关键词、署名、地点与署名行
+        if let Some(iptc) = &metadata.iptc {
+            if display_items.headline {
+                if let Some(headline) = &iptc.headline {
+                    lines.push(headline.clone());
+                }
+            }
+            
+            if display_items.caption {
+                if let Some(caption) = &iptc.caption {
+                    lines.push(caption.clone());
+                }
+            }
+            
+            if display_items.keywords && !iptc.keywords.is_empty() {
+                lines.push(iptc.keywords.join(", "));
+            }
+            
+            if display_items.byline {
+                if let Some(byline) = &iptc.byline {
+                    lines.push(byline.clone());
+                }
+            }
+            
+            if display_items.iptc_location {
+                if let Some(location) = IptcService::location_text(iptc) {
+                    lines.push(location);
+                }
+            }
+        }
         
-        // 注意：焦距信息暂时不在前端的优先级列表中，所以这里也不包含
+        if display_items.credit_line {
+            if let Some(credit) = IptcService::credit_line(metadata) {
+                lines.push(credit);
+            }
+        }
+
         
-        lines.join("\n")
+        lines
+    }
+
+    /// 拍摄时间文本，未配置日期格式时显示原始EXIF时间字符串
+    fn timestamp_text(metadata: &PhotoMetadata, date_format: &DateFormatSettings) -> Option<String> {
+        match &metadata.capture_time {
+            Some(capture_time) if DateFormatter::is_configured(date_format) => {
+                Some(DateFormatter::format(capture_time, date_format))
+            }
+            _ => metadata.timestamp.clone(),
+        }
+    }
+
+    /// 自定义布局中单个元素的文本，格式与预设布局中的对应行一致
+    fn element_text(
+        metadata: &PhotoMetadata,
+        element_type: MetadataElementType,
+        overlay_settings: &OverlaySettings,
+    ) -> Option<String> {
+        match element_type {
+            MetadataElementType::Brand => metadata.camera.make.clone(),
+            MetadataElementType::Model => metadata.camera.model.clone(),
+            MetadataElementType::Aperture => metadata.settings.aperture.clone(),
+            MetadataElementType::ShutterSpeed => metadata.settings.shutter_speed.clone(),
+            MetadataElementType::Iso => metadata.settings.iso.map(|iso| format!("ISO {}", iso)),
+            MetadataElementType::Timestamp => Self::timestamp_text(metadata, &overlay_settings.date_format),
+            MetadataElementType::Location => metadata
+                .location
+                .as_ref()
+                .map(|location| GpsFormatter::location_text(location, &overlay_settings.location_format)),
+            // 品牌Logo没有文本，由 apply_custom_layout 单独绘制
+            MetadataElementType::BrandLogo => None,
+        }
+    }
+
+    /// 按自定义布局逐个绘制元素
+    /// 元素位置为文本左上角（百分比），背景向四周扩展内边距，与前端预览一致
+    fn apply_custom_layout(
+        img_rgba: &mut RgbaImage,
+        metadata: &PhotoMetadata,
+        overlay_settings: &OverlaySettings,
+        layout: &CustomLayoutSettings,
+    ) -> Result<()> {
+        let font = match Self::load_font(&overlay_settings.font) {
+            Ok(font) => font,
+            Err(e) => {
+                println!("⚠️ Font loading failed: {}, continuing without text overlay", e);
+                return Ok(());
+            }
+        };
+        let (img_width, img_height) = img_rgba.dimensions();
+        let background = &overlay_settings.background;
+
+        for element in layout.elements.iter().filter(|element| element.visible) {
+            let style = element.style.clone().unwrap_or_default();
+            let font_size = style.font_size.filter(|size| *size > 0.0).unwrap_or(overlay_settings.font.size);
+            // 负的内边距按0处理
+            let padding = style.padding.unwrap_or(background.padding).max(0.0);
+
+            let (x_percent, y_percent) = Self::snapped_position(element.position, layout);
+            let x = (x_percent / 100.0 * img_width as f32).round() as i32;
+            let y = (y_percent / 100.0 * img_height as f32).round() as i32;
+
+            // Logo直接绘制在元素位置，不加背景（与前端预览一致）
+            if element.element_type == MetadataElementType::BrandLogo {
+                if let Some(logo) = Self::render_logo(metadata, overlay_settings, font_size)? {
+                    image::imageops::overlay(img_rgba, &logo, x as i64, y as i64);
+                }
+                continue;
+            }
+            let Some(text) = Self::element_text(metadata, element.element_type, overlay_settings) else {
+                continue;
+            };
+
+            let scale = Scale::uniform(font_size);
+            let text_width = TextLayout::measure(&font, scale, &text).ceil() as u32;
+            let text_height = font_size.ceil() as u32;
+
+            if background.opacity > 0.0 {
+                let bg_color = style.background_color.as_deref().unwrap_or(&background.color);
+                let bg_color = Self::parse_color(bg_color, background.opacity)?;
+                let padding = padding.round() as u32;
+                let (bg_width, bg_height) = (text_width + 2 * padding, text_height + 2 * padding);
+                // 空文本且没有内边距时不绘制背景（imageproc不接受零尺寸矩形）
+                if bg_width > 0 && bg_height > 0 {
+                    let bg_rect = Rect::at(x - padding as i32, y - padding as i32).of_size(bg_width, bg_height);
+                    draw_filled_rect_mut(img_rgba, bg_rect, bg_color);
+                }
+            }
+
+            let font_color = Self::parse_color(style.color.as_deref().unwrap_or(&overlay_settings.font.color), 1.0)?;
+            TextLayout::draw_line(img_rgba, font_color, x, y, scale, &font, &text);
+        }
+
+        println!("✅ Rendered custom layout with {} elements", layout.elements.len());
+        Ok(())
+    }
+
+    /// 开启网格吸附时，将元素位置对齐到最近的网格线（与布局编辑器的吸附规则相同）
+    fn snapped_position(position: ElementPosition, layout: &CustomLayoutSettings) -> (f32, f32) {
+        if !layout.snap_to_grid || layout.grid_size <= 0.0 {
+            return (position.x, position.y);
+        }
+        let snap = |value: f32| (value / layout.grid_size).round() * layout.grid_size;
+        (snap(position.x), snap(position.y))
+    }
+
+    /// 渲染相机品牌的Logo，高度为字号的1.2倍（与前端预览一致）
+    fn render_logo(
+        metadata: &PhotoMetadata,
+        overlay_settings: &OverlaySettings,
+        font_size: f32,
+    ) -> Result<Option<RgbaImage>> {
+        let Some(make) = &metadata.camera.make else {
+            return Ok(None);
+        };
+        let variant = Self::logo_variant(overlay_settings)?;
+        let height = (font_size * LOGO_HEIGHT_RATIO).round() as u32;
+        Ok(BrandLogoService::render(make, variant, height))
+    }
+
+    /// 解析Logo底色：Auto 时按叠加背景的明暗选择，透明背景时浅色文字说明照片较暗
+    fn logo_variant(overlay_settings: &OverlaySettings) -> Result<LogoVariant> {
+        if overlay_settings.logo.variant != LogoVariant::Auto {
+            return Ok(overlay_settings.logo.variant);
+        }
+        let background = &overlay_settings.background;
+        let dark = if background.opacity > 0.0 {
+            Self::is_dark(Self::parse_color(&background.color, 1.0)?)
+        } else {
+            !Self::is_dark(Self::parse_color(&overlay_settings.font.color, 1.0)?)
+        };
+        Ok(if dark { LogoVariant::Dark } else { LogoVariant::Light })
+    }
+
+    fn is_dark(color: Rgba<u8>) -> bool {
+        let [r, g, b, _] = color.0;
+        let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        luminance < 128.0
+    }
+
+    /// 组合Logo与文本块，返回总尺寸以及Logo和文本块各自的偏移
+    /// 上方放置时Logo居中、文本块按对齐方式放置；左右放置时两者垂直居中
+    fn arrange_logo(
+        placement: LogoPlacement,
+        alignment: TextAlignment,
+        logo_size: Option<(u32, u32)>,
+        block: &TextBlock,
+    ) -> ((u32, u32), (u32, u32), (u32, u32)) {
+        let Some((logo_width, logo_height)) = logo_size else {
+            return ((block.width, block.height), (0, 0), (0, 0));
+        };
+        if block.lines.is_empty() {
+            return ((logo_width, logo_height), (0, 0), (0, 0));
+        }
+
+        match placement {
+            LogoPlacement::Above => {
+                let width = logo_width.max(block.width);
+                let text_x = match alignment {
+                    TextAlignment::Left => 0,
+                    TextAlignment::Center => (width - block.width) / 2,
+                    TextAlignment::Right => width - block.width,
+                };
+                (
+                    (width, logo_height + LOGO_GAP + block.height),
+                    ((width - logo_width) / 2, 0),
+                    (text_x, logo_height + LOGO_GAP),
+                )
+            }
+            LogoPlacement::Left | LogoPlacement::Right => {
+                let height = logo_height.max(block.height);
+                let (logo_x, text_x) = if placement == LogoPlacement::Left {
+                    (0, logo_width + LOGO_GAP)
+                } else {
+                    (block.width + LOGO_GAP, 0)
+                };
+                (
+                    (logo_width + LOGO_GAP + block.width, height),
+                    (logo_x, (height - logo_height) / 2),
+                    (text_x, (height - block.height) / 2),
+                )
+            }
+        }
     }
 
     /// 计算叠加位置
@@ -383,27 +826,14 @@ impl ImageProcessingService {
         text_height: u32,
         padding: u32,
     ) -> (u32, u32) {
+        // 背景框包含两侧内边距，与图片边缘再留出一个内边距的距离，放不下时贴边
+        let right = img_width.saturating_sub(text_width + 3 * padding);
+        let bottom = img_height.saturating_sub(text_height + 3 * padding);
         match position {
             OverlayPosition::TopLeft => (padding, padding),
-            OverlayPosition::TopRight => (img_width - text_width - padding, padding),
-            OverlayPosition::BottomLeft => (padding, img_height - text_height - padding),
-            OverlayPosition::BottomRight => (
-                img_width - text_width - padding,
-                img_height - text_height - padding,
-            ),
-        }
-    }
-
-    /// 计算文本宽度
-    fn calculate_text_width(font: &Font, scale: Scale, text: &str) -> u32 {
-        let v_metrics = font.v_metrics(scale);
-        let glyphs: Vec<_> = font.layout(text, scale, rusttype::point(0.0, v_metrics.ascent)).collect();
-        
-        if let (Some(first), Some(last)) = (glyphs.first(), glyphs.last()) {
-            let width = last.position().x + last.unpositioned().h_metrics().advance_width - first.position().x;
-            width.ceil() as u32
-        } else {
-            0
+            OverlayPosition::TopRight => (right, padding),
+            OverlayPosition::BottomLeft => (padding, bottom),
+            OverlayPosition::BottomRight => (right, bottom),
         }
     }
 
@@ -469,6 +899,7 @@ impl ImageProcessingService {
         output_path: &str,
         _display_items: &DisplayItems,
         quality: u8,
+        source_metadata: &SourceMetadata,
     ) -> Result<()> {
         let output_path = Path::new(output_path);
         
@@ -480,17 +911,21 @@ impl ImageProcessingService {
         };
         
         // 对于JPEG格式，需要特殊处理质量设置
+        let mut buffer = Vec::new();
         if format == ImageFormat::Jpeg {
-            let mut buffer = Vec::new();
             img.write_to(&mut std::io::Cursor::new(&mut buffer), image::ImageOutputFormat::Jpeg(quality))
                 .with_context(|| "Failed to encode JPEG image")?;
-            std::fs::write(output_path, buffer)
-                .with_context(|| format!("Failed to write image to {}", output_path.display()))?;
         } else {
-            img.save_with_format(output_path, format)
-                .with_context(|| format!("Failed to save image to {}", output_path.display()))?;
+            img.write_to(&mut std::io::Cursor::new(&mut buffer), image::ImageOutputFormat::Png)
+                .with_context(|| "Failed to encode PNG image")?;
         }
         
+        // 写入元数据：图片已按方向摆正，EXIF中的方向统一为1
+        let buffer = MetadataWriter::write_metadata(buffer, format, source_metadata, img.width(), img.height())?;
+        
+        std::fs::write(output_path, buffer)
+            .with_context(|| format!("Failed to write image to {}", output_path.display()))?;
+        
         Ok(())
     }
 
@@ -526,6 +961,167 @@ impl ImageProcessingService {
         }
         Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一个2x2的图片，四个像素的红色通道依次为1、2、3、4
+    fn sample_image() -> DynamicImage {
+        let mut img = RgbaImage::new(2, 2);
+        img.put_pixel(0, 0, Rgba([1, 0, 0, 255]));
+        img.put_pixel(1, 0, Rgba([2, 0, 0, 255]));
+        img.put_pixel(0, 1, Rgba([3, 0, 0, 255]));
+        img.put_pixel(1, 1, Rgba([4, 0, 0, 255]));
+        DynamicImage::ImageRgba8(img)
+    }
 
+    fn pixels(img: &DynamicImage) -> Vec<u8> {
+        img.to_rgba8().pixels().map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn test_apply_orientation() {
+        let cases: [(u16, [u8; 4]); 8] = [
+            (1, [1, 2, 3, 4]),
+            (2, [2, 1, 4, 3]),
+            (3, [4, 3, 2, 1]),
+            (4, [3, 4, 1, 2]),
+            (5, [1, 3, 2, 4]),
+            (6, [3, 1, 4, 2]),
+            (7, [4, 2, 3, 1]),
+            (8, [2, 4, 1, 3]),
+        ];
+
+        for (orientation, expected) in cases {
+            let result = ImageProcessingService::apply_orientation(sample_image(), orientation);
+            assert_eq!(pixels(&result), expected.to_vec(), "orientation {}", orientation);
+        }
+
+        // 非标准取值保持原样
+        let result = ImageProcessingService::apply_orientation(sample_image(), 0);
+        assert_eq!(pixels(&result), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_overlay_location_lines() {
+        let overlay_settings: OverlaySettings = serde_json::from_value(serde_json::json!({
+            "position": "BottomRight",
+            "font": { "family": "Arial", "size": 16.0, "color": "#FFFFFF", "weight": "Normal" },
+            "background": { "color": "#000000", "opacity": 0.8, "padding": 10.0, "border_radius": 5.0 },
+            "display_items": {
+                "brand": false, "model": false, "aperture": false, "shutter_speed": false,
+                "iso": false, "timestamp": false, "location": true, "brand_logo": false, "altitude": true
+            },
+            "location_format": { "coordinate_format": "Dms", "altitude_unit": "Meters" }
+        }))
+        .unwrap();
+
+        let mut metadata = PhotoMetadata {
+            location: Some(LocationInfo {
+                latitude: 35.689_444,
+                longitude: 139.691_667,
+                altitude: Some(40.2),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let lines = ImageProcessingService::overlay_lines(&metadata, &overlay_settings);
+        assert_eq!(lines, ["35°41'22\"N, 139°41'30\"E", "40 m"]);
+
+        // 有地址时优先显示地址
+        metadata.location.as_mut().unwrap().address = Some("Shinjuku, Tokyo".to_string());
+        let lines = ImageProcessingService::overlay_lines(&metadata, &overlay_settings);
+        assert_eq!(lines, ["Shinjuku, Tokyo", "40 m"]);
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_custom_layout_elements() {
+        let overlay_settings: OverlaySettings = serde_json::from_value(serde_json::json!({
+            "position": "BottomRight",
+            "font": { "family": "Arial", "size": 16.0, "color": "#FFFFFF", "weight": "Normal" },
+            "background": { "color": "#000000", "opacity": 1.0, "padding": 4.0, "border_radius": 0.0 },
+            "display_items": {
+                "brand": true, "model": true, "aperture": false, "shutter_speed": false,
+                "iso": false, "timestamp": false, "location": false, "brand_logo": false
+            },
+            "layout_mode": "Custom",
+            "custom_layout": {
+                "elements": [
+                    { "id": "iso", "type": "Iso", "position": { "x": 12.0, "y": 9.0 }, "visible": true,
+                      "style": { "font_size": 20.0, "color": "#FF0000", "background_color": "#0000FF", "padding": 2.0 } },
+                    { "id": "model", "type": "Model", "position": { "x": 60.0, "y": 60.0 }, "visible": false },
+                    { "id": "brand", "type": "Brand", "position": { "x": 80.0, "y": 10.0 }, "visible": true,
+                      "style": { "padding": 0.0 } },
+                    { "id": "aperture", "type": "Aperture", "position": { "x": 10.0, "y": 80.0 }, "visible": true,
+                      "style": { "padding": -5.0 } }
+                ],
+                "grid_enabled": true,
+                "grid_size": 10.0,
+                "snap_to_grid": true
+            }
+        }))
+        .unwrap();
+
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.model = Some("X-T5".to_string());
+        metadata.settings.iso = Some(400);
+        // 空品牌文本（宽度为0）与负内边距都不应导致绘制背景时出错
+        metadata.camera.make = Some(String::new());
+        metadata.settings.aperture = Some("f/2.8".to_string());
+
+        let layout = overlay_settings.custom_layout.as_ref().unwrap();
+        assert_eq!(ImageProcessingService::snapped_position(layout.elements[0].position, layout), (10.0, 10.0));
+        assert_eq!(
+            ImageProcessingService::element_text(&metadata, MetadataElementType::Iso, &overlay_settings).as_deref(),
+            Some("ISO 400")
+        );
+
+        // ISO元素吸附到(20, 20)，背景从(18, 18)开始；隐藏的型号元素不绘制
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 200, Rgba([128, 128, 128, 255])));
+        let result = ImageProcessingService::apply_overlay(img, &metadata, &overlay_settings).unwrap().to_rgba8();
+        assert_eq!(*result.get_pixel(18, 18), Rgba([0, 0, 255, 255]));
+        assert_eq!(*result.get_pixel(17, 17), Rgba([128, 128, 128, 255]));
+        assert_eq!(*result.get_pixel(150, 150), Rgba([128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn test_brand_logo_next_to_text() {
+        let line = crate::text_layout::TextLine { text: "X-T5".to_string(), x: 0, y: 0, width: 100 };
+        let block = TextBlock { lines: vec![line], width: 100, height: 30 };
+
+        // 上方：Logo居中，文本块在Logo下方留出间距
+        let (size, logo, text) =
+            ImageProcessingService::arrange_logo(LogoPlacement::Above, TextAlignment::Left, Some((60, 20)), &block);
+        assert_eq!((size, logo, text), ((100, 58), (20, 0), (0, 28)));
+
+        // 左侧：两者垂直居中
+        let (size, logo, text) =
+            ImageProcessingService::arrange_logo(LogoPlacement::Left, TextAlignment::Left, Some((60, 20)), &block);
+        assert_eq!((size, logo, text), ((168, 30), (0, 5), (68, 0)));
+
+        // 只显示Logo时，背景按Logo尺寸绘制在右下角，深色背景自动选用深色底Logo
+        let overlay_settings: OverlaySettings = serde_json::from_value(serde_json::json!({
+            "position": "BottomRight",
+            "font": { "family": "Arial", "size": 20.0, "color": "#FFFFFF", "weight": "Normal" },
+            "background": { "color": "#000000", "opacity": 1.0, "padding": 10.0, "border_radius": 0.0 },
+            "display_items": {
+                "brand": false, "model": false, "aperture": false, "shutter_speed": false,
+                "iso": false, "timestamp": false, "location": false, "brand_logo": true
+            }
+        }))
+        .unwrap();
+        assert_eq!(ImageProcessingService::logo_variant(&overlay_settings).unwrap(), LogoVariant::Dark);
+
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("Canon".to_string());
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(300, 200, Rgba([128, 128, 128, 255])));
+        let result = ImageProcessingService::apply_overlay(img, &metadata, &overlay_settings).unwrap().to_rgba8();
+
+        // Logo为72x24，背景框 (300 - 72 - 30, 200 - 24 - 30) = (198, 146) 起，Logo从 (208, 156) 起
+        assert_eq!(*result.get_pixel(198, 146), Rgba([0, 0, 0, 255]));
+        assert_eq!(*result.get_pixel(197, 146), Rgba([128, 128, 128, 255]));
+        assert_eq!(*result.get_pixel(212, 160), Rgba([0x1a, 0x1a, 0x1a, 255]));
+    }
+}
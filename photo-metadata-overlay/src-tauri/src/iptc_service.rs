@@ -0,0 +1,247 @@
+use crate::types::*;
+
+/// JPEG APP13段的Photoshop标识
+pub const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+
+/// Photoshop图像资源中存放IPTC-IIM的资源ID
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+/// IIM中表示UTF-8字符集的转义序列（记录1:90）
+const UTF8_CHARSET: &[u8] = b"\x1B%G";
+
+/// IPTC服务
+/// 解析新闻与图片社工作流使用的IPTC-IIM数据，并生成署名行
+pub struct IptcService;
+
+impl IptcService {
+    /// 从Photoshop图像资源（8BIM块序列）中取出IPTC-IIM数据
+    pub fn photoshop_iptc(resources: &[u8]) -> Option<&[u8]> {
+        let mut position = 0;
+
+        while position + 12 <= resources.len() {
+            if &resources[position..position + 4] != b"8BIM" {
+                return None;
+            }
+            let id = u16::from_be_bytes([resources[position + 4], resources[position + 5]]);
+
+            // 资源名为Pascal字符串，连同长度字节补齐到偶数
+            let name_length = resources[position + 6] as usize;
+            let name_size = (name_length + 2) & !1;
+            let size_position = position + 6 + name_size;
+            let size = u32::from_be_bytes(resources.get(size_position..size_position + 4)?.try_into().ok()?) as usize;
+            let data_position = size_position + 4;
+            let data = resources.get(data_position..data_position + size)?;
+
+            if id == IPTC_RESOURCE_ID {
+                return Some(data);
+            }
+            position = data_position + ((size + 1) & !1);
+        }
+
+        None
+    }
+
+    /// 生成JPEG APP13段的内容（不含标记与长度）
+    pub fn photoshop_segment(iim: &[u8]) -> Vec<u8> {
+        let mut segment = PHOTOSHOP_HEADER.to_vec();
+        segment.extend_from_slice(b"8BIM");
+        segment.extend_from_slice(&IPTC_RESOURCE_ID.to_be_bytes());
+        segment.extend_from_slice(&[0, 0]); // 空资源名
+        segment.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        segment.extend_from_slice(iim);
+        if iim.len() % 2 == 1 {
+            segment.push(0);
+        }
+        segment
+    }
+
+    /// 解析IPTC-IIM数据集
+    pub fn parse_iim(data: &[u8]) -> IptcInfo {
+        let mut datasets: Vec<(u8, u8, &[u8])> = Vec::new();
+        let mut position = 0;
+
+        while position + 5 <= data.len() && data[position] == 0x1C {
+            let record = data[position + 1];
+            let dataset = data[position + 2];
+            let mut length = u16::from_be_bytes([data[position + 3], data[position + 4]]) as usize;
+            position += 5;
+
+            // 最高位表示扩展长度，低位为长度字段本身的字节数
+            if length & 0x8000 != 0 {
+                let length_size = length & 0x7FFF;
+                let Some(bytes) = data.get(position..position + length_size).filter(|_| length_size <= 4) else {
+                    break;
+                };
+                length = bytes.iter().fold(0, |value, &byte| (value << 8) | byte as usize);
+                position += length_size;
+            }
+
+            let Some(value) = data.get(position..position + length) else {
+                break;
+            };
+            datasets.push((record, dataset, value));
+            position += length;
+        }
+
+        let utf8 = datasets
+            .iter()
+            .any(|(record, dataset, value)| *record == 1 && *dataset == 90 && value.starts_with(UTF8_CHARSET));
+
+        let mut info = IptcInfo::default();
+        for (record, dataset, value) in datasets {
+            if record != 2 {
+                continue;
+            }
+            let text = Self::decode_text(value, utf8);
+            if text.is_empty() {
+                continue;
+            }
+
+            let target = match dataset {
+                5 => &mut info.title,
+                25 => {
+                    info.keywords.push(text);
+                    continue;
+                }
+                80 => &mut info.byline,
+                85 => &mut info.byline_title,
+                90 => &mut info.city,
+                92 => &mut info.sublocation,
+                95 => &mut info.province_state,
+                100 => &mut info.country_code,
+                101 => &mut info.country,
+                105 => &mut info.headline,
+                110 => &mut info.credit,
+                115 => &mut info.source,
+                116 => &mut info.copyright_notice,
+                120 => &mut info.caption,
+                _ => continue,
+            };
+            // 可重复的数据集（如多个作者）只取第一个
+            if target.is_none() {
+                *target = Some(text);
+            }
+        }
+
+        info
+    }
+
+    /// 未声明字符集时先按UTF-8解码，失败再按Latin-1解码
+    fn decode_text(value: &[u8], utf8: bool) -> String {
+        let text = match std::str::from_utf8(value) {
+            Ok(text) => text.to_string(),
+            Err(_) if utf8 => String::from_utf8_lossy(value).to_string(),
+            Err(_) => value.iter().map(|&byte| byte as char).collect(),
+        };
+        text.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string()
+    }
+
+    /// 将IPTC字段合并到元数据，只覆盖IPTC中存在的字段
+    pub fn apply_to_metadata(metadata: &mut PhotoMetadata, info: &IptcInfo) {
+        let target = metadata.iptc.get_or_insert_with(IptcInfo::default);
+        let fields = [
+            (&mut target.title, &info.title),
+            (&mut target.headline, &info.headline),
+            (&mut target.caption, &info.caption),
+            (&mut target.byline, &info.byline),
+            (&mut target.byline_title, &info.byline_title),
+            (&mut target.credit, &info.credit),
+            (&mut target.source, &info.source),
+            (&mut target.copyright_notice, &info.copyright_notice),
+            (&mut target.sublocation, &info.sublocation),
+            (&mut target.city, &info.city),
+            (&mut target.province_state, &info.province_state),
+            (&mut target.country, &info.country),
+            (&mut target.country_code, &info.country_code),
+        ];
+        for (target, value) in fields {
+            if value.is_some() {
+                *target = value.clone();
+            }
+        }
+        if !info.keywords.is_empty() {
+            target.keywords = info.keywords.clone();
+        }
+    }
+
+    /// 地点文本，由具体到概括排列，如 "外滩, 上海, 中国"
+    pub fn location_text(info: &IptcInfo) -> Option<String> {
+        let parts: Vec<&str> = [&info.sublocation, &info.city, &info.province_state, &info.country]
+            .into_iter()
+            .filter_map(|part| part.as_deref())
+            .filter(|part| !part.is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// 署名行 "© 作者 / 机构"
+    /// 作者取IPTC署名，没有时取EXIF作者；机构取IPTC Credit，没有时取Source
+    pub fn credit_line(metadata: &PhotoMetadata) -> Option<String> {
+        let iptc = metadata.iptc.as_ref();
+        let name = iptc
+            .and_then(|iptc| iptc.byline.as_deref())
+            .or(metadata.artist.as_deref())
+            .filter(|name| !name.is_empty());
+        let agency = iptc
+            .and_then(|iptc| iptc.credit.as_deref().or(iptc.source.as_deref()))
+            .filter(|agency| !agency.is_empty() && Some(*agency) != name);
+
+        let credit = match (name, agency) {
+            (Some(name), Some(agency)) => format!("{} / {}", name, agency),
+            (Some(name), None) => name.to_string(),
+            (None, Some(agency)) => agency.to_string(),
+            (None, None) => return None,
+        };
+        Some(format!("© {}", credit.trim_start_matches('©').trim_start()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(record: u8, number: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x1C, record, number];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_iim_from_photoshop_segment() {
+        let mut iim = dataset(1, 90, UTF8_CHARSET);
+        iim.extend(dataset(2, 105, b"Harbour at dusk"));
+        iim.extend(dataset(2, 120, "外滩的黄昏".as_bytes()));
+        iim.extend(dataset(2, 25, b"harbour"));
+        iim.extend(dataset(2, 25, b"sunset"));
+        iim.extend(dataset(2, 80, b"Jane Doe"));
+        iim.extend(dataset(2, 110, b"Example Press"));
+        iim.extend(dataset(2, 90, b"Shanghai"));
+        iim.extend(dataset(2, 101, b"China"));
+
+        let segment = IptcService::photoshop_segment(&iim);
+        let block = IptcService::photoshop_iptc(&segment[PHOTOSHOP_HEADER.len()..]).unwrap();
+        let info = IptcService::parse_iim(block);
+
+        assert_eq!(info.headline.as_deref(), Some("Harbour at dusk"));
+        assert_eq!(info.caption.as_deref(), Some("外滩的黄昏"));
+        assert_eq!(info.keywords, ["harbour", "sunset"]);
+        assert_eq!(IptcService::location_text(&info).as_deref(), Some("Shanghai, China"));
+
+        let mut metadata = PhotoMetadata::default();
+        IptcService::apply_to_metadata(&mut metadata, &info);
+        assert_eq!(IptcService::credit_line(&metadata).as_deref(), Some("© Jane Doe / Example Press"));
+    }
+
+    #[test]
+    fn test_latin1_and_credit_fallbacks() {
+        let info = IptcService::parse_iim(&dataset(2, 80, b"Jos\xE9"));
+        assert_eq!(info.byline.as_deref(), Some("José"));
+
+        let mut metadata = PhotoMetadata::default();
+        assert_eq!(IptcService::credit_line(&metadata), None);
+
+        metadata.artist = Some("© John Smith".to_string());
+        assert_eq!(IptcService::credit_line(&metadata).as_deref(), Some("© John Smith"));
+    }
+}
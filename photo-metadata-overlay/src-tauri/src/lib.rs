@@ -1,14 +1,47 @@
 mod types;
+mod app_paths;
+mod brand_logo;
+mod camera_names;
+mod date_format;
+mod derived_metrics;
 mod exif_service;
+mod film_roll;
+mod focal_length;
+mod font_registry;
+mod geocoder;
+mod image_inspector;
+mod gps_format;
 mod image_processing;
+mod iptc_service;
+mod maker_note;
+mod metadata_compare;
+mod metadata_fields;
+mod metadata_override;
+mod metadata_scan;
+mod metadata_writer;
+mod raw_service;
+mod text_layout;
+mod thumbnail;
+mod tiff_reader;
 mod unified_engine;
+mod xmp_service;
 #[cfg(test)]
 mod test_utils;
 
 use types::*;
+use app_paths::AppPaths;
 use exif_service::ExifService;
+use film_roll::FilmRollService;
+use font_registry::FontRegistry;
+use image_inspector::ImageInspector;
 use image_processing::ImageProcessingService;
+use metadata_compare::MetadataCompareService;
+use metadata_override::MetadataOverrideService;
+use metadata_scan::MetadataScanService;
+use thumbnail::{ThumbnailService, DEFAULT_THUMBNAIL_SIZE};
 use unified_engine::{UNIFIED_ENGINE, ProcessingRequestType};
+use xmp_service::XmpService;
+use std::collections::HashMap;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -23,10 +56,71 @@ async fn extract_metadata(file_path: String) -> Result<PhotoMetadata, String> {
         .map_err(|e| e.to_string())
 }
 
-/// 验证图片文件格式
+/// 验证图片文件格式（按文件内容检查）
 #[tauri::command]
 fn validate_image_file(file_path: String) -> bool {
-    ExifService::validate_image_file(&file_path)
+    ImageInspector::is_valid(&file_path)
+}
+
+/// 检查图片文件，返回真实格式、尺寸、颜色类型、元数据与截断情况
+#[tauri::command]
+async fn inspect_image_file(file_path: String) -> Result<ImageFileReport, String> {
+    ImageInspector::inspect(&file_path)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取文件列表缩略图，优先使用内嵌的EXIF缩略图或MPF预览
+#[tauri::command]
+async fn get_thumbnail(file_path: String, max_size: Option<u32>) -> Result<Thumbnail, String> {
+    ThumbnailService::generate(&file_path, max_size.unwrap_or(DEFAULT_THUMBNAIL_SIZE))
+        .map_err(|e| e.to_string())
+}
+
+/// 对比多张图片的元数据（第一张作为参照），返回字段差异、拍摄时间差与建议的时钟修正
+#[tauri::command]
+async fn compare_metadata(image_paths: Vec<String>) -> Result<MetadataComparison, String> {
+    MetadataCompareService::compare(&image_paths)
+        .map_err(|e| e.to_string())
+}
+
+/// 批量扫描文件夹中图片的元数据，进度通过 `metadata-scan-progress` 事件推送
+/// 指定 `output_path` 时按扩展名导出为CSV或JSON
+#[tauri::command]
+async fn scan_folder_metadata(
+    app_handle: tauri::AppHandle,
+    folder: String,
+    recursive: Option<bool>,
+    output_path: Option<String>,
+) -> Result<MetadataScanSummary, String> {
+    use tauri::Emitter;
+
+    tokio::task::spawn_blocking(move || {
+        let output_path = output_path.map(std::path::PathBuf::from);
+        MetadataScanService::scan_folder(&folder, recursive.unwrap_or(false), output_path.as_deref(), |progress| {
+            let _ = app_handle.emit("metadata-scan-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// 列出可用的字体家族（系统字体与用户导入的字体）
+#[tauri::command]
+async fn list_font_families() -> Result<Vec<FontFamilyInfo>, String> {
+    // 首次调用需要索引系统字体
+    tokio::task::spawn_blocking(|| FontRegistry::shared().families())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 将字体文件导入应用数据目录的fonts文件夹，返回其中的字体家族
+#[tauri::command]
+async fn import_font(file_path: String) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || FontRegistry::shared().import(std::path::Path::new(&file_path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 /// 处理单张图片
@@ -38,7 +132,11 @@ async fn process_image(
     frame_settings: FrameSettings,
     output_path: String,
     quality: u8,
+    preserve_metadata: Option<bool>,
+    metadata_override: Option<MetadataOverride>,
 ) -> Result<ProcessedImageInfo, String> {
+    let metadata = MetadataOverrideService::applied(metadata, metadata_override.as_ref())
+        .map_err(|e| e.to_string())?;
     ImageProcessingService::process_image(
         &input_path,
         metadata,
@@ -46,11 +144,70 @@ async fn process_image(
         frame_settings,
         &output_path,
         quality,
+        preserve_metadata.unwrap_or(true),
     )
     .await
     .map_err(|e| e.to_string())
 }
 
+/// 将叠加方案和编辑过的元数据写入XMP边车文件，返回边车路径
+#[tauri::command]
+async fn write_xmp_sidecar(
+    image_path: String,
+    metadata: PhotoMetadata,
+    overlay_settings: OverlaySettings,
+) -> Result<String, String> {
+    XmpService::write_sidecar(&image_path, &metadata, &overlay_settings)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// 读取图片边车中保存的元数据覆盖
+#[tauri::command]
+fn get_metadata_override(image_path: String) -> Result<Option<MetadataOverride>, String> {
+    MetadataOverrideService::load(&image_path).map_err(|e| e.to_string())
+}
+
+/// 将元数据覆盖保存到一张或多张图片的边车文件（与已保存的覆盖合并）
+#[tauri::command]
+async fn save_metadata_override(
+    image_paths: Vec<String>,
+    metadata_override: MetadataOverride,
+) -> Result<Vec<String>, String> {
+    image_paths
+        .iter()
+        .map(|image_path| {
+            MetadataOverrideService::save(image_path, &metadata_override)
+                .map(|path| path.to_string_lossy().to_string())
+                .map_err(|e| format!("{}: {}", image_path, e))
+        })
+        .collect()
+}
+
+/// 删除图片边车中保存的元数据覆盖
+#[tauri::command]
+async fn clear_metadata_override(image_paths: Vec<String>) -> Result<(), String> {
+    for image_path in &image_paths {
+        MetadataOverrideService::clear(image_path).map_err(|e| format!("{}: {}", image_path, e))?;
+    }
+    Ok(())
+}
+
+/// 读取胶片卷记录表（CSV或TOML）
+#[tauri::command]
+async fn load_film_roll(sheet_path: String) -> Result<FilmRoll, String> {
+    FilmRollService::load(&sheet_path).map_err(|e| e.to_string())
+}
+
+/// 将胶片卷记录表对应到扫描文件，返回每个文件的元数据覆盖（可用于预览或保存到边车）
+#[tauri::command]
+async fn map_film_roll(
+    roll: FilmRoll,
+    image_paths: Vec<String>,
+) -> Result<HashMap<String, MetadataOverride>, String> {
+    FilmRollService::overrides(&roll, &image_paths).map_err(|e| e.to_string())
+}
+
 /// 批量处理图片
 #[tauri::command]
 async fn batch_process_images(
@@ -69,8 +226,9 @@ async fn generate_preview(
     image_path: String,
     settings: PreviewSettings,
 ) -> Result<Vec<u8>, String> {
-    // 提取元数据
+    // 提取元数据并应用尚未保存的覆盖
     let metadata = ExifService::extract_metadata(&image_path)
+        .and_then(|metadata| MetadataOverrideService::applied(metadata, settings.metadata_override.as_ref()))
         .map_err(|e| e.to_string())?;
     
     // 使用统一引擎处理预览
@@ -80,6 +238,8 @@ async fn generate_preview(
         settings.overlay_settings,
         settings.frame_settings,
         ProcessingRequestType::Preview,
+        // 预览只返回图像数据，不写入元数据
+        false,
     ).await {
         Ok(unified_engine::ProcessingResult::Preview(data)) => Ok(data),
         Ok(_) => Err("Unexpected result type".to_string()),
@@ -96,11 +256,16 @@ async fn save_processed_image(
     overlay_settings: OverlaySettings,
     frame_settings: FrameSettings,
     quality: u8,
+    preserve_metadata: Option<bool>,
+    metadata_override: Option<MetadataOverride>,
 ) -> Result<String, String> {
     use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
     use std::sync::{Arc, Mutex};
     use tokio::sync::oneshot;
     
+    let metadata = MetadataOverrideService::applied(metadata, metadata_override.as_ref())
+        .map_err(|e| e.to_string())?;
+    
     // 获取原始文件名
     let input_file = std::path::Path::new(&input_path);
     let file_stem = input_file.file_stem()
@@ -146,6 +311,7 @@ async fn save_processed_image(
                 frame_settings,
                 &output_path,
                 quality,
+                preserve_metadata.unwrap_or(true),
             ).await {
                 Ok(_result) => {
                     // 显示成功消息
@@ -202,14 +368,34 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .setup(|app| {
+            use tauri::Manager;
+            // 用户可在应用数据目录中放入完整的地名库、字体、品牌Logo等数据
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                AppPaths::init(app_data_dir);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             extract_metadata,
             validate_image_file,
+            inspect_image_file,
+            get_thumbnail,
+            scan_folder_metadata,
+            compare_metadata,
+            list_font_families,
+            import_font,
             process_image,
             batch_process_images,
             generate_preview,
-            save_processed_image
+            save_processed_image,
+            write_xmp_sidecar,
+            get_metadata_override,
+            save_metadata_override,
+            clear_metadata_override,
+            load_film_roll,
+            map_film_roll
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
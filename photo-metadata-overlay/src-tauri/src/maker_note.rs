@@ -0,0 +1,306 @@
+use crate::tiff_reader::{IfdEntry, TiffReader};
+
+/// 从厂商私有数据（MakerNote）中解码出的字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MakerNoteInfo {
+    pub film_simulation: Option<String>,
+    pub lens_model: Option<String>,
+    pub shutter_count: Option<u32>,
+    pub serial_number: Option<String>,
+}
+
+/// MakerNote解码器
+/// 支持富士、佳能、尼康和索尼的常见格式；未知厂商或结构损坏的数据返回None，不影响EXIF提取
+pub struct MakerNoteDecoder;
+
+impl MakerNoteDecoder {
+    /// 解码MakerNote
+    /// `exif_tiff` 为完整的EXIF（TIFF）数据，`offset` 为MakerNote在其中的偏移，
+    /// 佳能与索尼的MakerNote内部偏移以TIFF头为基准
+    pub fn decode(make: &str, exif_tiff: &[u8], offset: usize, little_endian: bool) -> Option<MakerNoteInfo> {
+        let maker_note = exif_tiff.get(offset..)?;
+        let make = make.to_ascii_lowercase();
+
+        let info = if maker_note.starts_with(b"FUJIFILM") {
+            Self::decode_fujifilm(maker_note)
+        } else if maker_note.starts_with(b"Nikon\0") {
+            Self::decode_nikon(maker_note)
+        } else if make.contains("canon") {
+            Self::decode_canon(&TiffReader::with_byte_order(exif_tiff, little_endian), offset)
+        } else if make.contains("sony") {
+            // 部分机型在IFD前有12字节的 "SONY DSC " / "SONY CAM " 标识
+            let ifd_offset = if maker_note.starts_with(b"SONY") { offset + 12 } else { offset };
+            Self::decode_sony(&TiffReader::with_byte_order(exif_tiff, little_endian), ifd_offset)
+        } else {
+            None
+        }?;
+
+        (info != MakerNoteInfo::default()).then_some(info)
+    }
+
+    /// 富士："FUJIFILM" + 小端IFD偏移，偏移以MakerNote开头为基准
+    fn decode_fujifilm(maker_note: &[u8]) -> Option<MakerNoteInfo> {
+        let reader = TiffReader::with_byte_order(maker_note, true);
+        let entries = reader.ifd_entries(reader.read_u32(8)? as usize)?;
+        let value = |tag: u16| Self::first_u32(&reader, &entries, tag);
+
+        // 黑白与棕褐色模式记录在Saturation标签中，此时没有FilmMode
+        let monochrome = value(0x1003).and_then(|saturation| match saturation {
+            0x300 => Some("Monochrome"),
+            0x301 => Some("Monochrome+R"),
+            0x302 => Some("Monochrome+Ye"),
+            0x303 => Some("Monochrome+G"),
+            0x310 => Some("Sepia"),
+            0x500 => Some("ACROS"),
+            0x501 => Some("ACROS+R"),
+            0x502 => Some("ACROS+Ye"),
+            0x503 => Some("ACROS+G"),
+            _ => None,
+        });
+        let film_mode = value(0x1401).and_then(|mode| match mode {
+            0x000 => Some("PROVIA/Standard"),
+            0x100 => Some("Studio Portrait"),
+            0x110 => Some("Studio Portrait Enhanced Saturation"),
+            0x120 => Some("ASTIA/Soft"),
+            0x130 => Some("Studio Portrait Increased Sharpness"),
+            0x200 | 0x400 => Some("Velvia/Vivid"),
+            0x300 => Some("Studio Portrait Ex"),
+            0x500 => Some("PRO Neg. Std"),
+            0x501 => Some("PRO Neg. Hi"),
+            0x600 => Some("Classic Chrome"),
+            0x700 => Some("ETERNA/Cinema"),
+            0x800 => Some("Classic Neg."),
+            0x900 => Some("ETERNA Bleach Bypass"),
+            0xA00 => Some("Nostalgic Neg."),
+            0xB00 => Some("REALA ACE"),
+            _ => None,
+        });
+
+        Some(MakerNoteInfo {
+            film_simulation: monochrome.or(film_mode).map(str::to_string),
+            lens_model: None,
+            // 最高位是标志位
+            shutter_count: value(0x1438).map(|count| count & 0x7FFF).filter(|&count| count > 0),
+            serial_number: Self::string(&reader, &entries, 0x0010),
+        })
+    }
+
+    /// 尼康第3类："Nikon\0" + 版本，偏移10处是独立的TIFF头
+    fn decode_nikon(maker_note: &[u8]) -> Option<MakerNoteInfo> {
+        let reader = TiffReader::new(maker_note.get(10..)?)?;
+        let entries = reader.ifd_entries(reader.first_ifd_offset()?)?;
+
+        Some(MakerNoteInfo {
+            film_simulation: None,
+            lens_model: Self::nikon_lens(&reader, &entries),
+            shutter_count: Self::first_u32(&reader, &entries, 0x00A7),
+            serial_number: Self::string(&reader, &entries, 0x001D),
+        })
+    }
+
+    /// 尼康Lens标签：最短焦距、最长焦距、最大光圈（广角端）、最大光圈（长焦端）
+    fn nikon_lens(reader: &TiffReader, entries: &[IfdEntry]) -> Option<String> {
+        let entry = entries.iter().find(|entry| entry.tag == 0x0084)?;
+        let values = reader.entry_rationals(entry);
+        let [Some(short), Some(long), wide_aperture, tele_aperture] = values.get(..4)? else {
+            return None;
+        };
+
+        let focal = if (short - long).abs() < 0.5 {
+            format!("{:.0}mm", short)
+        } else {
+            format!("{:.0}-{:.0}mm", short, long)
+        };
+        let aperture = match (wide_aperture, tele_aperture) {
+            (Some(wide), Some(tele)) if (wide - tele).abs() >= 0.05 => format!(" f/{}-{}", Self::trim(*wide), Self::trim(*tele)),
+            (Some(wide), _) if *wide > 0.0 => format!(" f/{}", Self::trim(*wide)),
+            _ => String::new(),
+        };
+        Some(format!("{}{}", focal, aperture))
+    }
+
+    /// 佳能：标准IFD，无头部
+    fn decode_canon(reader: &TiffReader, offset: usize) -> Option<MakerNoteInfo> {
+        let entries = reader.ifd_entries(offset)?;
+        let serial_number = Self::first_u32(reader, &entries, 0x000C)
+            .filter(|&serial| serial > 0)
+            .map(|serial| serial.to_string())
+            .or_else(|| Self::string(reader, &entries, 0x0096));
+
+        Some(MakerNoteInfo {
+            film_simulation: None,
+            lens_model: Self::string(reader, &entries, 0x0095),
+            shutter_count: None,
+            serial_number,
+        })
+    }
+
+    /// 索尼：镜头规格标签（LensSpec）以BCD编码焦距与光圈；快门次数与序列号经过加密，不解码
+    fn decode_sony(reader: &TiffReader, offset: usize) -> Option<MakerNoteInfo> {
+        let entries = reader.ifd_entries(offset)?;
+        let lens_model = entries
+            .iter()
+            .find(|entry| entry.tag == 0xB02A)
+            .and_then(|entry| reader.entry_bytes(entry))
+            .and_then(Self::sony_lens_spec);
+
+        Some(MakerNoteInfo {
+            lens_model,
+            ..Default::default()
+        })
+    }
+
+    /// 解析索尼LensSpec的8个字节：标志、最短焦距、最长焦距、广角端光圈、长焦端光圈、标志
+    fn sony_lens_spec(bytes: &[u8]) -> Option<String> {
+        let bytes: &[u8; 8] = bytes.try_into().ok()?;
+        let bcd = |digits: &[u8]| -> Option<u32> {
+            digits.iter().try_fold(0u32, |value, &byte| {
+                let (high, low) = ((byte >> 4) as u32, (byte & 0x0F) as u32);
+                (high < 10 && low < 10).then_some(value * 100 + high * 10 + low)
+            })
+        };
+
+        let short = bcd(&bytes[1..3])?;
+        let long = bcd(&bytes[3..5])?;
+        let wide_aperture = bcd(&bytes[5..6])? as f64 / 10.0;
+        let tele_aperture = bcd(&bytes[6..7])? as f64 / 10.0;
+        if short == 0 {
+            return None;
+        }
+
+        let focal = if long == 0 || long == short {
+            format!("{}mm", short)
+        } else {
+            format!("{}-{}mm", short, long)
+        };
+        let aperture = if tele_aperture > 0.0 && (tele_aperture - wide_aperture).abs() >= 0.05 {
+            format!(" F{}-{}", Self::trim(wide_aperture), Self::trim(tele_aperture))
+        } else if wide_aperture > 0.0 {
+            format!(" F{}", Self::trim(wide_aperture))
+        } else {
+            String::new()
+        };
+        Some(format!("{}{}", focal, aperture))
+    }
+
+    fn first_u32(reader: &TiffReader, entries: &[IfdEntry], tag: u16) -> Option<u32> {
+        let entry = entries.iter().find(|entry| entry.tag == tag)?;
+        reader.entry_u32_values(entry).first().copied()
+    }
+
+    fn string(reader: &TiffReader, entries: &[IfdEntry], tag: u16) -> Option<String> {
+        let entry = entries.iter().find(|entry| entry.tag == tag && entry.field_type == 2)?;
+        reader.entry_string(entry)
+    }
+
+    /// 去掉多余的小数位（2.80 → "2.8"，4.0 → "4"）
+    fn trim(value: f64) -> String {
+        let text = format!("{:.1}", value);
+        text.trim_end_matches(".0").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一个IFD：条目按 (标签, 类型, 数量, 值字节) 给出，超过4字节的值放在IFD之后
+    fn build_ifd(entries: &[(u16, u16, u32, Vec<u8>)], ifd_offset: usize, little_endian: bool) -> Vec<u8> {
+        let u16_bytes = |v: u16| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+        let u32_bytes = |v: u32| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+
+        let mut ifd = u16_bytes(entries.len() as u16).to_vec();
+        let mut extra = Vec::new();
+        let extra_start = ifd_offset + 2 + entries.len() * 12 + 4;
+        for (tag, field_type, count, value) in entries {
+            ifd.extend_from_slice(&u16_bytes(*tag));
+            ifd.extend_from_slice(&u16_bytes(*field_type));
+            ifd.extend_from_slice(&u32_bytes(*count));
+            if value.len() <= 4 {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                ifd.extend_from_slice(&inline);
+            } else {
+                ifd.extend_from_slice(&u32_bytes((extra_start + extra.len()) as u32));
+                extra.extend_from_slice(value);
+            }
+        }
+        ifd.extend_from_slice(&[0, 0, 0, 0]);
+        ifd.extend_from_slice(&extra);
+        ifd
+    }
+
+    #[test]
+    fn test_decode_fujifilm() {
+        let mut maker_note = b"FUJIFILM".to_vec();
+        maker_note.extend_from_slice(&12u32.to_le_bytes());
+        maker_note.extend(build_ifd(
+            &[
+                (0x0010, 2, 8, b"FF02B12\0".to_vec()),
+                (0x1401, 3, 1, 0x600u16.to_le_bytes().to_vec()),
+                (0x1438, 4, 1, 0x8123u32.to_le_bytes().to_vec()),
+            ],
+            12,
+            true,
+        ));
+
+        let info = MakerNoteDecoder::decode("FUJIFILM", &maker_note, 0, false).unwrap();
+        assert_eq!(info.film_simulation.as_deref(), Some("Classic Chrome"));
+        assert_eq!(info.serial_number.as_deref(), Some("FF02B12"));
+        assert_eq!(info.shutter_count, Some(0x123));
+    }
+
+    #[test]
+    fn test_decode_nikon_and_canon() {
+        let mut lens = Vec::new();
+        for (num, den) in [(240u32, 10u32), (700, 10), (28, 10), (28, 10)] {
+            lens.extend_from_slice(&num.to_be_bytes());
+            lens.extend_from_slice(&den.to_be_bytes());
+        }
+        let mut maker_note = b"Nikon\0\x02\x10\0\0MM\0\x2A\0\0\0\x08".to_vec();
+        maker_note.extend(build_ifd(
+            &[
+                (0x001D, 2, 8, b"3012345\0".to_vec()),
+                (0x0084, 5, 4, lens),
+                (0x00A7, 4, 1, 48213u32.to_be_bytes().to_vec()),
+            ],
+            8,
+            false,
+        ));
+        let info = MakerNoteDecoder::decode("NIKON CORPORATION", &maker_note, 0, false).unwrap();
+        assert_eq!(info.lens_model.as_deref(), Some("24-70mm f/2.8"));
+        assert_eq!(info.shutter_count, Some(48213));
+        assert_eq!(info.serial_number.as_deref(), Some("3012345"));
+
+        // 佳能的偏移以TIFF头为基准，MakerNote放在偏移16处
+        let mut tiff = b"II\x2A\0\x08\0\0\0\0\0\0\0\0\0\0\0".to_vec();
+        tiff.extend(build_ifd(
+            &[
+                (0x000C, 4, 1, 1234567u32.to_le_bytes().to_vec()),
+                (0x0095, 2, 14, b"EF24-70mm f/4\0".to_vec()),
+            ],
+            16,
+            true,
+        ));
+        let info = MakerNoteDecoder::decode("Canon", &tiff, 16, true).unwrap();
+        assert_eq!(info.lens_model.as_deref(), Some("EF24-70mm f/4"));
+        assert_eq!(info.serial_number.as_deref(), Some("1234567"));
+    }
+
+    #[test]
+    fn test_sony_lens_spec_and_corrupt_notes() {
+        assert_eq!(
+            MakerNoteDecoder::sony_lens_spec(&[0x00, 0x00, 0x24, 0x00, 0x70, 0x28, 0x28, 0x00]).as_deref(),
+            Some("24-70mm F2.8")
+        );
+        assert_eq!(
+            MakerNoteDecoder::sony_lens_spec(&[0x00, 0x00, 0x16, 0x00, 0x50, 0x35, 0x56, 0x00]).as_deref(),
+            Some("16-50mm F3.5-5.6")
+        );
+
+        assert_eq!(MakerNoteDecoder::decode("FUJIFILM", b"FUJIFILM\xFF\xFF\xFF\xFF", 0, true), None);
+        assert_eq!(MakerNoteDecoder::decode("Canon", &[0xFF; 6], 2, true), None);
+        assert_eq!(MakerNoteDecoder::decode("Leica", &[0u8; 64], 0, true), None);
+        assert_eq!(MakerNoteDecoder::decode("Canon", &[], 100, true), None);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::date_format::DateFormatter;
+use crate::exif_service::ExifService;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+
+/// 不参与对比的字段：文件名、大小等文件属性每张图片都不同
+const IGNORED_FIELDS: &[&str] = &["file"];
+
+/// 相机时钟未设置时区时，按15分钟的整数倍估计时区偏移
+const TIME_ZONE_STEP_SECONDS: i64 = 15 * 60;
+
+/// 多张图片的元数据对比
+/// 用于发现多机位拍摄时机身之间的时钟漂移和设置差异
+pub struct MetadataCompareService;
+
+impl MetadataCompareService {
+    /// 提取并对比多张图片的元数据，第一张作为参照
+    pub fn compare(image_paths: &[String]) -> Result<MetadataComparison> {
+        if image_paths.len() < 2 {
+            anyhow::bail!("At least two images are required for comparison");
+        }
+
+        let images = image_paths
+            .iter()
+            .map(|path| {
+                ExifService::extract_metadata(path)
+                    .with_context(|| format!("Failed to read metadata: {}", path))
+                    .map(|metadata| (path.clone(), metadata))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::compare_metadata(&images))
+    }
+
+    /// 逐字段对比，并计算拍摄时间差与建议的时钟修正
+    pub fn compare_metadata(images: &[(String, PhotoMetadata)]) -> MetadataComparison {
+        let flattened: Vec<_> = images
+            .iter()
+            .map(|(_, metadata)| {
+                let mut fields = MetadataFields::flatten(metadata);
+                fields.retain(|key, _| !IGNORED_FIELDS.contains(&key.as_str()));
+                fields
+            })
+            .collect();
+        let keys: BTreeSet<&String> = flattened.iter().flat_map(|fields| fields.keys()).collect();
+
+        let mut comparison = MetadataComparison {
+            file_paths: images.iter().map(|(path, _)| path.clone()).collect(),
+            ..Default::default()
+        };
+
+        for key in keys {
+            let values: Vec<_> = flattened.iter().map(|fields| fields.get(key).cloned()).collect();
+            match &values[0] {
+                Some(first) if values.iter().all(|value| value.as_ref() == Some(first)) => {
+                    comparison.common_fields.insert(key.clone(), first.clone());
+                }
+                _ => comparison.differences.push(FieldDifference { field: key.clone(), values }),
+            }
+        }
+
+        let reference = images.first().and_then(|(_, metadata)| metadata.capture_time.as_ref());
+        comparison.time_deltas = images
+            .iter()
+            .map(|(_, metadata)| Some(Self::time_delta(metadata.capture_time.as_ref()?, reference?)))
+            .collect();
+
+        for (index, (path, metadata)) in images.iter().enumerate() {
+            let suggestion = match Self::gps_clock_offset(metadata) {
+                Some(offset) => Some((offset, ClockOffsetBasis::GpsTime)),
+                None if index > 0 => comparison.time_deltas[index].map(|delta| (-delta, ClockOffsetBasis::Reference)),
+                None => None,
+            };
+            if let Some((offset_seconds, basis)) = suggestion.filter(|(offset, _)| *offset != 0) {
+                comparison.clock_offsets.push(ClockOffsetSuggestion { file_path: path.clone(), offset_seconds, basis });
+            }
+        }
+
+        comparison
+    }
+
+    /// 两个拍摄时间的差（秒）
+    /// 两者都有时区偏移时按UTC比较，否则按当地时间比较
+    pub fn time_delta(time: &CaptureTimestamp, reference: &CaptureTimestamp) -> i64 {
+        if time.offset_minutes.is_some() && reference.offset_minutes.is_some() {
+            DateFormatter::to_unix_seconds(time) - DateFormatter::to_unix_seconds(reference)
+        } else {
+            DateFormatter::local_seconds(time) - DateFormatter::local_seconds(reference)
+        }
+    }
+
+    /// 按GPS卫星时间（UTC）计算相机时钟的修正量
+    /// 拍摄时间没有时区偏移时，差值中最接近的15分钟整数倍视为时区，其余部分视为时钟误差
+    pub fn gps_clock_offset(metadata: &PhotoMetadata) -> Option<i64> {
+        let capture_time = metadata.capture_time.as_ref()?;
+        let gps_time = metadata.location.as_ref()?.gps_time.as_ref()?;
+        let gps_seconds = DateFormatter::to_unix_seconds(gps_time);
+
+        let drift = match capture_time.offset_minutes {
+            Some(_) => DateFormatter::to_unix_seconds(capture_time) - gps_seconds,
+            None => {
+                let difference = DateFormatter::local_seconds(capture_time) - gps_seconds;
+                let time_zone = (difference as f64 / TIME_ZONE_STEP_SECONDS as f64).round() as i64 * TIME_ZONE_STEP_SECONDS;
+                difference - time_zone
+            }
+        };
+        Some(-drift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(model: &str, time: &str, offset_minutes: Option<i16>) -> PhotoMetadata {
+        let mut capture_time = DateFormatter::parse_exif_string(time, TimestampSource::DateTimeOriginal).unwrap();
+        capture_time.offset_minutes = offset_minutes;
+        let mut metadata = PhotoMetadata {
+            timestamp: Some(time.to_string()),
+            capture_time: Some(capture_time),
+            ..Default::default()
+        };
+        metadata.camera.make = Some("Canon".to_string());
+        metadata.camera.model = Some(model.to_string());
+        metadata.settings.iso = Some(800);
+        metadata
+    }
+
+    #[test]
+    fn test_field_diff_and_time_deltas() {
+        let images = vec![
+            ("a.jpg".to_string(), metadata("EOS R5", "2024:06:01 14:00:00", None)),
+            ("b.jpg".to_string(), metadata("EOS R6", "2024:06:01 14:03:20", None)),
+            ("c.jpg".to_string(), PhotoMetadata::default()),
+        ];
+        let comparison = MetadataCompareService::compare_metadata(&images[..2]);
+
+        assert_eq!(comparison.common_fields.get("camera.make"), Some(&serde_json::json!("Canon")));
+        let model = comparison.differences.iter().find(|diff| diff.field == "camera.model").unwrap();
+        assert_eq!(model.values, vec![Some(serde_json::json!("EOS R5")), Some(serde_json::json!("EOS R6"))]);
+        assert_eq!(comparison.time_deltas, vec![Some(0), Some(200)]);
+        assert_eq!(
+            comparison.clock_offsets,
+            vec![ClockOffsetSuggestion { file_path: "b.jpg".to_string(), offset_seconds: -200, basis: ClockOffsetBasis::Reference }]
+        );
+
+        // 缺少的字段同样算作差异
+        let comparison = MetadataCompareService::compare_metadata(&images);
+        assert!(comparison.common_fields.is_empty());
+        assert_eq!(comparison.time_deltas[2], None);
+
+        // 都有时区偏移时按UTC比较：东京 15:00 与 UTC 06:00 是同一时刻
+        let tokyo = metadata("EOS R5", "2024:06:01 15:00:00", Some(540)).capture_time.unwrap();
+        let utc = metadata("EOS R6", "2024:06:01 06:00:00", Some(0)).capture_time.unwrap();
+        assert_eq!(MetadataCompareService::time_delta(&tokyo, &utc), 0);
+    }
+
+    #[test]
+    fn test_gps_clock_offset() {
+        // 相机时钟比GPS快95秒，没有时区偏移（实际为UTC+8）
+        let mut camera = metadata("EOS R5", "2024:06:01 20:01:35", None);
+        let gps_time = DateFormatter::parse_exif_string("2024:06:01 12:00:00", TimestampSource::Gps).unwrap();
+        camera.location = Some(LocationInfo { gps_time: Some(gps_time), ..Default::default() });
+        assert_eq!(MetadataCompareService::gps_clock_offset(&camera), Some(-95));
+
+        // 有时区偏移时直接按UTC计算
+        camera.capture_time.as_mut().unwrap().offset_minutes = Some(480);
+        assert_eq!(MetadataCompareService::gps_clock_offset(&camera), Some(-95));
+
+        let images = vec![
+            ("a.jpg".to_string(), metadata("EOS R6", "2024:06:01 20:00:00", None)),
+            ("b.jpg".to_string(), camera),
+        ];
+        let comparison = MetadataCompareService::compare_metadata(&images);
+        assert_eq!(comparison.clock_offsets[0].basis, ClockOffsetBasis::GpsTime);
+    }
+}
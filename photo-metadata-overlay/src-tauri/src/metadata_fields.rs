@@ -0,0 +1,95 @@
+use crate::types::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 按字段（而非整体）记录来源的分组
+const GROUPED_SECTIONS: &[&str] = &["camera", "settings", "location", "iptc", "film"];
+
+/// 元数据字段工具
+/// 以 "分组.字段" 的扁平形式访问 `PhotoMetadata`，用于来源记录、导出和对比
+pub struct MetadataFields;
+
+impl MetadataFields {
+    /// 将元数据展开为 "分组.字段" → 值 的映射，空值不包含在内
+    pub fn flatten(metadata: &PhotoMetadata) -> BTreeMap<String, Value> {
+        let mut fields = BTreeMap::new();
+        let Ok(Value::Object(root)) = serde_json::to_value(metadata) else {
+            return fields;
+        };
+
+        for (key, value) in root {
+            if key == "field_sources" || Self::is_empty(&value) {
+                continue;
+            }
+            match value {
+                Value::Object(section) if GROUPED_SECTIONS.contains(&key.as_str()) => {
+                    for (field, value) in section {
+                        if !Self::is_empty(&value) {
+                            fields.insert(format!("{}.{}", key, field), value);
+                        }
+                    }
+                }
+                value => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+
+        fields
+    }
+
+    /// 空值与空数组视为不存在
+    fn is_empty(value: &Value) -> bool {
+        match value {
+            Value::Null => true,
+            Value::Array(items) => items.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// 对比处理阶段前后的字段，将新增或改变的字段标记为指定来源，被清除的字段移除来源
+    pub fn record_sources(
+        metadata: &mut PhotoMetadata,
+        before: &BTreeMap<String, Value>,
+        source: MetadataSource,
+    ) {
+        let after = Self::flatten(metadata);
+
+        for (key, value) in &after {
+            if before.get(key) != Some(value) {
+                metadata.field_sources.insert(key.clone(), source);
+            }
+        }
+
+        metadata.field_sources.retain(|key, _| after.contains_key(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_and_record_sources() {
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("Canon".to_string());
+        metadata.settings.iso = Some(400);
+
+        let before = MetadataFields::flatten(&PhotoMetadata::default());
+        MetadataFields::record_sources(&mut metadata, &before, MetadataSource::Exif);
+
+        let fields = MetadataFields::flatten(&metadata);
+        assert_eq!(fields.get("camera.make"), Some(&Value::from("Canon")));
+        assert_eq!(fields.get("settings.iso"), Some(&Value::from(400)));
+        assert!(!fields.contains_key("camera.model"));
+        assert_eq!(metadata.field_sources.get("settings.iso"), Some(&MetadataSource::Exif));
+
+        // 之后的阶段只覆盖改变的字段
+        let before = MetadataFields::flatten(&metadata);
+        metadata.settings.iso = Some(800);
+        metadata.camera.make = None;
+        MetadataFields::record_sources(&mut metadata, &before, MetadataSource::File);
+        assert_eq!(metadata.field_sources.get("settings.iso"), Some(&MetadataSource::File));
+        assert!(!metadata.field_sources.contains_key("camera.make"));
+    }
+}
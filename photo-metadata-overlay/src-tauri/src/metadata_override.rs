@@ -0,0 +1,290 @@
+use crate::date_format::DateFormatter;
+use crate::derived_metrics::DerivedMetrics;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+use crate::xmp_service::{XmpPacket, XmpService, XmpValue};
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// 边车文件中保存手动覆盖的属性
+const OVERRIDE_PROPERTY: &str = "pmo:MetadataOverride";
+
+/// 拍摄时间偏移的上限（约100年），超出的偏移视为无效输入
+const MAX_TIME_OFFSET_SECONDS: u64 = 100 * 366 * 86_400;
+
+/// 手动元数据覆盖
+/// 用于修正扫描胶片、手动镜头、时钟错误的相机等产生的错误或缺失的元数据
+pub struct MetadataOverrideService;
+
+impl MetadataOverrideService {
+    /// 覆盖是否不包含任何修改
+    pub fn is_empty(metadata_override: &MetadataOverride) -> bool {
+        metadata_override.fields.is_empty() && metadata_override.time_offset_seconds.unwrap_or(0) == 0
+    }
+
+    /// 将覆盖合并到元数据，被修改的字段来源记为 `Override`
+    pub fn apply(metadata: &mut PhotoMetadata, metadata_override: &MetadataOverride) -> Result<()> {
+        Self::apply_with_source(metadata, metadata_override, MetadataSource::Override)
+    }
+
+    /// 将覆盖合并到元数据，被修改的字段来源记为 `source`
+    pub fn apply_with_source(
+        metadata: &mut PhotoMetadata,
+        metadata_override: &MetadataOverride,
+        source: MetadataSource,
+    ) -> Result<()> {
+        if Self::is_empty(metadata_override) {
+            return Ok(());
+        }
+        if let Some(offset) = metadata_override.time_offset_seconds {
+            if offset.unsigned_abs() > MAX_TIME_OFFSET_SECONDS {
+                anyhow::bail!("Time offset out of range: {} seconds", offset);
+            }
+        }
+
+        let before = MetadataFields::flatten(metadata);
+        let mut value = serde_json::to_value(&*metadata)?;
+        for (key, field) in &metadata_override.fields {
+            Self::set_field(&mut value, key, field.clone())?;
+        }
+
+        let mut updated: PhotoMetadata = serde_json::from_value(value).context("Invalid metadata override")?;
+        Self::check_fields(&updated, metadata_override)?;
+
+        // 只覆盖了时间字符串或结构化时间之一时，同步另一个
+        let overridden = |key: &str| metadata_override.fields.contains_key(key);
+        if overridden("timestamp") && !overridden("capture_time") {
+            updated.capture_time = updated
+                .timestamp
+                .as_deref()
+                .and_then(|timestamp| DateFormatter::parse_exif_string(timestamp, TimestampSource::Override));
+        } else if overridden("capture_time") && !overridden("timestamp") {
+            updated.timestamp = updated.capture_time.as_ref().map(DateFormatter::to_exif_string);
+        }
+
+        if let Some(offset) = metadata_override.time_offset_seconds.filter(|offset| *offset != 0) {
+            if let Some(capture_time) = &updated.capture_time {
+                let shifted = DateFormatter::shift(capture_time, offset)
+                    .context("Time offset moves the capture time out of range")?;
+                updated.timestamp = Some(DateFormatter::to_exif_string(&shifted));
+                updated.capture_time = Some(shifted);
+            }
+        }
+
+        *metadata = updated;
+        MetadataFields::record_sources(metadata, &before, source);
+
+        // 拍摄参数或机型被覆盖后重新计算派生值
+        DerivedMetrics::apply_to_metadata(metadata);
+        Ok(())
+    }
+
+    /// 应用可选的覆盖并返回结果
+    pub fn applied(mut metadata: PhotoMetadata, metadata_override: Option<&MetadataOverride>) -> Result<PhotoMetadata> {
+        if let Some(metadata_override) = metadata_override {
+            Self::apply(&mut metadata, metadata_override)?;
+        }
+        Ok(metadata)
+    }
+
+    /// 叠加两层覆盖，`layer` 中的字段优先
+    pub fn merge(base: &MetadataOverride, layer: &MetadataOverride) -> MetadataOverride {
+        let mut merged = base.clone();
+        merged.fields.extend(layer.fields.clone());
+        if layer.time_offset_seconds.is_some() {
+            merged.time_offset_seconds = layer.time_offset_seconds;
+        }
+        merged
+    }
+
+    /// 按 "分组.字段" 路径写入值，路径上为null的对象会被创建
+    fn set_field(root: &mut Value, key: &str, field: Value) -> Result<()> {
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let leaf = parts.pop().filter(|leaf| !leaf.is_empty()).with_context(|| format!("Invalid override field: {:?}", key))?;
+
+        let mut current = root;
+        for part in parts {
+            let object = current
+                .as_object_mut()
+                .with_context(|| format!("Invalid override field: {:?}", key))?;
+            let child = object.entry(part).or_insert(Value::Null);
+            if child.is_null() {
+                *child = Value::Object(Map::new());
+            }
+            current = child;
+        }
+
+        current
+            .as_object_mut()
+            .with_context(|| format!("Invalid override field: {:?}", key))?
+            .insert(leaf.to_string(), field);
+        Ok(())
+    }
+
+    /// 反序列化会忽略未知字段，这里确认每个覆盖的字段都真实存在
+    fn check_fields(metadata: &PhotoMetadata, metadata_override: &MetadataOverride) -> Result<()> {
+        let value = serde_json::to_value(metadata)?;
+        for key in metadata_override.fields.keys() {
+            let (parent, leaf) = match key.rsplit_once('.') {
+                Some((parent, leaf)) => (value.pointer(&format!("/{}", parent.replace('.', "/"))), leaf),
+                None => (Some(&value), key.as_str()),
+            };
+            // 分组不存在或不是对象（如 "setings.iso"、"artist.name"）同样视为未知字段
+            match parent {
+                Some(Value::Object(object)) if object.contains_key(leaf) => {}
+                _ => anyhow::bail!("Unknown metadata field: {}", key),
+            }
+        }
+        Ok(())
+    }
+
+    /// 从边车数据包中读取保存的覆盖
+    pub fn from_packet(packet: &XmpPacket) -> Option<MetadataOverride> {
+        let json = packet.value(OVERRIDE_PROPERTY).flatten()?;
+        match serde_json::from_str(json) {
+            Ok(metadata_override) => Some(metadata_override),
+            Err(e) => {
+                println!("⚠️ Ignoring invalid saved metadata override: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 读取图片边车中保存的覆盖
+    pub fn load<P: AsRef<Path>>(image_path: P) -> Result<Option<MetadataOverride>> {
+        match XmpService::find_sidecar(image_path.as_ref()) {
+            Some(sidecar_path) => Ok(Self::from_packet(&XmpService::read_sidecar(sidecar_path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 将覆盖合并到图片边车中已保存的覆盖，重新处理时得到相同的结果
+    pub fn save<P: AsRef<Path>>(image_path: P, metadata_override: &MetadataOverride) -> Result<PathBuf> {
+        let image_path = image_path.as_ref();
+        let saved = Self::load(image_path)?.unwrap_or_default();
+        let merged = Self::merge(&saved, metadata_override);
+
+        let value = XmpValue::Text(serde_json::to_string(&merged)?);
+        XmpService::update_sidecar(image_path, &[(OVERRIDE_PROPERTY, value)])
+    }
+
+    /// 删除图片边车中保存的覆盖
+    pub fn clear<P: AsRef<Path>>(image_path: P) -> Result<()> {
+        let image_path = image_path.as_ref();
+        if XmpService::find_sidecar(image_path).is_some() {
+            XmpService::update_sidecar(image_path, &[(OVERRIDE_PROPERTY, XmpValue::Empty)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metadata_override(fields: Value, time_offset_seconds: Option<i64>) -> MetadataOverride {
+        MetadataOverride {
+            fields: serde_json::from_value(fields).unwrap(),
+            time_offset_seconds,
+        }
+    }
+
+    #[test]
+    fn test_apply_set_and_clear() {
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("Canon".to_string());
+        metadata.settings.aperture = Some("f/5.6".to_string());
+        metadata.artist = Some("Someone".to_string());
+
+        let film = metadata_override(
+            json!({
+                "camera.lens_model": "Helios 44-2 58mm f/2",
+                "settings.aperture": "f/2.0",
+                "settings.iso": 400,
+                "artist": null,
+                "location.latitude": 35.0,
+                "location.longitude": 135.75,
+                "timestamp": "1987:06:01 10:00:00"
+            }),
+            None,
+        );
+        MetadataOverrideService::apply(&mut metadata, &film).unwrap();
+
+        assert_eq!(metadata.camera.make.as_deref(), Some("Canon"));
+        assert_eq!(metadata.camera.lens_model.as_deref(), Some("Helios 44-2 58mm f/2"));
+        assert_eq!(metadata.settings.iso, Some(400));
+        assert_eq!(metadata.artist, None);
+        assert_eq!(metadata.location.as_ref().unwrap().longitude, 135.75);
+
+        let capture_time = metadata.capture_time.as_ref().unwrap();
+        assert_eq!((capture_time.year, capture_time.source), (1987, TimestampSource::Override));
+        assert_eq!(metadata.field_sources.get("settings.aperture"), Some(&MetadataSource::Override));
+        assert!(!metadata.field_sources.contains_key("artist"));
+
+        let unknown = metadata_override(json!({ "camera.mount": "M42" }), None);
+        assert!(MetadataOverrideService::apply(&mut metadata, &unknown).is_err());
+        for key in ["setings.iso", "foo.bar", "camera.make.name"] {
+            let unknown_group = metadata_override(json!({ key: "x" }), None);
+            assert!(MetadataOverrideService::apply(&mut metadata, &unknown_group).is_err(), "{}", key);
+        }
+        let wrong_type = metadata_override(json!({ "settings.iso": "fast" }), None);
+        assert!(MetadataOverrideService::apply(&mut metadata, &wrong_type).is_err());
+    }
+
+    #[test]
+    fn test_time_offset_and_merge() {
+        let mut metadata = PhotoMetadata {
+            timestamp: Some("2024:01:01 00:30:00".to_string()),
+            capture_time: DateFormatter::parse_exif_string("2024:01:01 00:30:00", TimestampSource::DateTimeOriginal),
+            ..Default::default()
+        };
+
+        let batch = metadata_override(json!({ "artist": "Studio" }), Some(-3600));
+        let image = metadata_override(json!({ "artist": "Jane Doe" }), None);
+        let merged = MetadataOverrideService::merge(&batch, &image);
+        assert_eq!(merged.time_offset_seconds, Some(-3600));
+
+        MetadataOverrideService::apply(&mut metadata, &merged).unwrap();
+        assert_eq!(metadata.artist.as_deref(), Some("Jane Doe"));
+        assert_eq!(metadata.timestamp.as_deref(), Some("2023:12:31 23:30:00"));
+        assert_eq!(metadata.capture_time.as_ref().unwrap().source, TimestampSource::DateTimeOriginal);
+
+        // 超出范围的偏移报错，元数据保持不变
+        for offset in [i64::MAX, i64::MIN, 101 * 366 * 86_400] {
+            let invalid = metadata_override(json!({}), Some(offset));
+            assert!(MetadataOverrideService::apply(&mut metadata, &invalid).is_err());
+        }
+        assert_eq!(metadata.timestamp.as_deref(), Some("2023:12:31 23:30:00"));
+    }
+
+    #[test]
+    fn test_saved_override_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("scan.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+
+        let film = metadata_override(json!({ "camera.make": "Nikon", "camera.model": "FM2" }), None);
+        MetadataOverrideService::save(&image_path, &film).unwrap();
+        MetadataOverrideService::save(&image_path, &metadata_override(json!({ "settings.iso": 400 }), None)).unwrap();
+
+        let saved = MetadataOverrideService::load(&image_path).unwrap().unwrap();
+        assert_eq!(saved.fields.len(), 3);
+
+        // 提取时自动应用保存的覆盖
+        let metadata = crate::exif_service::ExifService::extract_metadata(&image_path).unwrap();
+        assert_eq!(metadata.camera.model.as_deref(), Some("FM2"));
+        assert_eq!(metadata.settings.iso, Some(400));
+        assert_eq!(metadata.field_sources.get("camera.model"), Some(&MetadataSource::Override));
+
+        // 胶片卷记录表在保存的覆盖之前应用，不会替换用户保存的值
+        let roll = metadata_override(json!({ "camera.model": "F3", "film.stock": "Kodak Tri-X 400" }), None);
+        let metadata = crate::exif_service::ExifService::extract_metadata_with_roll(&image_path, Some(&roll)).unwrap();
+        assert_eq!(metadata.camera.model.as_deref(), Some("FM2"));
+        assert_eq!(metadata.field_sources.get("film.stock"), Some(&MetadataSource::FilmRoll));
+
+        MetadataOverrideService::clear(&image_path).unwrap();
+        assert!(MetadataOverrideService::load(&image_path).unwrap().is_none());
+    }
+}
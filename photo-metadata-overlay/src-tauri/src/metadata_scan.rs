@@ -0,0 +1,293 @@
+use crate::exif_service::ExifService;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// 导出表中排在元数据字段之前的列
+const LEADING_COLUMNS: &[&str] = &["file_path", "has_exif", "error"];
+
+/// 单个文件的扫描结果
+pub struct ScanRecord {
+    pub file_path: String,
+    pub metadata: Option<PhotoMetadata>,
+    pub error: Option<String>,
+}
+
+impl ScanRecord {
+    /// 是否有字段来自EXIF（包括MakerNote）
+    pub fn has_exif(&self) -> bool {
+        self.metadata.as_ref().is_some_and(|metadata| {
+            metadata
+                .field_sources
+                .values()
+                .any(|source| matches!(source, MetadataSource::Exif | MetadataSource::MakerNote))
+        })
+    }
+}
+
+/// 批量元数据扫描
+/// 并行提取文件夹中所有图片的元数据，用于审计拍摄使用的机身、镜头、ISO分布等
+pub struct MetadataScanService;
+
+impl MetadataScanService {
+    /// 扫描文件夹并导出，每处理完一个文件调用一次 `on_progress`
+    /// `output_path` 以 .json 结尾时导出JSON，否则导出CSV
+    pub fn scan_folder<P: AsRef<Path>>(
+        folder: P,
+        recursive: bool,
+        output_path: Option<&Path>,
+        on_progress: impl Fn(ScanProgress) + Sync,
+    ) -> Result<MetadataScanSummary> {
+        let start_time = Instant::now();
+        let files = Self::list_images(folder.as_ref(), recursive)?;
+        println!("🔍 Scanning metadata of {} files in {:?}", files.len(), folder.as_ref());
+
+        let records = Self::scan(&files, on_progress);
+        if let Some(output_path) = output_path {
+            Self::export(&records, output_path)?;
+        }
+
+        let mut summary = Self::summarize(&records);
+        summary.output_path = output_path.map(|path| path.to_string_lossy().to_string());
+        summary.total_time_ms = start_time.elapsed().as_millis() as u64;
+        println!(
+            "✅ Scanned {} files ({} without EXIF, {} failed) in {}ms",
+            summary.total_files,
+            summary.without_exif.len(),
+            summary.failed.len(),
+            summary.total_time_ms
+        );
+        Ok(summary)
+    }
+
+    /// 列出文件夹中的图片（按扩展名筛选），结果按路径排序
+    pub fn list_images(folder: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![folder.to_path_buf()];
+
+        while let Some(directory) = pending.pop() {
+            let entries = std::fs::read_dir(&directory).with_context(|| format!("Failed to read folder: {:?}", directory))?;
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    if recursive {
+                        pending.push(path);
+                    }
+                } else if ExifService::validate_image_file(&path) {
+                    files.push(path);
+                }
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// 并行提取元数据，结果顺序与输入一致
+    pub fn scan(files: &[PathBuf], on_progress: impl Fn(ScanProgress) + Sync) -> Vec<ScanRecord> {
+        let processed = AtomicUsize::new(0);
+
+        files
+            .par_iter()
+            .map(|path| {
+                let file_path = path.to_string_lossy().to_string();
+                let (metadata, error) = match ExifService::extract_metadata(path) {
+                    Ok(metadata) => (Some(metadata), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+
+                on_progress(ScanProgress {
+                    processed: processed.fetch_add(1, Ordering::Relaxed) + 1,
+                    total: files.len(),
+                    current_file: file_path.clone(),
+                });
+                ScanRecord { file_path, metadata, error }
+            })
+            .collect()
+    }
+
+    /// 统计机身、镜头与ISO，记录没有EXIF与提取失败的文件
+    pub fn summarize(records: &[ScanRecord]) -> MetadataScanSummary {
+        let mut summary = MetadataScanSummary { total_files: records.len(), ..Default::default() };
+
+        for record in records {
+            if let Some(error) = &record.error {
+                summary.failed.push(ProcessingError {
+                    file_path: record.file_path.clone(),
+                    error_message: error.clone(),
+                    error_type: ErrorType::ExifReadError,
+                });
+            }
+            let Some(metadata) = &record.metadata else {
+                continue;
+            };
+            if !record.has_exif() {
+                summary.without_exif.push(record.file_path.clone());
+                continue;
+            }
+
+            summary.with_exif += 1;
+            if let Some(camera) = Self::camera_name(&metadata.camera) {
+                *summary.cameras.entry(camera).or_default() += 1;
+            }
+            if let Some(lens) = &metadata.camera.lens_model {
+                *summary.lenses.entry(lens.clone()).or_default() += 1;
+            }
+            if let Some(iso) = metadata.settings.iso {
+                *summary.iso.entry(iso).or_default() += 1;
+            }
+        }
+
+        summary
+    }
+
+    /// 品牌与型号（提取时已规范化），如 "Fujifilm X-T5"
+    fn camera_name(camera: &CameraInfo) -> Option<String> {
+        match (&camera.make, &camera.model) {
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.clone().or_else(|| model.clone()),
+        }
+    }
+
+    /// 导出为每个文件一行的表，列为所有文件中出现过的字段
+    pub fn export(records: &[ScanRecord], output_path: &Path) -> Result<()> {
+        let rows: Vec<Map<String, Value>> = records.iter().map(Self::row).collect();
+        let is_json = output_path
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("json"));
+
+        let content = if is_json {
+            serde_json::to_string_pretty(&rows)?
+        } else {
+            Self::to_csv(&rows)
+        };
+        std::fs::write(output_path, content).with_context(|| format!("Failed to write scan export: {:?}", output_path))?;
+        println!("💾 Exported {} rows to {:?}", rows.len(), output_path);
+        Ok(())
+    }
+
+    /// 单个文件的一行：路径、是否有EXIF、错误，以及展开后的所有字段
+    fn row(record: &ScanRecord) -> Map<String, Value> {
+        let mut row = Map::new();
+        row.insert("file_path".to_string(), Value::String(record.file_path.clone()));
+        row.insert("has_exif".to_string(), Value::Bool(record.has_exif()));
+        row.insert("error".to_string(), record.error.clone().map(Value::String).unwrap_or(Value::Null));
+        if let Some(metadata) = &record.metadata {
+            row.extend(MetadataFields::flatten(metadata));
+        }
+        row
+    }
+
+    fn to_csv(rows: &[Map<String, Value>]) -> String {
+        let fields: BTreeSet<&String> = rows
+            .iter()
+            .flat_map(|row| row.keys())
+            .filter(|key| !LEADING_COLUMNS.contains(&key.as_str()))
+            .collect();
+        let columns: Vec<&str> = LEADING_COLUMNS.iter().copied().chain(fields.into_iter().map(String::as_str)).collect();
+
+        let mut csv = Self::csv_line(columns.iter().map(|column| column.to_string()));
+        for row in rows {
+            csv.push_str(&Self::csv_line(columns.iter().map(|column| match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(text)) => text.clone(),
+                // 嵌套的对象与数组保留为JSON文本
+                Some(value) => value.to_string(),
+            })));
+        }
+        csv
+    }
+
+    fn csv_line(cells: impl Iterator<Item = String>) -> String {
+        let cells: Vec<String> = cells
+            .map(|cell| {
+                if cell.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell
+                }
+            })
+            .collect();
+        format!("{}\n", cells.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(file_path: &str, metadata: Option<PhotoMetadata>, error: Option<&str>) -> ScanRecord {
+        ScanRecord { file_path: file_path.to_string(), metadata, error: error.map(str::to_string) }
+    }
+
+    fn exif_metadata(model: &str, lens: &str, iso: u32) -> PhotoMetadata {
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("FUJIFILM".to_string());
+        metadata.camera.model = Some(model.to_string());
+        metadata.camera.lens_model = Some(lens.to_string());
+        metadata.settings.iso = Some(iso);
+        metadata.field_sources.insert("camera.make".to_string(), MetadataSource::Exif);
+        metadata
+    }
+
+    #[test]
+    fn test_summarize_and_export() {
+        let records = vec![
+            record("a.jpg", Some(exif_metadata("X-T5", "XF23mmF1.4 R LM WR", 400)), None),
+            record("b.jpg", Some(exif_metadata("X-T5", "XF56mmF1.2 R", 400)), None),
+            record("scan.png", Some(PhotoMetadata::default()), None),
+            record("broken.jpg", None, Some("Failed to open image")),
+        ];
+
+        let summary = MetadataScanService::summarize(&records);
+        assert_eq!((summary.total_files, summary.with_exif), (4, 2));
+        assert_eq!(summary.without_exif, vec!["scan.png"]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.cameras.get("FUJIFILM X-T5"), Some(&2));
+        assert_eq!(summary.iso.get(&400), Some(&2));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv_path = temp_dir.path().join("audit.csv");
+        MetadataScanService::export(&records, &csv_path).unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("file_path,has_exif,error,camera.lens_model,camera.make,camera.model"));
+        assert!(lines[1].starts_with("a.jpg,true,,XF23mmF1.4 R LM WR,FUJIFILM,X-T5"));
+        assert!(lines[3].starts_with("scan.png,false,,"));
+
+        let json_path = temp_dir.path().join("audit.json");
+        MetadataScanService::export(&records, &json_path).unwrap();
+        let rows: Vec<Value> = serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(rows[3]["error"], "Failed to open image");
+        assert_eq!(rows[0]["settings.iso"], 400);
+    }
+
+    #[test]
+    fn test_scan_folder_reports_progress() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("day2")).unwrap();
+        for name in ["1.png", "2.png", "day2/3.png"] {
+            image::RgbImage::new(4, 4).save(temp_dir.path().join(name)).unwrap();
+        }
+        std::fs::write(temp_dir.path().join("notes.txt"), "not an image").unwrap();
+
+        let progress = AtomicUsize::new(0);
+        let summary = MetadataScanService::scan_folder(temp_dir.path(), true, None, |update| {
+            assert_eq!(update.total, 3);
+            progress.fetch_add(1, Ordering::Relaxed);
+        })
+        .unwrap();
+        assert_eq!(progress.into_inner(), 3);
+        assert_eq!((summary.total_files, summary.without_exif.len()), (3, 3));
+
+        let top_level = MetadataScanService::list_images(temp_dir.path(), false).unwrap();
+        assert_eq!(top_level.len(), 2);
+    }
+}
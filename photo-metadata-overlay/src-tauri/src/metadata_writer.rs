@@ -0,0 +1,679 @@
+use crate::iptc_service::{IptcService, PHOTOSHOP_HEADER};
+use crate::raw_service::RawService;
+use crate::tiff_reader::TiffReader;
+use crate::xmp_service::XmpService;
+use anyhow::{Context, Result};
+use exif::experimental::Writer;
+use exif::{Field, In, Tag, Value};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use image::ImageFormat;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+/// JPEG中APP1段的EXIF标识
+pub const EXIF_HEADER: &[u8] = b"Exif\0\0";
+/// JPEG中APP1段的XMP标识
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// JPEG中APP2段的ICC标识
+pub const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+/// PNG中XMP所用的iTXt关键字
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+/// PNG文件签名
+pub const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// 单个JPEG段的最大数据长度（不含长度字段）
+const MAX_SEGMENT_DATA: usize = 65533;
+
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_LENGTH: u16 = 0x0101;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_EXIF_IFD_POINTER: u16 = 0x8769;
+const TAG_PIXEL_X_DIMENSION: u16 = 0xA002;
+const TAG_PIXEL_Y_DIMENSION: u16 = 0xA003;
+
+/// 从TIFF/RAW重建EXIF时保留的IFD0标签，条带、SubIFD等结构性标签不能照搬
+const PRESERVED_TIFF_TAGS: &[Tag] = &[
+    Tag::ImageDescription,
+    Tag::Make,
+    Tag::Model,
+    Tag::XResolution,
+    Tag::YResolution,
+    Tag::ResolutionUnit,
+    Tag::Software,
+    Tag::DateTime,
+    Tag::Artist,
+    Tag::Copyright,
+];
+
+/// 源文件中需要保留的元数据块
+#[derive(Debug, Clone, Default)]
+pub struct SourceMetadata {
+    /// TIFF格式的EXIF数据（不含 "Exif\0\0" 前缀）
+    pub exif: Option<Vec<u8>>,
+    /// XMP数据包
+    pub xmp: Option<Vec<u8>>,
+    /// ICC颜色配置文件
+    pub icc: Option<Vec<u8>>,
+    /// IPTC-IIM数据集
+    pub iptc: Option<Vec<u8>>,
+}
+
+/// 元数据写入服务
+/// 负责读取源文件的EXIF/XMP/ICC/IPTC，并嵌入到已编码的JPEG/PNG字节流中
+pub struct MetadataWriter;
+
+impl MetadataWriter {
+    /// 读取源文件中的EXIF、XMP、ICC和IPTC数据，读取失败时返回空结果
+    pub fn read_source_metadata<P: AsRef<Path>>(file_path: P) -> SourceMetadata {
+        let Ok(data) = std::fs::read(&file_path) else {
+            return SourceMetadata::default();
+        };
+
+        if data.starts_with(&[0xFF, 0xD8]) {
+            Self::read_jpeg_metadata(&data)
+        } else if data.starts_with(PNG_SIGNATURE) {
+            Self::read_png_metadata(&data)
+        } else if TiffReader::new(&data).is_some() || RawService::is_raw_file(&file_path) {
+            Self::read_tiff_metadata(&file_path)
+        } else {
+            SourceMetadata::default()
+        }
+    }
+
+    /// 将源元数据写入已编码的图片
+    /// EXIF中的方向改为1，像素尺寸更新为输出尺寸，并移除已过时的缩略图
+    pub fn write_metadata(
+        encoded: Vec<u8>,
+        format: ImageFormat,
+        source: &SourceMetadata,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>> {
+        let mut exif_data = match &source.exif {
+            Some(exif_data) => {
+                let mut exif_data = exif_data.clone();
+                Self::patch_exif(&mut exif_data, width, height);
+                exif_data
+            }
+            None => Self::build_orientation_exif()?,
+        };
+        let xmp = source.xmp.as_deref().and_then(|xmp| Self::patch_xmp(xmp, width, height));
+
+        match format {
+            ImageFormat::Jpeg => {
+                // EXIF只能放在单个APP1段中，超长时先去掉缩略图和MakerNote
+                if EXIF_HEADER.len() + exif_data.len() > MAX_SEGMENT_DATA {
+                    let original_length = exif_data.len();
+                    exif_data = Self::shrink_exif(&exif_data)?;
+                    println!("⚠️ EXIF too large ({} bytes), shrunk to {} bytes", original_length, exif_data.len());
+                    if EXIF_HEADER.len() + exif_data.len() > MAX_SEGMENT_DATA {
+                        return Err(anyhow::anyhow!(
+                            "EXIF data too large to embed in JPEG ({} bytes)",
+                            exif_data.len()
+                        ));
+                    }
+                }
+
+                let mut segments = Vec::new();
+                Self::push_jpeg_segment(&mut segments, 0xE1, &[EXIF_HEADER, &exif_data]);
+                if let Some(xmp) = &xmp {
+                    Self::push_jpeg_segment(&mut segments, 0xE1, &[XMP_HEADER, xmp]);
+                }
+                if let Some(icc) = &source.icc {
+                    Self::push_icc_segments(&mut segments, icc);
+                }
+                if let Some(iptc) = &source.iptc {
+                    Self::push_jpeg_segment(&mut segments, 0xED, &[&IptcService::photoshop_segment(iptc)]);
+                }
+                Self::insert_jpeg_segments(encoded, &segments)
+            }
+            ImageFormat::Png => {
+                let mut output = Self::insert_png_chunk(encoded, b"eXIf", &exif_data)?;
+                if let Some(xmp) = &xmp {
+                    let mut itxt = PNG_XMP_KEYWORD.to_vec();
+                    // 关键字结束符、不压缩、压缩方法、空语言标签、空翻译关键字
+                    itxt.extend_from_slice(&[0, 0, 0, 0, 0]);
+                    itxt.extend_from_slice(xmp);
+                    output = Self::insert_png_chunk(output, b"iTXt", &itxt)?;
+                }
+                if let Some(icc) = &source.icc {
+                    let mut iccp = b"ICC Profile\0\0".to_vec();
+                    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                    encoder.write_all(icc)?;
+                    iccp.extend_from_slice(&encoder.finish()?);
+                    output = Self::insert_png_chunk(output, b"iCCP", &iccp)?;
+                }
+                Ok(output)
+            }
+            _ => Ok(encoded),
+        }
+    }
+
+    /// 生成只包含 Orientation=1 的最小EXIF（TIFF格式）
+    /// 图片在处理前已按方向标签摆正，输出必须声明为正常方向
+    pub fn build_orientation_exif() -> Result<Vec<u8>> {
+        let orientation = Field {
+            tag: Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![1]),
+        };
+
+        let mut writer = Writer::new();
+        writer.push_field(&orientation);
+
+        let mut buffer = Cursor::new(Vec::new());
+        writer
+            .write(&mut buffer, false)
+            .with_context(|| "Failed to encode EXIF data")?;
+
+        Ok(buffer.into_inner())
+    }
+
+    /// 读取JPEG中的APP1(EXIF/XMP)、APP2(ICC)与APP13(IPTC)段
+    fn read_jpeg_metadata(data: &[u8]) -> SourceMetadata {
+        let mut metadata = SourceMetadata::default();
+        let mut icc_chunks: Vec<(u8, Vec<u8>)> = Vec::new();
+
+        for (marker, payload) in Self::jpeg_segments(data) {
+            match marker {
+                0xE1 if payload.starts_with(EXIF_HEADER) && metadata.exif.is_none() => {
+                    metadata.exif = Some(payload[EXIF_HEADER.len()..].to_vec());
+                }
+                0xE1 if payload.starts_with(XMP_HEADER) && metadata.xmp.is_none() => {
+                    metadata.xmp = Some(payload[XMP_HEADER.len()..].to_vec());
+                }
+                0xED if payload.starts_with(PHOTOSHOP_HEADER) && metadata.iptc.is_none() => {
+                    metadata.iptc = IptcService::photoshop_iptc(&payload[PHOTOSHOP_HEADER.len()..]).map(<[u8]>::to_vec);
+                }
+                0xE2 if payload.starts_with(ICC_HEADER) && payload.len() > ICC_HEADER.len() + 2 => {
+                    let sequence = payload[ICC_HEADER.len()];
+                    icc_chunks.push((sequence, payload[ICC_HEADER.len() + 2..].to_vec()));
+                }
+                _ => {}
+            }
+        }
+
+        if !icc_chunks.is_empty() {
+            icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+            metadata.icc = Some(icc_chunks.into_iter().flat_map(|(_, chunk)| chunk).collect());
+        }
+
+        metadata
+    }
+
+    /// 遍历JPEG在图像数据（SOS）之前的所有标记段
+    pub fn jpeg_segments(data: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut segments = Vec::new();
+        let mut position = 2;
+
+        while position + 4 <= data.len() && data[position] == 0xFF {
+            let marker = data[position + 1];
+            if marker == 0xDA || marker == 0xD9 {
+                break;
+            }
+            let length = u16::from_be_bytes([data[position + 2], data[position + 3]]) as usize;
+            let Some(payload) = data.get(position + 4..position + 2 + length) else {
+                break;
+            };
+            segments.push((marker, payload));
+            position += 2 + length;
+        }
+
+        segments
+    }
+
+    /// 读取PNG中的eXIf、iTXt(XMP)与iCCP块
+    fn read_png_metadata(data: &[u8]) -> SourceMetadata {
+        let mut metadata = SourceMetadata::default();
+
+        for (chunk_type, payload) in Self::png_chunks(data) {
+            match &chunk_type {
+                b"eXIf" => metadata.exif = Some(payload.to_vec()),
+                b"iTXt" if payload.starts_with(PNG_XMP_KEYWORD) => {
+                    metadata.xmp = Self::parse_itxt_text(&payload[PNG_XMP_KEYWORD.len()..]);
+                }
+                b"iCCP" => {
+                    let Some(name_end) = payload.iter().position(|&b| b == 0) else {
+                        continue;
+                    };
+                    // 名称、结束符、压缩方法之后是zlib数据
+                    let compressed = payload.get(name_end + 2..).unwrap_or_default();
+                    let mut profile = Vec::new();
+                    if ZlibDecoder::new(compressed).read_to_end(&mut profile).is_ok() {
+                        metadata.icc = Some(profile);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        metadata
+    }
+
+    /// 遍历PNG的所有块
+    pub fn png_chunks(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut chunks = Vec::new();
+        let mut position = PNG_SIGNATURE.len();
+
+        while position + 8 <= data.len() {
+            let length = u32::from_be_bytes([data[position], data[position + 1], data[position + 2], data[position + 3]]) as usize;
+            let chunk_type = [data[position + 4], data[position + 5], data[position + 6], data[position + 7]];
+            let Some(payload) = data.get(position + 8..position + 8 + length) else {
+                break;
+            };
+            chunks.push((chunk_type, payload));
+            if &chunk_type == b"IEND" {
+                break;
+            }
+            position += 12 + length;
+        }
+
+        chunks
+    }
+
+    /// 解析iTXt中关键字之后的部分，返回文本内容
+    fn parse_itxt_text(rest: &[u8]) -> Option<Vec<u8>> {
+        // 关键字结束符、压缩标志、压缩方法
+        let compressed = *rest.get(1)? == 1;
+        let rest = rest.get(3..)?;
+        let language_end = rest.iter().position(|&b| b == 0)?;
+        let rest = &rest[language_end + 1..];
+        let translated_end = rest.iter().position(|&b| b == 0)?;
+        let text = &rest[translated_end + 1..];
+
+        if compressed {
+            let mut decoded = Vec::new();
+            ZlibDecoder::new(text).read_to_end(&mut decoded).ok()?;
+            Some(decoded)
+        } else {
+            Some(text.to_vec())
+        }
+    }
+
+    /// TIFF/RAW的EXIF与图像数据交织在一起，需要用解析后的字段重建
+    fn read_tiff_metadata<P: AsRef<Path>>(file_path: P) -> SourceMetadata {
+        let sources = if RawService::is_raw_file(&file_path) {
+            RawService::read_exif(&file_path).unwrap_or_default()
+        } else {
+            std::fs::read(&file_path)
+                .ok()
+                .and_then(|data| exif::Reader::new().read_raw(data).ok())
+                .into_iter()
+                .collect()
+        };
+
+        let mut metadata = SourceMetadata::default();
+        let mut fields: Vec<&Field> = Vec::new();
+
+        for exif_data in &sources {
+            for field in exif_data.fields() {
+                if field.ifd_num != In::PRIMARY {
+                    continue;
+                }
+                match field.tag {
+                    // XMP、ICC 与 IPTC 以TIFF标签形式存放
+                    Tag(exif::Context::Tiff, 700) => {
+                        if let Value::Byte(bytes) | Value::Undefined(bytes, _) = &field.value {
+                            metadata.xmp = Some(bytes.clone());
+                        }
+                    }
+                    Tag(exif::Context::Tiff, 34675) => {
+                        if let Value::Undefined(bytes, _) | Value::Byte(bytes) = &field.value {
+                            metadata.icc = Some(bytes.clone());
+                        }
+                    }
+                    // IPTC-NAA标签常被写成LONG类型，需按文件字节序还原
+                    Tag(exif::Context::Tiff, 33723) => match &field.value {
+                        Value::Undefined(bytes, _) | Value::Byte(bytes) => metadata.iptc = Some(bytes.clone()),
+                        Value::Long(values) => {
+                            let bytes = values
+                                .iter()
+                                .flat_map(|value| {
+                                    if exif_data.little_endian() {
+                                        value.to_le_bytes()
+                                    } else {
+                                        value.to_be_bytes()
+                                    }
+                                })
+                                .collect();
+                            metadata.iptc = Some(bytes);
+                        }
+                        _ => {}
+                    },
+                    Tag(exif::Context::Tiff, 34377) if metadata.iptc.is_none() => {
+                        if let Value::Byte(bytes) | Value::Undefined(bytes, _) = &field.value {
+                            metadata.iptc = IptcService::photoshop_iptc(bytes).map(<[u8]>::to_vec);
+                        }
+                    }
+                    // 厂商私有数据中的偏移在重建后会失效
+                    Tag::MakerNote => {}
+                    Tag(exif::Context::Exif, _) | Tag(exif::Context::Gps, _) => fields.push(field),
+                    tag if PRESERVED_TIFF_TAGS.contains(&tag) => fields.push(field),
+                    _ => {}
+                }
+            }
+        }
+
+        if !fields.is_empty() {
+            let mut writer = Writer::new();
+            for field in fields {
+                writer.push_field(field);
+            }
+            let mut buffer = Cursor::new(Vec::new());
+            if writer.write(&mut buffer, false).is_ok() {
+                metadata.exif = Some(buffer.into_inner());
+            }
+        }
+
+        metadata
+    }
+
+    /// 就地修改EXIF：方向设为1，更新像素尺寸，并断开指向缩略图IFD的链接
+    fn patch_exif(tiff: &mut [u8], width: u32, height: u32) {
+        let mut writes: Vec<(usize, u16, u32)> = Vec::new();
+        let mut ifd1_link = None;
+
+        if let Some(reader) = TiffReader::new(tiff) {
+            let Some(ifd0) = reader.first_ifd_offset() else {
+                return;
+            };
+            let Some(entries) = reader.ifd_entries(ifd0) else {
+                return;
+            };
+
+            for entry in &entries {
+                match entry.tag {
+                    TAG_ORIENTATION => writes.push((entry.value_position, entry.field_type, 1)),
+                    TAG_IMAGE_WIDTH => writes.push((entry.value_position, entry.field_type, width)),
+                    TAG_IMAGE_LENGTH => writes.push((entry.value_position, entry.field_type, height)),
+                    TAG_EXIF_IFD_POINTER => {
+                        let exif_ifd = reader.entry_u32_values(entry).first().copied().unwrap_or(0) as usize;
+                        for exif_entry in reader.ifd_entries(exif_ifd).unwrap_or_default() {
+                            match exif_entry.tag {
+                                TAG_PIXEL_X_DIMENSION => writes.push((exif_entry.value_position, exif_entry.field_type, width)),
+                                TAG_PIXEL_Y_DIMENSION => writes.push((exif_entry.value_position, exif_entry.field_type, height)),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            ifd1_link = Some(ifd0 + 2 + entries.len() * 12);
+        }
+
+        let little_endian = tiff.starts_with(b"II");
+        for (position, field_type, value) in writes {
+            match field_type {
+                3 if value <= u16::MAX as u32 => {
+                    let bytes = if little_endian { (value as u16).to_le_bytes() } else { (value as u16).to_be_bytes() };
+                    tiff[position..position + 2].copy_from_slice(&bytes);
+                }
+                4 => {
+                    let bytes = if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+                    tiff[position..position + 4].copy_from_slice(&bytes);
+                }
+                _ => {}
+            }
+        }
+
+        // 原缩略图未包含叠加层且方向已变，直接移除
+        if let Some(link) = ifd1_link {
+            if link + 4 <= tiff.len() {
+                tiff[link..link + 4].copy_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    /// 与EXIF相同，XMP中的方向设为1、像素尺寸更新为输出尺寸
+    /// 无法解析的XMP不再写入，避免其中过时的方向让图片被再次旋转
+    fn patch_xmp(xmp: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+        let values = [
+            ("tiff:Orientation", "1".to_string()),
+            ("exif:PixelXDimension", width.to_string()),
+            ("exif:PixelYDimension", height.to_string()),
+        ];
+        let patched = std::str::from_utf8(xmp)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| XmpService::replace_values(content, &values));
+
+        match patched {
+            Ok(patched) => Some(patched.into_bytes()),
+            Err(e) => {
+                println!("⚠️ Failed to patch XMP, skipped: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 用主IFD的字段重建EXIF，丢弃缩略图IFD与MakerNote
+    /// 重建时指针和条带偏移由写入器重新生成
+    fn shrink_exif(tiff: &[u8]) -> Result<Vec<u8>> {
+        let exif_data = exif::Reader::new()
+            .read_raw(tiff.to_vec())
+            .with_context(|| "Failed to parse EXIF data")?;
+
+        let mut writer = Writer::new();
+        for field in exif_data.fields() {
+            if field.ifd_num == In::PRIMARY && field.tag != Tag::MakerNote {
+                writer.push_field(field);
+            }
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        writer
+            .write(&mut buffer, exif_data.little_endian())
+            .with_context(|| "Failed to encode EXIF data")?;
+
+        Ok(buffer.into_inner())
+    }
+
+    /// 组装一个JPEG标记段，超出单段长度上限时丢弃并记录
+    fn push_jpeg_segment(segments: &mut Vec<u8>, marker: u8, parts: &[&[u8]]) {
+        let length: usize = parts.iter().map(|part| part.len()).sum();
+        if length > MAX_SEGMENT_DATA {
+            println!("⚠️ Metadata segment too large ({} bytes), skipped", length);
+            return;
+        }
+
+        segments.extend_from_slice(&[0xFF, marker]);
+        segments.extend_from_slice(&((length + 2) as u16).to_be_bytes());
+        for part in parts {
+            segments.extend_from_slice(part);
+        }
+    }
+
+    /// ICC配置文件按APP2分段写入，每段带序号和总段数
+    fn push_icc_segments(segments: &mut Vec<u8>, icc: &[u8]) {
+        let chunk_size = MAX_SEGMENT_DATA - ICC_HEADER.len() - 2;
+        let chunks: Vec<&[u8]> = icc.chunks(chunk_size).collect();
+        if chunks.len() > u8::MAX as usize {
+            println!("⚠️ ICC profile too large ({} bytes), skipped", icc.len());
+            return;
+        }
+
+        let total = chunks.len() as u8;
+        for (index, chunk) in chunks.iter().enumerate() {
+            let sequence = [index as u8 + 1, total];
+            Self::push_jpeg_segment(segments, 0xE2, &[ICC_HEADER, &sequence, chunk]);
+        }
+    }
+
+    /// 在JPEG的SOI/APP0之后插入标记段
+    fn insert_jpeg_segments(encoded: Vec<u8>, segments: &[u8]) -> Result<Vec<u8>> {
+        if encoded.len() < 4 || encoded[0..2] != [0xFF, 0xD8] {
+            return Err(anyhow::anyhow!("Encoded data is not a JPEG stream"));
+        }
+
+        // JFIF规定APP0必须紧跟SOI，元数据段放在其后
+        let mut insert_at = 2;
+        if encoded[2..4] == [0xFF, 0xE0] && encoded.len() >= 6 {
+            let app0_length = u16::from_be_bytes([encoded[4], encoded[5]]) as usize;
+            insert_at = (4 + app0_length).min(encoded.len());
+        }
+
+        let mut output = Vec::with_capacity(encoded.len() + segments.len());
+        output.extend_from_slice(&encoded[..insert_at]);
+        output.extend_from_slice(segments);
+        output.extend_from_slice(&encoded[insert_at..]);
+
+        Ok(output)
+    }
+
+    /// 在IHDR块之后插入一个PNG块（eXIf/iCCP规范要求位于IDAT之前）
+    fn insert_png_chunk(encoded: Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) -> Result<Vec<u8>> {
+        if encoded.len() < 16 || &encoded[..8] != PNG_SIGNATURE || &encoded[12..16] != b"IHDR" {
+            return Err(anyhow::anyhow!("Encoded data is not a PNG stream"));
+        }
+
+        let ihdr_length = u32::from_be_bytes([encoded[8], encoded[9], encoded[10], encoded[11]]) as usize;
+        // 签名(8) + 长度(4) + 类型(4) + 数据 + CRC(4)
+        let insert_at = 8 + 12 + ihdr_length;
+        if insert_at > encoded.len() {
+            return Err(anyhow::anyhow!("Truncated PNG header"));
+        }
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+        let crc = hasher.finalize();
+
+        let mut output = Vec::with_capacity(encoded.len() + data.len() + 12);
+        output.extend_from_slice(&encoded[..insert_at]);
+        output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        output.extend_from_slice(chunk_type);
+        output.extend_from_slice(data);
+        output.extend_from_slice(&crc.to_be_bytes());
+        output.extend_from_slice(&encoded[insert_at..]);
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+    fn encode(format: ImageOutputFormat) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(4, 3));
+        let mut buffer = Vec::new();
+        img.write_to(&mut Cursor::new(&mut buffer), format).unwrap();
+        buffer
+    }
+
+    fn read_exif(data: &[u8]) -> exif::Exif {
+        exif::Reader::new()
+            .read_from_container(&mut Cursor::new(data))
+            .unwrap()
+    }
+
+    fn uint_field(exif_data: &exif::Exif, tag: Tag) -> Option<u32> {
+        exif_data
+            .get_field(tag, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+    }
+
+    /// 方向写在XML属性上、像素尺寸写成子元素的XMP
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+ <rdf:Description rdf:about="" xmlns:tiff="http://ns.adobe.com/tiff/1.0/" tiff:Orientation="6"/>
+ <rdf:Description rdf:about="" xmlns:exif="http://ns.adobe.com/exif/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <exif:PixelXDimension>6000</exif:PixelXDimension>
+  <exif:PixelYDimension>4000</exif:PixelYDimension>
+  <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Title</rdf:li></rdf:Alt></dc:title>
+ </rdf:Description>
+</rdf:RDF></x:xmpmeta>"#;
+
+    /// 构造带方向、像素尺寸和版权的源EXIF
+    fn source_exif() -> Vec<u8> {
+        build_exif(&[])
+    }
+
+    fn build_exif(extra_fields: &[Field]) -> Vec<u8> {
+        let fields = [
+            Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![6]) },
+            Field { tag: Tag::Copyright, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"Jane Doe".to_vec()]) },
+            Field { tag: Tag::PixelXDimension, ifd_num: In::PRIMARY, value: Value::Long(vec![6000]) },
+            Field { tag: Tag::PixelYDimension, ifd_num: In::PRIMARY, value: Value::Long(vec![4000]) },
+        ];
+        let mut writer = Writer::new();
+        for field in fields.iter().chain(extra_fields) {
+            writer.push_field(field);
+        }
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, true).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_orientation_only_when_no_source_metadata() {
+        let source = SourceMetadata::default();
+
+        let jpeg = MetadataWriter::write_metadata(encode(ImageOutputFormat::Jpeg(90)), ImageFormat::Jpeg, &source, 4, 3).unwrap();
+        assert_eq!(uint_field(&read_exif(&jpeg), Tag::Orientation), Some(1));
+        assert!(image::load_from_memory(&jpeg).is_ok());
+
+        let png = MetadataWriter::write_metadata(encode(ImageOutputFormat::Png), ImageFormat::Png, &source, 4, 3).unwrap();
+        assert_eq!(uint_field(&read_exif(&png), Tag::Orientation), Some(1));
+        assert!(image::load_from_memory(&png).is_ok());
+    }
+
+    #[test]
+    fn test_preserve_and_rewrite_round_trip() {
+        let source = SourceMetadata {
+            exif: Some(source_exif()),
+            xmp: Some(XMP.as_bytes().to_vec()),
+            icc: Some(vec![7u8; 70_000]),
+            iptc: Some(b"\x1C\x02\x69\x00\x05Title".to_vec()),
+        };
+
+        for (format, output_format) in [
+            (ImageFormat::Jpeg, ImageOutputFormat::Jpeg(90)),
+            (ImageFormat::Png, ImageOutputFormat::Png),
+        ] {
+            let output = MetadataWriter::write_metadata(encode(output_format), format, &source, 4000, 6000).unwrap();
+            assert!(image::load_from_memory(&output).is_ok());
+
+            let exif_data = read_exif(&output);
+            assert_eq!(uint_field(&exif_data, Tag::Orientation), Some(1));
+            assert_eq!(uint_field(&exif_data, Tag::PixelXDimension), Some(4000));
+            assert_eq!(uint_field(&exif_data, Tag::PixelYDimension), Some(6000));
+            assert!(exif_data.get_field(Tag::Copyright, In::PRIMARY).is_some());
+
+            // 写出的文件再次读取应得到相同的XMP与ICC
+            let temp_dir = tempfile::tempdir().unwrap();
+            let path = temp_dir.path().join("output.bin");
+            std::fs::write(&path, &output).unwrap();
+            let reread = MetadataWriter::read_source_metadata(&path);
+            let xmp = String::from_utf8(reread.xmp.unwrap()).unwrap();
+            assert!(xmp.contains(r#"tiff:Orientation="1""#));
+            assert!(xmp.contains("<exif:PixelXDimension>4000</exif:PixelXDimension>"));
+            assert!(xmp.contains("<exif:PixelYDimension>6000</exif:PixelYDimension>"));
+            assert!(xmp.contains("<dc:title>"));
+            assert_eq!(reread.icc, source.icc);
+            // PNG没有标准的IPTC块，只有JPEG保留IPTC
+            if format == ImageFormat::Jpeg {
+                assert_eq!(reread.iptc, source.iptc);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_oversized_exif() {
+        let maker_note = Field { tag: Tag::MakerNote, ifd_num: In::PRIMARY, value: Value::Undefined(vec![0; 70_000], 0) };
+        let source = SourceMetadata { exif: Some(build_exif(&[maker_note])), ..Default::default() };
+
+        let jpeg = MetadataWriter::write_metadata(encode(ImageOutputFormat::Jpeg(90)), ImageFormat::Jpeg, &source, 4000, 6000).unwrap();
+        let exif_data = read_exif(&jpeg);
+        assert!(exif_data.get_field(Tag::MakerNote, In::PRIMARY).is_none());
+        assert!(exif_data.get_field(Tag::Copyright, In::PRIMARY).is_some());
+        assert_eq!(uint_field(&exif_data, Tag::Orientation), Some(1));
+        assert_eq!(uint_field(&exif_data, Tag::PixelXDimension), Some(4000));
+
+        // 去掉MakerNote后仍然超长时报错，而不是静默丢弃EXIF
+        let comment = Field { tag: Tag::UserComment, ifd_num: In::PRIMARY, value: Value::Undefined(vec![b' '; 70_000], 0) };
+        let source = SourceMetadata { exif: Some(build_exif(&[comment])), ..Default::default() };
+        assert!(MetadataWriter::write_metadata(encode(ImageOutputFormat::Jpeg(90)), ImageFormat::Jpeg, &source, 4000, 6000).is_err());
+    }
+}
@@ -0,0 +1,571 @@
+use crate::tiff_reader::TiffReader;
+use anyhow::{Context, Result};
+use std::io::Cursor;
+use std::path::Path;
+
+/// 支持的RAW文件扩展名
+pub const RAW_EXTENSIONS: &[&str] = &["dng", "cr2", "cr3", "nef", "arw", "raf", "orf"];
+
+/// RAW容器类型（按文件内容判断）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RawContainer {
+    /// 标准TIFF结构：DNG、CR2、NEF、ARW
+    Tiff,
+    /// Olympus ORF：TIFF结构，但头部魔数为 IIRO/IIRS/MMOR
+    Orf,
+    /// Fujifilm RAF：专有头部，内嵌带EXIF的JPEG
+    Raf,
+    /// Canon CR3：ISO基础媒体文件格式，元数据位于CMT*盒中
+    Cr3,
+}
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+const TAG_EXIF_IFD_POINTER: u16 = 0x8769;
+const TAG_GPS_IFD_POINTER: u16 = 0x8825;
+const TAG_MAKER_NOTE: u16 = 0x927C;
+
+/// CR3中存放CMT*元数据盒的uuid盒（位于moov内）
+const CR3_METADATA_UUID: [u8; 16] = [
+    0x85, 0xC0, 0xB6, 0x87, 0x82, 0x0F, 0x11, 0xE0, 0x81, 0x11, 0xF4, 0xCE, 0x46, 0x2B, 0x6A, 0x48,
+];
+/// CR3中存放PRVW预览盒的顶层uuid盒
+const CR3_PREVIEW_UUID: [u8; 16] = [
+    0xEA, 0xF4, 0x2B, 0x5E, 0x1C, 0x98, 0x4B, 0x88, 0xB9, 0xFB, 0xB7, 0xDC, 0x40, 0x6E, 0x4D, 0x16,
+];
+
+/// Olympus MakerNote中的CameraSettings子IFD及其中的预览图位置
+const TAG_OLYMPUS_CAMERA_SETTINGS: u16 = 0x2020;
+const TAG_OLYMPUS_PREVIEW_START: u16 = 0x0101;
+const TAG_OLYMPUS_PREVIEW_LENGTH: u16 = 0x0102;
+
+/// RAW文件服务
+/// 提供RAW的EXIF读取和内嵌JPEG预览提取
+pub struct RawService;
+
+impl RawService {
+    /// 判断是否为RAW文件扩展名
+    pub fn is_raw_file<P: AsRef<Path>>(file_path: P) -> bool {
+        file_path
+            .as_ref()
+            .extension()
+            .map(|ext| RAW_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+
+    /// 按文件头判断是否为头部魔数非标准TIFF的RAW容器（RAF、CR3、ORF）
+    /// DNG、CR2、NEF、ARW与普通TIFF的头部相同，需要结合扩展名判断
+    pub fn is_raw_data(data: &[u8]) -> bool {
+        Self::detect_container(data).is_some_and(|container| container != RawContainer::Tiff)
+    }
+
+    /// 读取RAW文件中的EXIF数据
+    /// CR3的基本信息和拍摄参数分别存放在不同的盒中，因此可能返回多个来源
+    pub fn read_exif<P: AsRef<Path>>(file_path: P) -> Result<Vec<exif::Exif>> {
+        let data = std::fs::read(&file_path)
+            .with_context(|| format!("Failed to read RAW file: {:?}", file_path.as_ref()))?;
+        Self::read_exif_data(data).with_context(|| format!("Failed to read EXIF from {:?}", file_path.as_ref()))
+    }
+
+    /// 从已读入内存的RAW数据中读取EXIF
+    pub fn read_exif_data(data: Vec<u8>) -> Result<Vec<exif::Exif>> {
+        let container = Self::detect_container(&data).ok_or_else(|| anyhow::anyhow!("Unrecognized RAW container"))?;
+
+        let reader = exif::Reader::new();
+        let sources = match container {
+            RawContainer::Tiff => vec![reader.read_raw(data)?],
+            RawContainer::Orf => vec![reader.read_raw(Self::normalize_orf_header(data))?],
+            RawContainer::Raf => {
+                let jpeg = Self::raf_embedded_jpeg(&data)
+                    .ok_or_else(|| anyhow::anyhow!("RAF file has no embedded JPEG"))?;
+                vec![reader.read_from_container(&mut Cursor::new(jpeg))?]
+            }
+            RawContainer::Cr3 => {
+                let mut sources = Vec::new();
+                if let Some(ifd0) = Self::cr3_metadata_box(&data, b"CMT1") {
+                    sources.push(reader.read_raw(ifd0.to_vec())?);
+                }
+                if let Some(exif_ifd) = Self::cr3_metadata_box(&data, b"CMT2") {
+                    let wrapped = Self::wrap_sub_ifd(exif_ifd, TAG_EXIF_IFD_POINTER)?;
+                    sources.push(reader.read_raw(wrapped)?);
+                }
+                if let Some(gps_ifd) = Self::cr3_metadata_box(&data, b"CMT4") {
+                    let wrapped = Self::wrap_sub_ifd(gps_ifd, TAG_GPS_IFD_POINTER)?;
+                    sources.push(reader.read_raw(wrapped)?);
+                }
+                sources
+            }
+        };
+
+        if sources.is_empty() {
+            return Err(anyhow::anyhow!("No EXIF data found in RAW file"));
+        }
+
+        Ok(sources)
+    }
+
+    /// 提取RAW文件中最大的内嵌JPEG预览，作为渲染源
+    pub fn extract_preview<P: AsRef<Path>>(file_path: P) -> Result<Vec<u8>> {
+        let data = std::fs::read(&file_path)
+            .with_context(|| format!("Failed to read RAW file: {:?}", file_path.as_ref()))?;
+
+        Self::preview_data(&data)
+            .map(|jpeg| jpeg.to_vec())
+            .ok_or_else(|| anyhow::anyhow!("No embedded JPEG preview found in {:?}", file_path.as_ref()))
+    }
+
+    /// 在已读入内存的RAW数据中查找最大的内嵌JPEG预览
+    pub fn preview_data(data: &[u8]) -> Option<&[u8]> {
+        match Self::detect_container(data)? {
+            RawContainer::Tiff => TiffReader::new(data).and_then(|reader| Self::largest_tiff_preview(&reader)),
+            RawContainer::Orf => {
+                let reader = TiffReader::with_byte_order(data, data[0] == b'I');
+                Self::largest_tiff_preview(&reader)
+            }
+            RawContainer::Raf => Self::raf_embedded_jpeg(data),
+            RawContainer::Cr3 => Self::cr3_preview(data),
+        }
+    }
+
+    /// 根据文件头判断RAW容器类型
+    fn detect_container(data: &[u8]) -> Option<RawContainer> {
+        let header = data.get(0..16)?;
+        if header.starts_with(b"FUJIFILMCCD-RAW") {
+            Some(RawContainer::Raf)
+        } else if &header[4..8] == b"ftyp" && &header[8..12] == b"crx " {
+            Some(RawContainer::Cr3)
+        } else if header.starts_with(b"IIRO") || header.starts_with(b"IIRS") || header.starts_with(b"MMOR") {
+            Some(RawContainer::Orf)
+        } else if TiffReader::new(data).is_some() {
+            Some(RawContainer::Tiff)
+        } else {
+            None
+        }
+    }
+
+    /// 将ORF的非标准魔数替换为标准TIFF魔数，以便kamadak-exif解析
+    fn normalize_orf_header(mut data: Vec<u8>) -> Vec<u8> {
+        if data[0] == b'I' {
+            data[2..4].copy_from_slice(&[0x2A, 0x00]);
+        } else {
+            data[2..4].copy_from_slice(&[0x00, 0x2A]);
+        }
+        data
+    }
+
+    /// 遍历所有IFD（包括SubIFD），返回可解码的最大JPEG
+    fn largest_tiff_preview<'a>(reader: &TiffReader<'a>) -> Option<&'a [u8]> {
+        let mut pending: Vec<usize> = reader.first_ifd_offset().into_iter().collect();
+        let mut visited = Vec::new();
+        let mut best: Option<&'a [u8]> = None;
+
+        while let Some(offset) = pending.pop() {
+            // 防止损坏文件中的循环引用
+            if visited.contains(&offset) || visited.len() > 64 {
+                continue;
+            }
+            visited.push(offset);
+
+            let Some(entries) = reader.ifd_entries(offset) else {
+                continue;
+            };
+            let find = |tag: u16| entries.iter().find(|entry| entry.tag == tag);
+            let values = |tag: u16| find(tag).map(|entry| reader.entry_u32_values(entry)).unwrap_or_default();
+
+            let mut candidates = Vec::new();
+
+            // JPEGInterchangeFormat 形式的预览
+            if let (Some(&start), Some(&length)) = (values(TAG_JPEG_OFFSET).first(), values(TAG_JPEG_LENGTH).first()) {
+                candidates.push((start as usize, length as usize));
+            }
+
+            // 以单条带JPEG压缩存储的预览（CR2的IFD0、DNG的预览IFD）
+            let compression = values(TAG_COMPRESSION).first().copied();
+            let strip_offsets = values(TAG_STRIP_OFFSETS);
+            let strip_counts = values(TAG_STRIP_BYTE_COUNTS);
+            if matches!(compression, Some(6) | Some(7)) && strip_offsets.len() == 1 && strip_counts.len() == 1 {
+                candidates.push((strip_offsets[0] as usize, strip_counts[0] as usize));
+            }
+
+            // ORF的大预览存放在Exif IFD的Olympus MakerNote中
+            if let Some(&exif_ifd) = values(TAG_EXIF_IFD_POINTER).first() {
+                candidates.extend(Self::olympus_preview_range(reader, exif_ifd as usize));
+            }
+
+            for (start, length) in candidates {
+                let Some(jpeg) = reader.data().get(start..start.saturating_add(length)) else {
+                    continue;
+                };
+                if Self::is_decodable_jpeg(jpeg) && best.is_none_or(|current| jpeg.len() > current.len()) {
+                    best = Some(jpeg);
+                }
+            }
+
+            pending.extend(values(TAG_SUB_IFDS).into_iter().map(|offset| offset as usize));
+            if let Some(next) = reader.next_ifd_offset(offset) {
+                pending.push(next);
+            }
+        }
+
+        best
+    }
+
+    /// Olympus MakerNote中CameraSettings子IFD（0x2020）记录的预览图位置，返回文件内的绝对偏移和长度
+    /// 新格式（"OLYMPUS\0II"、"OM SYSTEM"）的偏移相对于MakerNote起始，旧格式（"OLYMP\0"）相对于TIFF头
+    fn olympus_preview_range(reader: &TiffReader, exif_ifd: usize) -> Option<(usize, usize)> {
+        let exif_entries = reader.ifd_entries(exif_ifd)?;
+        let maker_note_entry = exif_entries.iter().find(|entry| entry.tag == TAG_MAKER_NOTE)?;
+        let maker_note_start = reader.entry_data_position(maker_note_entry)?;
+        let maker_note = reader.entry_bytes(maker_note_entry)?;
+
+        let (base, ifd_offset, little_endian) = if maker_note.starts_with(b"OLYMPUS\0") {
+            (maker_note_start, 12, maker_note.get(8..10)? == b"II")
+        } else if maker_note.starts_with(b"OM SYSTEM\0") {
+            (maker_note_start, 16, maker_note.get(12..14)? == b"II")
+        } else if maker_note.starts_with(b"OLYMP\0") {
+            (0, maker_note_start + 8, reader.little_endian())
+        } else {
+            return None;
+        };
+
+        let maker_reader = TiffReader::with_byte_order(reader.data().get(base..)?, little_endian);
+        let entries = maker_reader.ifd_entries(ifd_offset)?;
+        let camera_settings = entries.iter().find(|entry| entry.tag == TAG_OLYMPUS_CAMERA_SETTINGS)?;
+        let camera_settings_ifd = *maker_reader.entry_u32_values(camera_settings).first()? as usize;
+
+        let settings_entries = maker_reader.ifd_entries(camera_settings_ifd)?;
+        let value = |tag: u16| {
+            let entry = settings_entries.iter().find(|entry| entry.tag == tag)?;
+            maker_reader.entry_u32_values(entry).first().map(|&value| value as usize)
+        };
+        let start = value(TAG_OLYMPUS_PREVIEW_START)?;
+        let length = value(TAG_OLYMPUS_PREVIEW_LENGTH)?;
+
+        (length > 0).then_some((base.checked_add(start)?, length))
+    }
+
+    /// 检查是否为image crate可解码的JPEG（排除RAW数据使用的无损JPEG）
+    fn is_decodable_jpeg(data: &[u8]) -> bool {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return false;
+        }
+
+        let mut position = 2;
+        while position + 4 <= data.len() {
+            if data[position] != 0xFF {
+                return false;
+            }
+            let marker = data[position + 1];
+            match marker {
+                // 基线、扩展顺序、渐进式DCT
+                0xC0..=0xC2 => return true,
+                // 无损、分层、算术编码等image crate不支持的帧类型
+                0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return false,
+                0xD8 | 0x01 | 0xD0..=0xD7 => position += 2,
+                0xDA | 0xD9 => return false,
+                _ => {
+                    let length = u16::from_be_bytes([data[position + 2], data[position + 3]]) as usize;
+                    position += 2 + length;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// RAF头部在偏移84和88处记录内嵌JPEG的位置和长度（大端）
+    fn raf_embedded_jpeg(data: &[u8]) -> Option<&[u8]> {
+        let read_be = |position: usize| {
+            data.get(position..position + 4)
+                .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+        let offset = read_be(84)?;
+        let length = read_be(88)?;
+        data.get(offset..offset.checked_add(length)?)
+            .filter(|jpeg| jpeg.starts_with(&[0xFF, 0xD8]))
+    }
+
+    /// 按盒大小遍历同一层的ISO BMFF盒，返回 (类型, 内容)，内容不含盒头
+    /// 大小为1时使用64位扩展大小，为0时表示延伸到数据末尾
+    fn boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut boxes = Vec::new();
+        let mut position = 0usize;
+
+        while let Some(header) = data.get(position..position + 8) {
+            let box_type = [header[4], header[5], header[6], header[7]];
+            let (header_length, size) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (8, data.len() - position),
+                1 => {
+                    let Some(large) = data.get(position + 8..position + 16) else {
+                        break;
+                    };
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(large);
+                    let Ok(size) = usize::try_from(u64::from_be_bytes(bytes)) else {
+                        break;
+                    };
+                    (16, size)
+                }
+                size => (8, size as usize),
+            };
+
+            let Some(content) = position
+                .checked_add(size)
+                .filter(|_| size >= header_length)
+                .and_then(|end| data.get(position + header_length..end))
+            else {
+                break;
+            };
+            boxes.push((box_type, content));
+            position += size;
+        }
+
+        boxes
+    }
+
+    /// 查找同一层中指定类型的盒
+    fn child_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+        Self::boxes(data)
+            .into_iter()
+            .find(|(current, _)| current == box_type)
+            .map(|(_, content)| content)
+    }
+
+    /// 查找同一层中指定用户类型的uuid盒，返回用户类型之后的内容
+    fn uuid_box<'a>(data: &'a [u8], uuid: &[u8; 16]) -> Option<&'a [u8]> {
+        Self::boxes(data)
+            .into_iter()
+            .filter(|(box_type, _)| box_type == b"uuid")
+            .find_map(|(_, content)| content.strip_prefix(uuid.as_slice()))
+    }
+
+    /// CR3的元数据盒：moov → uuid(Canon) → CMT1/CMT2/CMT4
+    fn cr3_metadata_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+        let moov = Self::child_box(data, b"moov")?;
+        let canon = Self::uuid_box(moov, &CR3_METADATA_UUID)?;
+        Self::child_box(canon, box_type)
+    }
+
+    /// CR3的预览图存放在顶层uuid盒的PRVW盒中，uuid盒内容以8字节未知字段开头
+    /// JPEG数据紧跟在PRVW盒内的描述字段之后
+    fn cr3_preview(data: &[u8]) -> Option<&[u8]> {
+        let preview_uuid = Self::uuid_box(data, &CR3_PREVIEW_UUID)?;
+        let content = Self::child_box(preview_uuid.get(8..)?, b"PRVW")?;
+        let start = content.windows(3).take(64).position(|window| window == [0xFF, 0xD8, 0xFF])?;
+        Some(&content[start..]).filter(|jpeg| Self::is_decodable_jpeg(jpeg))
+    }
+
+    /// CR3的CMT2/CMT4盒是以IFD0形式存放的Exif/GPS IFD。
+    /// 在末尾追加一个只含指针的新IFD0，使kamadak-exif按正确的上下文解析标签
+    fn wrap_sub_ifd(tiff: &[u8], pointer_tag: u16) -> Result<Vec<u8>> {
+        let reader = TiffReader::new(tiff).ok_or_else(|| anyhow::anyhow!("Invalid TIFF header in CR3 metadata box"))?;
+        let sub_ifd_offset = reader
+            .first_ifd_offset()
+            .ok_or_else(|| anyhow::anyhow!("Truncated TIFF header in CR3 metadata box"))? as u32;
+        let little_endian = reader.little_endian();
+
+        let mut wrapped = tiff.to_vec();
+        if wrapped.len() % 2 == 1 {
+            wrapped.push(0);
+        }
+        let new_ifd_offset = wrapped.len() as u32;
+
+        let u16_bytes = |value: u16| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+        let u32_bytes = |value: u32| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+
+        wrapped.extend_from_slice(&u16_bytes(1));
+        wrapped.extend_from_slice(&u16_bytes(pointer_tag));
+        wrapped.extend_from_slice(&u16_bytes(4)); // LONG
+        wrapped.extend_from_slice(&u32_bytes(1));
+        wrapped.extend_from_slice(&u32_bytes(sub_ifd_offset));
+        wrapped.extend_from_slice(&u32_bytes(0));
+        wrapped[4..8].copy_from_slice(&u32_bytes(new_ifd_offset));
+
+        Ok(wrapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, In, Tag, Value};
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+    fn encode_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut buffer = Vec::new();
+        img.write_to(&mut Cursor::new(&mut buffer), ImageOutputFormat::Jpeg(90)).unwrap();
+        buffer
+    }
+
+    /// 构造一个类似CR2的TIFF：IFD0以JPEG条带存放大预览，IFD1存放缩略图
+    fn build_tiff_raw(preview: &[u8], thumbnail: &[u8]) -> Vec<u8> {
+        let make = Field {
+            tag: Tag::Make,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"Canon".to_vec()]),
+        };
+        let compression = Field {
+            tag: Tag::Compression,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![6]),
+        };
+        let strips = [preview];
+
+        let mut writer = Writer::new();
+        writer.push_field(&make);
+        writer.push_field(&compression);
+        writer.set_strips(&strips, In::PRIMARY);
+        writer.set_jpeg(thumbnail, In::THUMBNAIL);
+
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, true).unwrap();
+        buffer.into_inner()
+    }
+
+    /// 新格式Olympus MakerNote："OLYMPUS\0II\x03\0" 头，偏移相对于MakerNote起始
+    /// 主IFD只有CameraSettings指针，CameraSettings中记录预览图的位置和长度
+    fn olympus_maker_note(preview: &[u8]) -> Vec<u8> {
+        let entry = |tag: u16, field_type: u16, value: u32| {
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(&tag.to_le_bytes());
+            bytes.extend_from_slice(&field_type.to_le_bytes());
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+            bytes
+        };
+
+        // 头部(12) + 主IFD(2 + 12 + 4) = 30，CameraSettings(2 + 3 * 12 + 4)之后是预览图
+        let camera_settings_offset = 30u32;
+        let preview_offset = camera_settings_offset + 42;
+
+        let mut maker_note = b"OLYMPUS\0II\x03\0".to_vec();
+        maker_note.extend_from_slice(&1u16.to_le_bytes());
+        maker_note.extend(entry(0x2020, 13, camera_settings_offset));
+        maker_note.extend_from_slice(&0u32.to_le_bytes());
+        maker_note.extend_from_slice(&3u16.to_le_bytes());
+        maker_note.extend(entry(0x0100, 4, 1));
+        maker_note.extend(entry(0x0101, 4, preview_offset));
+        maker_note.extend(entry(0x0102, 4, preview.len() as u32));
+        maker_note.extend_from_slice(&0u32.to_le_bytes());
+        maker_note.extend_from_slice(preview);
+        maker_note
+    }
+
+    /// 构造一个按真实ORF布局的文件：IFD0为未压缩的RAW条带，IFD1为缩略图，
+    /// 大预览在Exif IFD的MakerNote中
+    fn build_orf(preview: &[u8], thumbnail: &[u8]) -> Vec<u8> {
+        let fields = [
+            Field { tag: Tag::Make, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"OLYMPUS CORPORATION".to_vec()]) },
+            Field { tag: Tag::Compression, ifd_num: In::PRIMARY, value: Value::Short(vec![1]) },
+            Field { tag: Tag::MakerNote, ifd_num: In::PRIMARY, value: Value::Undefined(olympus_maker_note(preview), 0) },
+        ];
+        let sensor_data = vec![0u8; 256];
+        let strips = [sensor_data.as_slice()];
+
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        writer.set_strips(&strips, In::PRIMARY);
+        writer.set_jpeg(thumbnail, In::THUMBNAIL);
+
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, true).unwrap();
+        let mut orf = buffer.into_inner();
+        orf[2..4].copy_from_slice(b"RO");
+        orf
+    }
+
+    #[test]
+    fn test_is_raw_file() {
+        assert!(RawService::is_raw_file("IMG_0001.CR2"));
+        assert!(RawService::is_raw_file("DSCF0001.raf"));
+        assert!(RawService::is_raw_file("photo.dng"));
+        assert!(!RawService::is_raw_file("photo.jpg"));
+    }
+
+    #[test]
+    fn test_tiff_raw_preview_and_exif() {
+        let preview = encode_jpeg(64, 48);
+        let thumbnail = encode_jpeg(8, 6);
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let cr2_path = temp_dir.path().join("IMG_0001.CR2");
+        std::fs::write(&cr2_path, build_tiff_raw(&preview, &thumbnail)).unwrap();
+        assert_eq!(RawService::extract_preview(&cr2_path).unwrap(), preview);
+        let sources = RawService::read_exif(&cr2_path).unwrap();
+        let make = sources[0].get_field(Tag::Make, In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+
+        // ORF使用非标准魔数，大预览位于Olympus MakerNote的CameraSettings子IFD中
+        let orf_path = temp_dir.path().join("P1010001.ORF");
+        std::fs::write(&orf_path, build_orf(&preview, &thumbnail)).unwrap();
+        assert_eq!(RawService::extract_preview(&orf_path).unwrap(), preview);
+        let sources = RawService::read_exif(&orf_path).unwrap();
+        assert!(sources[0].get_field(Tag::MakerNote, In::PRIMARY).is_some());
+    }
+
+    /// 组装一个ISO BMFF盒
+    fn bmff_box(box_type: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(box_type);
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    #[test]
+    fn test_cr3_box_tree() {
+        let preview = encode_jpeg(32, 24);
+        let cmt1 = build_tiff_raw(&encode_jpeg(8, 6), &encode_jpeg(4, 3));
+
+        let mut canon = CR3_METADATA_UUID.to_vec();
+        canon.extend(bmff_box(b"CNCV", b"CanonCR3_001/00.10.00/00.00.00"));
+        canon.extend(bmff_box(b"CMT1", &cmt1));
+        let moov = bmff_box(b"moov", &bmff_box(b"uuid", &canon));
+
+        let mut prvw = vec![0u8; 16];
+        prvw.extend_from_slice(&preview);
+        let mut preview_uuid = CR3_PREVIEW_UUID.to_vec();
+        preview_uuid.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        preview_uuid.extend(bmff_box(b"PRVW", &prvw));
+
+        // 其他盒内容中出现的盒类型字符串不应被当作盒
+        let mut decoy = b"\0\0\0\x10PRVW".to_vec();
+        decoy.extend_from_slice(&encode_jpeg(8, 8));
+
+        let mut cr3 = bmff_box(b"ftyp", b"crx \0\0\0\x01crx isom");
+        cr3.extend(bmff_box(b"free", &decoy));
+        cr3.extend(moov);
+        cr3.extend(bmff_box(b"uuid", &preview_uuid));
+        cr3.extend(bmff_box(b"mdat", &[0u8; 64]));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cr3_path = temp_dir.path().join("IMG_0001.CR3");
+        std::fs::write(&cr3_path, cr3).unwrap();
+        assert_eq!(RawService::extract_preview(&cr3_path).unwrap(), preview);
+        let sources = RawService::read_exif(&cr3_path).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0].get_field(Tag::Make, In::PRIMARY).is_some());
+    }
+
+    #[test]
+    fn test_raf_embedded_jpeg() {
+        let jpeg = encode_jpeg(16, 16);
+        let mut raf = b"FUJIFILMCCD-RAW 0201FF129502".to_vec();
+        raf.resize(100, 0);
+        raf[84..88].copy_from_slice(&100u32.to_be_bytes());
+        raf[88..92].copy_from_slice(&(jpeg.len() as u32).to_be_bytes());
+        raf.extend_from_slice(&jpeg);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let raf_path = temp_dir.path().join("DSCF0001.RAF");
+        std::fs::write(&raf_path, raf).unwrap();
+        assert_eq!(RawService::extract_preview(&raf_path).unwrap(), jpeg);
+    }
+}
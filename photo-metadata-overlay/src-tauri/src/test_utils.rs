@@ -79,6 +79,7 @@ mod tests {
                 size: 16.0,
                 color: "#FFFFFF".to_string(),
                 weight: FontWeight::Normal,
+                style: FontStyle::Normal,
             },
             background: BackgroundSettings {
                 color: "#000000".to_string(),
@@ -95,7 +96,15 @@ mod tests {
                 timestamp: true,
                 location: false,
                 brand_logo: true,
+                ..Default::default()
             },
+            date_format: DateFormatSettings::default(),
+            location_format: LocationFormatSettings::default(),
+            focal_length_format: FocalLengthFormat::default(),
+            text_layout: TextLayoutSettings::default(),
+            logo: LogoSettings::default(),
+            layout_mode: LayoutMode::Preset,
+            custom_layout: None,
         };
 
         // 测试序列化
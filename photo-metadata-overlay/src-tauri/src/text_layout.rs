@@ -0,0 +1,307 @@
+use crate::types::*;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use rusttype::{point, Font, Scale};
+
+/// 排好的一行文本，坐标相对于文本块左上角
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+}
+
+/// 排好的文本块
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextBlock {
+    pub lines: Vec<TextLine>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 字体回退链
+/// 主字体缺少的字符（如DejaVu Sans中没有的中日韩文字）依次在后面的字体中查找
+#[derive(Clone)]
+pub struct FontChain {
+    fonts: Vec<Font<'static>>,
+}
+
+impl FontChain {
+    /// 第一个字体为主字体，`fonts` 不能为空
+    pub fn new(fonts: Vec<Font<'static>>) -> Self {
+        assert!(!fonts.is_empty(), "Font chain requires a primary font");
+        Self { fonts }
+    }
+
+    /// 主字体，行高与基线以它为准
+    pub fn primary(&self) -> &Font<'static> {
+        &self.fonts[0]
+    }
+
+    pub fn font(&self, index: usize) -> &Font<'static> {
+        &self.fonts[index]
+    }
+
+    /// 将文本拆分为使用同一字体的连续片段，返回字体下标与片段
+    /// 空白以及所有字体都没有的字符沿用当前片段的字体，避免片段过碎
+    pub fn runs<'t>(&self, text: &'t str) -> Vec<(usize, &'t str)> {
+        let mut runs = Vec::new();
+        let mut current: Option<(usize, usize)> = None; // (字体下标, 片段起点)
+
+        for (position, c) in text.char_indices() {
+            let covering = if c.is_whitespace() {
+                None
+            } else {
+                self.fonts.iter().position(|font| font.glyph(c).id().0 != 0)
+            };
+            match (current, covering) {
+                (None, index) => current = Some((index.unwrap_or(0), position)),
+                (Some((index, start)), Some(covering)) if covering != index => {
+                    runs.push((index, &text[start..position]));
+                    current = Some((covering, position));
+                }
+                _ => {}
+            }
+        }
+        if let Some((index, start)) = current {
+            runs.push((index, &text[start..]));
+        }
+        runs
+    }
+}
+
+impl From<Font<'static>> for FontChain {
+    fn from(font: Font<'static>) -> Self {
+        Self::new(vec![font])
+    }
+}
+
+/// 叠加文本排版
+/// rusttype只能绘制单行文本，这里负责断行、逐行测量、行距与对齐
+pub struct TextLayout;
+
+impl TextLayout {
+    /// 排版叠加项目，`max_width` 为文本块的最大宽度（像素）
+    pub fn layout(
+        font: &FontChain,
+        scale: Scale,
+        items: &[String],
+        settings: &TextLayoutSettings,
+        max_width: Option<f32>,
+    ) -> TextBlock {
+        let max_width = max_width.filter(|width| *width > 0.0);
+        let items: Vec<&str> = items.iter().map(|item| item.trim()).filter(|item| !item.is_empty()).collect();
+        let rows = match settings.arrangement {
+            TextArrangement::Stacked => items.iter().flat_map(|item| Self::wrap(font, scale, item, max_width)).collect(),
+            TextArrangement::SingleRow => Self::join_rows(font, scale, &items, &settings.separator, max_width),
+        };
+        if rows.is_empty() {
+            return TextBlock::default();
+        }
+
+        let v_metrics = font.primary().v_metrics(scale);
+        let line_height = v_metrics.ascent - v_metrics.descent;
+        let line_spacing = if settings.line_spacing.is_finite() && settings.line_spacing > 0.0 { settings.line_spacing } else { 1.0 };
+        let line_advance = line_height * line_spacing;
+
+        let widths: Vec<u32> = rows.iter().map(|row| Self::measure(font, scale, row).ceil() as u32).collect();
+        let block_width = widths.iter().copied().max().unwrap_or(0);
+
+        let lines = rows
+            .into_iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(index, (text, width))| TextLine {
+                x: match settings.alignment {
+                    TextAlignment::Left => 0,
+                    TextAlignment::Center => (block_width - width) / 2,
+                    TextAlignment::Right => block_width - width,
+                },
+                y: (index as f32 * line_advance).round() as u32,
+                width,
+                text,
+            })
+            .collect::<Vec<_>>();
+
+        let height = ((lines.len() - 1) as f32 * line_advance + line_height).ceil() as u32;
+        TextBlock { lines, width: block_width, height }
+    }
+
+    /// 在 (x, y) 处绘制文本块
+    pub fn draw(img: &mut RgbaImage, block: &TextBlock, x: i32, y: i32, color: Rgba<u8>, font: &FontChain, scale: Scale) {
+        for line in &block.lines {
+            Self::draw_line(img, color, x + line.x as i32, y + line.y as i32, scale, font, &line.text);
+        }
+    }
+
+    /// 绘制单行文本，逐段切换字体
+    pub fn draw_line(img: &mut RgbaImage, color: Rgba<u8>, x: i32, y: i32, scale: Scale, font: &FontChain, text: &str) {
+        let ascent = font.primary().v_metrics(scale).ascent;
+        let mut offset: f32 = 0.0;
+        for (index, run) in font.runs(text) {
+            let run_font = font.font(index);
+            // 各字体的上升高度不同，按主字体对齐基线
+            let baseline_shift = ascent - run_font.v_metrics(scale).ascent;
+            draw_text_mut(img, color, x + offset.round() as i32, y + baseline_shift.round() as i32, scale, run_font, run);
+            offset += Self::measure_run(run_font, scale, run);
+        }
+    }
+
+    /// 单行文本的宽度（包括字距调整），各片段按各自的字体测量
+    pub fn measure(font: &FontChain, scale: Scale, text: &str) -> f32 {
+        font.runs(text).into_iter().map(|(index, run)| Self::measure_run(font.font(index), scale, run)).sum()
+    }
+
+    fn measure_run(font: &Font, scale: Scale, text: &str) -> f32 {
+        let v_metrics = font.v_metrics(scale);
+        let glyphs: Vec<_> = font.layout(text, scale, point(0.0, v_metrics.ascent)).collect();
+
+        match (glyphs.first(), glyphs.last()) {
+            (Some(first), Some(last)) => {
+                last.position().x + last.unpositioned().h_metrics().advance_width - first.position().x
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// 按最大宽度在空格处断行，单个词超出时按字符断开（也适用于不用空格分词的中日文）
+    fn wrap(font: &FontChain, scale: Scale, text: &str, max_width: Option<f32>) -> Vec<String> {
+        let Some(max_width) = max_width else {
+            return vec![text.to_string()];
+        };
+        let fits = |text: &str| Self::measure(font, scale, text) <= max_width;
+
+        let mut lines = Vec::new();
+        let mut current = String::new();
+        for word in text.split(' ').filter(|word| !word.is_empty()) {
+            let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+            if fits(&candidate) {
+                current = candidate;
+                continue;
+            }
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            for c in word.chars() {
+                current.push(c);
+                if !fits(&current) && current.chars().count() > 1 {
+                    current.pop();
+                    lines.push(std::mem::take(&mut current));
+                    current.push(c);
+                }
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+
+    /// 单行模式：用分隔符连接各项，超出最大宽度时在分隔符处换行
+    fn join_rows(font: &FontChain, scale: Scale, items: &[&str], separator: &str, max_width: Option<f32>) -> Vec<String> {
+        let mut rows = Vec::new();
+        let mut current = String::new();
+
+        for item in items {
+            let candidate = if current.is_empty() { item.to_string() } else { format!("{}{}{}", current, separator, item) };
+            if max_width.is_none_or(|max_width| Self::measure(font, scale, &candidate) <= max_width) {
+                current = candidate;
+                continue;
+            }
+            if !current.is_empty() {
+                rows.push(std::mem::take(&mut current));
+            }
+            // 单项本身超出最大宽度时在项内断行，最后一段可以继续连接后面的项
+            let mut pieces = Self::wrap(font, scale, item, max_width);
+            current = pieces.pop().unwrap_or_default();
+            rows.extend(pieces);
+        }
+        if !current.is_empty() {
+            rows.push(current);
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_registry::{FontRegistry, TEST_FONTS};
+
+    fn font() -> FontChain {
+        let registry = FontRegistry::with_fonts(TEST_FONTS, None);
+        registry.find("DejaVu Sans", &FontWeight::Normal, FontStyle::Normal).unwrap().into()
+    }
+
+    fn items(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn test_stacked_lines_and_alignment() {
+        let (font, scale) = (font(), Scale::uniform(20.0));
+        let mut settings = TextLayoutSettings { alignment: TextAlignment::Right, ..Default::default() };
+        let block = TextLayout::layout(&font, scale, &items(&["FUJIFILM X-T5", "ISO 400", ""]), &settings, None);
+
+        assert_eq!(block.lines.len(), 2);
+        assert_eq!(block.width, block.lines[0].width);
+        assert_eq!(block.lines[1].x + block.lines[1].width, block.width);
+        // 第二行按1.2倍行高下移，块高度为两行
+        let v_metrics = font.primary().v_metrics(scale);
+        let line_height = v_metrics.ascent - v_metrics.descent;
+        assert_eq!(block.lines[1].y, (line_height * 1.2).round() as u32);
+        assert_eq!(block.height, (line_height * 2.2).ceil() as u32);
+
+        settings.alignment = TextAlignment::Center;
+        let block = TextLayout::layout(&font, scale, &items(&["FUJIFILM X-T5", "ISO 400"]), &settings, None);
+        assert_eq!(block.lines[1].x, (block.width - block.lines[1].width) / 2);
+    }
+
+    #[test]
+    fn test_single_row_and_wrapping() {
+        let (font, scale) = (font(), Scale::uniform(20.0));
+        let settings = TextLayoutSettings { arrangement: TextArrangement::SingleRow, ..Default::default() };
+        let row = items(&["f/2.8", "1/250", "ISO 400"]);
+
+        let block = TextLayout::layout(&font, scale, &row, &settings, None);
+        let texts: Vec<&str> = block.lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, vec!["f/2.8 · 1/250 · ISO 400"]);
+
+        // 放不下时在分隔符处换行
+        let max_width = TextLayout::measure(&font, scale, "f/2.8 · 1/250");
+        let block = TextLayout::layout(&font, scale, &row, &settings, Some(max_width));
+        let texts: Vec<&str> = block.lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, vec!["f/2.8 · 1/250", "ISO 400"]);
+
+        // 堆叠模式中长文本在空格处断行，每行都不超过最大宽度
+        let caption = items(&["Morning light over the old harbour"]);
+        let block = TextLayout::layout(&font, scale, &caption, &TextLayoutSettings::default(), Some(150.0));
+        assert!(block.lines.len() > 1);
+        assert!(block.lines.iter().all(|line| line.width <= 150));
+        assert_eq!(block.lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join(" "), caption[0]);
+    }
+
+    #[test]
+    fn test_glyph_fallback_runs() {
+        let registry = FontRegistry::with_fonts(TEST_FONTS, None);
+        let mono = registry.find("DejaVu Sans Mono", &FontWeight::Normal, FontStyle::Normal).unwrap();
+        let sans = registry.find("DejaVu Sans", &FontWeight::Normal, FontStyle::Normal).unwrap();
+        let chain = FontChain::new(vec![mono.clone(), sans.clone()]);
+
+        // DejaVu Sans Mono 没有 "※"，改用 DejaVu Sans；都没有的 "中" 与空格沿用当前字体
+        assert_eq!(chain.runs("A※B 中"), vec![(0, "A"), (1, "※"), (0, "B 中")]);
+        assert_eq!(chain.runs(" ※"), vec![(0, " "), (1, "※")]);
+
+        let scale = Scale::uniform(20.0);
+        let expected = TextLayout::measure_run(&mono, scale, "A")
+            + TextLayout::measure_run(&sans, scale, "※")
+            + TextLayout::measure_run(&mono, scale, "B");
+        assert_eq!(TextLayout::measure(&chain, scale, "A※B"), expected);
+
+        // 逐段绘制：回退字体绘制的 "※" 有像素
+        let mut img = RgbaImage::new(60, 30);
+        TextLayout::draw_line(&mut img, Rgba([255, 255, 255, 255]), 0, 0, scale, &chain, "※");
+        assert!(img.pixels().any(|pixel| pixel.0[3] > 0));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::exif_service::ExifService;
+use crate::metadata_writer::{MetadataWriter, EXIF_HEADER};
+use crate::raw_service::RawService;
+use crate::tiff_reader::TiffReader;
+use crate::types::*;
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegDecoder;
+use image::DynamicImage;
+use std::io::Cursor;
+use std::path::Path;
+
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+const TAG_MP_ENTRY: u16 = 0xB002;
+
+/// APP2段中MPF数据的标识
+const MPF_HEADER: &[u8] = b"MPF\0";
+
+/// 文件列表缩略图的默认长边
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// 缩略图编码质量
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// 文件列表缩略图
+/// 优先使用文件内嵌的缩略图或预览，避免完整解码原图
+pub struct ThumbnailService;
+
+impl ThumbnailService {
+    /// 生成长边不超过 `max_size` 的缩略图
+    /// 内嵌图不小于 `max_size` 且不超过其两倍时直接返回原始JPEG数据
+    pub fn generate<P: AsRef<Path>>(file_path: P, max_size: u32) -> Result<Thumbnail> {
+        let path = file_path.as_ref();
+        let max_size = max_size.clamp(16, u16::MAX as u32);
+        let orientation = ExifService::read_orientation(path);
+
+        let (data, source) = if RawService::is_raw_file(path) {
+            (RawService::extract_preview(path)?, ThumbnailSource::RawPreview)
+        } else {
+            let data = std::fs::read(path).with_context(|| format!("Failed to read image: {:?}", path))?;
+            // 内嵌图都小于 `max_size` 时解码原图，避免返回比请求小的缩略图
+            match Self::embedded_thumbnail(&data, max_size) {
+                Some((jpeg, source)) if Self::long_side(jpeg).is_some_and(|size| size >= max_size) => {
+                    (jpeg.to_vec(), source)
+                }
+                _ => (data, ThumbnailSource::ScaledDecode),
+            }
+        };
+
+        if source != ThumbnailSource::ScaledDecode {
+            if let Some((width, height)) = Self::jpeg_dimensions(&data) {
+                if (max_size..=max_size * 2).contains(&width.max(height)) {
+                    return Ok(Thumbnail { data, width, height, orientation, source });
+                }
+            }
+        }
+
+        // 内嵌图过大时同样缩小解码，只是数据量比原图小
+        let image = if data.starts_with(&[0xFF, 0xD8]) {
+            Self::scaled_decode(&data, max_size)?
+        } else {
+            image::load_from_memory(&data).with_context(|| format!("Failed to decode image: {:?}", path))?
+        };
+        // 只缩小不放大（RAW预览可能小于请求的尺寸）
+        let image = if image.width().max(image.height()) > max_size {
+            image.thumbnail(max_size, max_size)
+        } else {
+            image
+        };
+        let (data, width, height) = Self::encode(image)?;
+        Ok(Thumbnail { data, width, height, orientation, source })
+    }
+
+    /// 从JPEG中取出最合适的内嵌图：能覆盖 `max_size` 的最小者，都不够大时取最大者
+    pub fn embedded_thumbnail(data: &[u8], max_size: u32) -> Option<(&[u8], ThumbnailSource)> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return None;
+        }
+
+        let mut candidates = Vec::new();
+        // 标记段负载在文件中的位置：SOI之后，每段有2字节标记与2字节长度
+        let mut position = 2;
+        for (marker, payload) in MetadataWriter::jpeg_segments(data) {
+            let payload_start = position + 4;
+            position = payload_start + payload.len();
+            match marker {
+                0xE1 if payload.starts_with(EXIF_HEADER) => {
+                    if let Some(jpeg) = Self::exif_thumbnail(&payload[EXIF_HEADER.len()..]) {
+                        candidates.push((jpeg, ThumbnailSource::ExifThumbnail));
+                    }
+                }
+                0xE2 if payload.starts_with(MPF_HEADER) => {
+                    // MPF中的偏移以其TIFF头为基准
+                    let base = payload_start + MPF_HEADER.len();
+                    candidates.extend(Self::mpf_previews(data, base).into_iter().map(|jpeg| (jpeg, ThumbnailSource::MpfPreview)));
+                }
+                _ => {}
+            }
+        }
+
+        let sized: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|(jpeg, source)| Some((jpeg, source, Self::long_side(jpeg)?)))
+            .collect();
+
+        sized
+            .iter()
+            .filter(|(_, _, size)| *size >= max_size)
+            .min_by_key(|(_, _, size)| *size)
+            .or_else(|| sized.iter().max_by_key(|(_, _, size)| *size))
+            .map(|(jpeg, source, _)| (*jpeg, *source))
+    }
+
+    /// IFD1中以 JPEGInterchangeFormat 存储的缩略图
+    fn exif_thumbnail(tiff: &[u8]) -> Option<&[u8]> {
+        let reader = TiffReader::new(tiff)?;
+        let ifd1 = reader.next_ifd_offset(reader.first_ifd_offset()?).filter(|offset| *offset != 0)?;
+        let entries = reader.ifd_entries(ifd1)?;
+        let value = |tag: u16| {
+            let entry = entries.iter().find(|entry| entry.tag == tag)?;
+            reader.entry_u32_values(entry).first().map(|value| *value as usize)
+        };
+
+        let (start, length) = (value(TAG_JPEG_OFFSET)?, value(TAG_JPEG_LENGTH)?);
+        tiff.get(start..start.checked_add(length)?).filter(|jpeg| jpeg.starts_with(&[0xFF, 0xD8]))
+    }
+
+    /// MPF索引中除主图像之外的所有图像（通常是大尺寸预览）
+    /// 每个MP条目16字节：属性、大小、偏移、两个从属图像编号
+    fn mpf_previews(data: &[u8], base: usize) -> Vec<&[u8]> {
+        let Some(reader) = data.get(base..).and_then(TiffReader::new) else {
+            return Vec::new();
+        };
+        let entries = reader.first_ifd_offset().and_then(|offset| reader.ifd_entries(offset)).unwrap_or_default();
+        let Some(mp_entry) = entries.iter().find(|entry| entry.tag == TAG_MP_ENTRY) else {
+            return Vec::new();
+        };
+        let Some(position) = reader.entry_data_position(mp_entry) else {
+            return Vec::new();
+        };
+
+        (0..mp_entry.count as usize / 16)
+            .filter_map(|index| {
+                let entry = position + index * 16;
+                let size = reader.read_u32(entry + 4)? as usize;
+                let offset = reader.read_u32(entry + 8)? as usize;
+                // 主图像的偏移为0
+                if offset == 0 {
+                    return None;
+                }
+                let start = base.checked_add(offset)?;
+                data.get(start..start.checked_add(size)?).filter(|jpeg| jpeg.starts_with(&[0xFF, 0xD8]))
+            })
+            .collect()
+    }
+
+    /// 按DCT缩放解码JPEG（1/2、1/4、1/8），只解码到不小于 `max_size` 的最小尺寸
+    pub fn scaled_decode(data: &[u8], max_size: u32) -> Result<DynamicImage> {
+        let mut decoder = JpegDecoder::new(Cursor::new(data)).context("Failed to read JPEG header")?;
+        let requested = max_size.min(u16::MAX as u32) as u16;
+        decoder.scale(requested, requested).context("Failed to scale JPEG decoder")?;
+        DynamicImage::from_decoder(decoder).context("Failed to decode JPEG")
+    }
+
+    /// 只读取文件头中的尺寸
+    fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+        image::io::Reader::with_format(Cursor::new(data), image::ImageFormat::Jpeg)
+            .into_dimensions()
+            .ok()
+    }
+
+    fn long_side(data: &[u8]) -> Option<u32> {
+        Self::jpeg_dimensions(data).map(|(width, height)| width.max(height))
+    }
+
+    fn encode(image: DynamicImage) -> Result<(Vec<u8>, u32, u32)> {
+        let image = DynamicImage::ImageRgb8(image.to_rgb8());
+        let mut buffer = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Jpeg(THUMBNAIL_QUALITY))
+            .context("Failed to encode thumbnail")?;
+        Ok((buffer, image.width(), image.height()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50])))
+            .write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Jpeg(90))
+            .unwrap();
+        buffer
+    }
+
+    /// 在JPEG的SOI之后插入带IFD1缩略图的EXIF段
+    fn with_exif_thumbnail(main: &[u8], thumbnail: &[u8]) -> Vec<u8> {
+        let mut tiff = b"II*\0\x08\0\0\0".to_vec();
+        // IFD0：没有条目，下一个IFD在偏移14
+        tiff.extend_from_slice(&[0, 0, 14, 0, 0, 0]);
+        // IFD1：缩略图偏移与长度，数据紧跟在IFD之后（14 + 2 + 24 + 4 = 44）
+        tiff.extend_from_slice(&[2, 0]);
+        for (tag, value) in [(TAG_JPEG_OFFSET, 44u32), (TAG_JPEG_LENGTH, thumbnail.len() as u32)] {
+            tiff.extend_from_slice(&tag.to_le_bytes());
+            tiff.extend_from_slice(&[4, 0, 1, 0, 0, 0]);
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+        tiff.extend_from_slice(&[0, 0, 0, 0]);
+        tiff.extend_from_slice(thumbnail);
+
+        let length = (2 + EXIF_HEADER.len() + tiff.len()) as u16;
+        let mut data = main[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(EXIF_HEADER);
+        data.extend_from_slice(&tiff);
+        data.extend_from_slice(&main[2..]);
+        data
+    }
+
+    #[test]
+    fn test_exif_thumbnail_is_used() {
+        let thumbnail = jpeg(160, 120);
+        let data = with_exif_thumbnail(&jpeg(1600, 1200), &thumbnail);
+
+        let (embedded, source) = ThumbnailService::embedded_thumbnail(&data, 160).unwrap();
+        assert_eq!((embedded, source), (thumbnail.as_slice(), ThumbnailSource::ExifThumbnail));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("photo.jpg");
+        std::fs::write(&path, &data).unwrap();
+        let result = ThumbnailService::generate(&path, 160).unwrap();
+        assert_eq!((result.width, result.height, result.source), (160, 120, ThumbnailSource::ExifThumbnail));
+        assert_eq!(result.data, thumbnail);
+
+        // 内嵌缩略图小于请求的尺寸时解码原图
+        let result = ThumbnailService::generate(&path, 256).unwrap();
+        assert_eq!((result.width, result.height, result.source), (256, 192, ThumbnailSource::ScaledDecode));
+    }
+
+    #[test]
+    fn test_scaled_decode_fallback() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("plain.jpg");
+        std::fs::write(&path, jpeg(1600, 1000)).unwrap();
+
+        // 1/8缩放解码得到200x125，不足256，因此按1/4解码再缩小
+        let decoded = ThumbnailService::scaled_decode(&std::fs::read(&path).unwrap(), 256).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (400, 250));
+
+        let result = ThumbnailService::generate(&path, 256).unwrap();
+        assert_eq!((result.width, result.height, result.source), (256, 160, ThumbnailSource::ScaledDecode));
+        assert_eq!(result.orientation, 1);
+    }
+}
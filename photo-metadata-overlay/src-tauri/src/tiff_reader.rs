@@ -0,0 +1,190 @@
+/// IFD条目
+#[derive(Debug, Clone, Copy)]
+pub struct IfdEntry {
+    pub tag: u16,
+    pub field_type: u16,
+    pub count: u32,
+    /// 条目中4字节值/偏移字段在数据中的位置
+    pub value_position: usize,
+}
+
+/// 最小化的TIFF结构读取器
+/// 只负责遍历IFD和读取条目，字段的语义解析交给kamadak-exif
+pub struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    /// 解析标准TIFF头（II*\0 或 MM\0*）
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        match data.get(0..4)? {
+            [b'I', b'I', 0x2A, 0x00] => Some(Self { data, little_endian: true }),
+            [b'M', b'M', 0x00, 0x2A] => Some(Self { data, little_endian: false }),
+            _ => None,
+        }
+    }
+
+    /// 使用指定字节序读取（用于头部魔数非标准的格式，如ORF）
+    pub fn with_byte_order(data: &'a [u8], little_endian: bool) -> Self {
+        Self { data, little_endian }
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn little_endian(&self) -> bool {
+        self.little_endian
+    }
+
+    /// 第一个IFD的偏移
+    pub fn first_ifd_offset(&self) -> Option<usize> {
+        self.read_u32(4).map(|offset| offset as usize)
+    }
+
+    pub fn read_u16(&self, position: usize) -> Option<u16> {
+        let bytes = self.data.get(position..position + 2)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([bytes[0], bytes[1]])
+        } else {
+            u16::from_be_bytes([bytes[0], bytes[1]])
+        })
+    }
+
+    pub fn read_u32(&self, position: usize) -> Option<u32> {
+        let bytes = self.data.get(position..position + 4)?;
+        Some(if self.little_endian {
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        } else {
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        })
+    }
+
+    /// 读取IFD中的全部条目
+    pub fn ifd_entries(&self, offset: usize) -> Option<Vec<IfdEntry>> {
+        let count = self.read_u16(offset)? as usize;
+        let end = offset + 2 + count * 12;
+        if end > self.data.len() {
+            return None;
+        }
+
+        let entries = (0..count)
+            .filter_map(|index| {
+                let position = offset + 2 + index * 12;
+                Some(IfdEntry {
+                    tag: self.read_u16(position)?,
+                    field_type: self.read_u16(position + 2)?,
+                    count: self.read_u32(position + 4)?,
+                    value_position: position + 8,
+                })
+            })
+            .collect();
+
+        Some(entries)
+    }
+
+    /// 下一个IFD的偏移，0表示链表结束
+    pub fn next_ifd_offset(&self, offset: usize) -> Option<usize> {
+        let count = self.read_u16(offset)? as usize;
+        match self.read_u32(offset + 2 + count * 12)? {
+            0 => None,
+            next => Some(next as usize),
+        }
+    }
+
+    /// 单个元素的字节数
+    fn type_size(field_type: u16) -> usize {
+        match field_type {
+            1 | 2 | 6 | 7 => 1,   // BYTE, ASCII, SBYTE, UNDEFINED
+            3 | 8 => 2,           // SHORT, SSHORT
+            4 | 9 | 11 | 13 => 4, // LONG, SLONG, FLOAT, IFD
+            5 | 10 | 12 => 8,     // RATIONAL, SRATIONAL, DOUBLE
+            _ => 0,
+        }
+    }
+
+    /// 条目数据所在的位置（内联时为值字段本身）
+    pub fn entry_data_position(&self, entry: &IfdEntry) -> Option<usize> {
+        let length = Self::type_size(entry.field_type).checked_mul(entry.count as usize)?;
+        if length <= 4 {
+            Some(entry.value_position)
+        } else {
+            self.read_u32(entry.value_position).map(|offset| offset as usize)
+        }
+    }
+
+    /// 条目的原始字节
+    pub fn entry_bytes(&self, entry: &IfdEntry) -> Option<&'a [u8]> {
+        let length = Self::type_size(entry.field_type).checked_mul(entry.count as usize)?;
+        let position = self.entry_data_position(entry)?;
+        self.data.get(position..position.checked_add(length)?)
+    }
+
+    /// 以字符串读取ASCII条目，去掉结尾的NUL与空白
+    pub fn entry_string(&self, entry: &IfdEntry) -> Option<String> {
+        let bytes = self.entry_bytes(entry)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let text = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// 读取RATIONAL类型条目的所有值，分母为0的值记为None
+    pub fn entry_rationals(&self, entry: &IfdEntry) -> Vec<Option<f64>> {
+        if entry.field_type != 5 {
+            return Vec::new();
+        }
+        let Some(position) = self.entry_data_position(entry) else {
+            return Vec::new();
+        };
+
+        (0..entry.count as usize)
+            .map_while(|index| {
+                let numerator = self.read_u32(position + index * 8)?;
+                let denominator = self.read_u32(position + index * 8 + 4)?;
+                Some((denominator != 0).then(|| numerator as f64 / denominator as f64))
+            })
+            .collect()
+    }
+
+    /// 以整数读取SHORT/LONG类型条目的所有值
+    pub fn entry_u32_values(&self, entry: &IfdEntry) -> Vec<u32> {
+        let Some(position) = self.entry_data_position(entry) else {
+            return Vec::new();
+        };
+
+        (0..entry.count as usize)
+            .map_while(|index| match entry.field_type {
+                3 => self.read_u16(position + index * 2).map(u32::from),
+                4 | 13 => self.read_u32(position + index * 4),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ifd_entries() {
+        // 大端TIFF：IFD0包含一个SHORT条目(Orientation=6)和一个LONG数组条目
+        let mut data = vec![b'M', b'M', 0x00, 0x2A, 0, 0, 0, 8];
+        data.extend_from_slice(&[0, 2]);
+        data.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0]);
+        data.extend_from_slice(&[0x01, 0x11, 0, 4, 0, 0, 0, 2, 0, 0, 0, 38]);
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 100, 0, 0, 0, 200]);
+
+        let reader = TiffReader::new(&data).unwrap();
+        let ifd0 = reader.first_ifd_offset().unwrap();
+        let entries = reader.ifd_entries(ifd0).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tag, 0x0112);
+        assert_eq!(reader.entry_u32_values(&entries[0]), vec![6]);
+        assert_eq!(reader.entry_u32_values(&entries[1]), vec![100, 200]);
+        assert_eq!(reader.next_ifd_offset(ifd0), None);
+    }
+}
@@ -1,37 +1,311 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// 照片元数据结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PhotoMetadata {
     pub camera: CameraInfo,
     pub settings: CameraSettings,
-    pub timestamp: Option<String>,
+    pub timestamp: Option<String>, // 拍摄时间的原始EXIF字符串
+    pub capture_time: Option<CaptureTimestamp>,
     pub location: Option<LocationInfo>,
+    pub orientation: Option<u16>, // EXIF方向标签 1-8
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    pub software: Option<String>,
+    pub iptc: Option<IptcInfo>,
+    /// 胶片信息，来自胶片卷记录表
+    #[serde(default)]
+    pub film: Option<FilmInfo>,
+    pub file: Option<FileInfo>,
+    /// 各字段的数据来源，键为 "分组.字段"（如 "camera.make"）
+    #[serde(default)]
+    pub field_sources: BTreeMap<String, MetadataSource>,
 }
 
-/// 相机信息
+/// 元数据字段的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataSource {
+    Exif,
+    File,
+    Xmp,
+    Iptc,
+    MakerNote,
+    Gazetteer, // 离线反向地理编码
+    Override,  // 手动覆盖
+    FilmRoll,  // 胶片卷记录表
+    Computed,  // 由其他字段计算得到
+}
+
+/// 手动元数据覆盖
+/// `fields` 的键与 `field_sources` 相同（如 "camera.make"、"settings.iso"、"location"），值为null表示清空该字段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataOverride {
+    #[serde(default)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+    /// 拍摄时间的整体偏移（秒），用于修正相机时钟
+    #[serde(default)]
+    pub time_offset_seconds: Option<i64>,
+}
+
+/// 胶片信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilmInfo {
+    pub stock: Option<String>, // 胶片型号，如 "Kodak Portra 400"
+    pub roll: Option<String>,  // 卷号或卷名
+    pub frame: Option<u32>,
+    pub notes: Option<String>,
+}
+
+/// 胶片卷记录表（CSV或TOML），为没有EXIF的扫描底片提供元数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilmRoll {
+    pub roll: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    #[serde(alias = "film")]
+    pub film_stock: Option<String>,
+    pub iso: Option<u32>,
+    pub lens: Option<String>,
+    pub mapping: FrameMapping,
+    /// 文件名模式，`{frame}` 匹配帧号，`*` / `?` 为通配符，如 "R12_{frame}*.tif"
+    pub file_pattern: Option<String>,
+    pub frames: Vec<FilmFrame>,
+}
+
+/// 胶片卷中的一帧
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilmFrame {
+    pub frame: Option<u32>,
+    /// 明确指定的扫描文件名，优先于映射方式
+    pub file: Option<String>,
+    pub aperture: Option<String>,
+    #[serde(alias = "shutter")]
+    pub shutter_speed: Option<String>,
+    pub lens: Option<String>,
+    pub focal_length: Option<String>,
+    pub date: Option<String>, // "2024-05-01"、"2024-05-01 14:30" 或EXIF格式
+    pub notes: Option<String>,
+}
+
+/// 记录表中的帧与扫描文件的对应方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum FrameMapping {
+    /// 按文件名排序后依次对应
+    #[default]
+    Order,
+    /// 按文件名中的帧号对应（`file_pattern` 或文件名中最后一组数字）
+    Pattern,
+}
+
+/// 从文件本身得到的信息，在没有EXIF时作为兜底
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub file_name: String,
+    pub file_size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub modified: Option<String>, // ISO 8601 UTC
+}
+
+/// 相机信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CameraInfo {
     pub make: Option<String>,
     pub model: Option<String>,
+    pub lens_make: Option<String>,
+    pub lens_model: Option<String>,
+    pub serial_number: Option<String>,
+    pub shutter_count: Option<u32>, // 来自MakerNote
+    /// 规范化之前的原始厂商与型号，如 "NIKON CORPORATION" / "NIKON Z 6_2"
+    #[serde(default)]
+    pub raw_make: Option<String>,
+    #[serde(default)]
+    pub raw_model: Option<String>,
 }
 
 /// 拍摄设置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CameraSettings {
     pub aperture: Option<String>,
     pub shutter_speed: Option<String>,
     pub iso: Option<u32>,
     pub focal_length: Option<String>,
+    pub exposure_bias: Option<String>, // 例如 "+0.7 EV"
+    pub flash: Option<String>,
+    pub metering_mode: Option<String>,
+    pub exposure_program: Option<String>,
+    pub white_balance: Option<String>,
+    pub film_simulation: Option<String>, // 富士胶片模拟，如 "Classic Chrome"
+    /// 35mm等效焦距，如 "35mm"
+    #[serde(default)]
+    pub focal_length_35mm: Option<String>,
+    /// 等效系数（35mm全画幅对角线 / 传感器对角线），如APS-C为1.5
+    #[serde(default)]
+    pub crop_factor: Option<f64>,
+    /// 数值形式的拍摄参数，与上面的显示文本对应
+    #[serde(default)]
+    pub f_number: Option<f64>,
+    #[serde(default)]
+    pub exposure_time: Option<f64>, // 秒
+    #[serde(default)]
+    pub focal_length_mm: Option<f64>,
+    #[serde(default)]
+    pub focal_length_35mm_mm: Option<f64>, // 35mm等效焦距
+    #[serde(default)]
+    pub exposure_bias_ev: Option<f64>,
+    #[serde(default)]
+    pub subject_distance: Option<f64>, // 对焦距离（米）
+    /// 由拍摄参数计算的值
+    #[serde(default)]
+    pub ev100: Option<f64>,
+    #[serde(default)]
+    pub hyperfocal_distance: Option<f64>, // 米
+    #[serde(default)]
+    pub dof_near: Option<f64>, // 景深近点（米）
+    #[serde(default)]
+    pub dof_far: Option<f64>, // 景深远点（米），有近点而没有远点表示延伸到无穷远
 }
 
-/// 位置信息
+/// 结构化的拍摄时间
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureTimestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: Option<u32>,
+    pub offset_minutes: Option<i16>, // 相对UTC的时区偏移（分钟）
+    pub source: TimestampSource,
+}
+
+/// 拍摄时间的来源标签
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampSource {
+    DateTimeOriginal,
+    DateTimeDigitized,
+    DateTime,
+    FileModified,
+    Xmp,
+    Gps,
+    Override,
+}
+
+/// 位置信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LocationInfo {
     pub latitude: f64,
     pub longitude: f64,
     pub address: Option<String>,
+    /// 海拔（米），低于海平面时为负数
+    #[serde(default)]
+    pub altitude: Option<f64>,
+    /// 拍摄方向（度，0-360）
+    #[serde(default)]
+    pub image_direction: Option<f64>,
+    #[serde(default)]
+    pub image_direction_ref: Option<String>, // "T" 真北 / "M" 磁北
+    /// 移动速度（km/h，已从英里或节换算）
+    #[serde(default)]
+    pub speed: Option<f64>,
+    /// GPS卫星时间（UTC）
+    #[serde(default)]
+    pub gps_time: Option<CaptureTimestamp>,
+    #[serde(default)]
+    pub map_datum: Option<String>, // 例如 "WGS-84"
+    /// 离线地名库中距离最近的城市
+    #[serde(default)]
+    pub place: Option<PlaceInfo>,
+}
+
+/// 反向地理编码得到的地点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceInfo {
+    pub city: String,
+    pub region: Option<String>,  // 一级行政区，如省、州
+    pub country: Option<String>,
+    pub country_code: String,    // ISO 3166-1
+    pub distance_km: f64,        // 与城市中心的距离
+}
+
+/// IPTC信息（IPTC-IIM 与 XMP中的 IPTC Core）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IptcInfo {
+    pub title: Option<String>,
+    pub headline: Option<String>,
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    pub byline: Option<String>,
+    pub byline_title: Option<String>,
+    pub credit: Option<String>,
+    pub source: Option<String>,
+    pub copyright_notice: Option<String>,
+    pub sublocation: Option<String>,
+    pub city: Option<String>,
+    pub province_state: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+}
+
+/// 按文件内容识别的图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetectedFormat {
+    Jpeg,
+    Png,
+    Tiff,
+    Raw,
+    Heic,
+    Avif,
+    WebP,
+    Gif,
+    Bmp,
+    Unknown,
+}
+
+/// 按文件头检查得到的图片文件报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageFileReport {
+    pub file_path: String,
+    pub file_size: u64,
+    pub format: DetectedFormat,
+    /// 扩展名与实际格式是否一致
+    pub extension_matches: bool,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub color_type: Option<String>, // "RGB"、"RGBA"、"Grayscale"、"CMYK"、"Palette"、"CFA" 等
+    pub bit_depth: Option<u8>,      // 每通道位数
+    pub has_exif: bool,
+    pub has_icc: bool,
+    pub truncated: bool,
+    /// 格式受支持、没有截断且能读到尺寸，可以交给处理流程
+    pub valid: bool,
+    /// 发现的问题，用于在界面上提示
+    pub problems: Vec<String>,
+}
+
+/// 缩略图来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThumbnailSource {
+    ExifThumbnail, // EXIF IFD1 中的JPEG缩略图
+    MpfPreview,    // MPF（多图片格式）中的预览图
+    RawPreview,    // RAW文件内嵌的JPEG预览
+    ScaledDecode,  // 缩小解码原图
+}
+
+/// 文件列表使用的缩略图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub data: Vec<u8>, // JPEG数据
+    pub width: u32,
+    pub height: u32,
+    /// EXIF方向标签（1-8），缩略图像素未旋转，由前端按此显示
+    pub orientation: u16,
+    pub source: ThumbnailSource,
 }
 
 /// 叠加设置
@@ -41,6 +315,208 @@ pub struct OverlaySettings {
     pub font: FontSettings,
     pub background: BackgroundSettings,
     pub display_items: DisplayItems,
+    #[serde(default)]
+    pub date_format: DateFormatSettings,
+    #[serde(default)]
+    pub location_format: LocationFormatSettings,
+    #[serde(default)]
+    pub focal_length_format: FocalLengthFormat,
+    #[serde(default)]
+    pub text_layout: TextLayoutSettings,
+    /// `display_items.brand_logo` 开启时使用
+    #[serde(default)]
+    pub logo: LogoSettings,
+    #[serde(default)]
+    pub layout_mode: LayoutMode,
+    /// 自定义模式使用
+    #[serde(default)]
+    pub custom_layout: Option<CustomLayoutSettings>,
+}
+
+/// 叠加文本的排版设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextLayoutSettings {
+    #[serde(default)]
+    pub arrangement: TextArrangement,
+    #[serde(default)]
+    pub alignment: TextAlignment,
+    /// 行距，字体行高的倍数
+    #[serde(default = "default_line_spacing")]
+    pub line_spacing: f32,
+    /// 单行模式中各项之间的分隔符
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    /// 文本块的最大宽度（占图片宽度的百分比），超出时换行
+    #[serde(default)]
+    pub max_width_percent: Option<f32>,
+}
+
+impl Default for TextLayoutSettings {
+    fn default() -> Self {
+        Self {
+            arrangement: TextArrangement::default(),
+            alignment: TextAlignment::default(),
+            line_spacing: default_line_spacing(),
+            separator: default_separator(),
+            max_width_percent: None,
+        }
+    }
+}
+
+fn default_line_spacing() -> f32 {
+    1.2
+}
+
+fn default_separator() -> String {
+    " · ".to_string()
+}
+
+/// 叠加项目的排列方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextArrangement {
+    #[default]
+    Stacked,   // 每项一行
+    SingleRow, // "A · B · C"，超出最大宽度时在分隔符处换行
+}
+
+/// 多行文本的对齐方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 品牌Logo设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogoSettings {
+    #[serde(default)]
+    pub variant: LogoVariant,
+    #[serde(default)]
+    pub placement: LogoPlacement,
+}
+
+/// Logo底色
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogoVariant {
+    #[default]
+    Auto,  // 按叠加背景的明暗选择
+    Light, // 浅色底，用于浅色背景
+    Dark,  // 深色底，用于深色背景
+}
+
+/// Logo相对文本块的位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogoPlacement {
+    #[default]
+    Above, // 文本上方居中，与前端预览一致
+    Left,
+    Right,
+}
+
+/// 叠加布局模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutMode {
+    #[default]
+    Preset, // 四个角之一，使用 `position`
+    Custom, // 拖放布局，使用 `custom_layout`
+}
+
+/// 自定义（拖放）布局
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomLayoutSettings {
+    pub elements: Vec<MetadataElement>,
+    #[serde(default)]
+    pub grid_enabled: bool,
+    /// 网格间距，与元素位置同为百分比
+    #[serde(default)]
+    pub grid_size: f32,
+    #[serde(default)]
+    pub snap_to_grid: bool,
+}
+
+/// 自定义布局中的单个元素
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataElement {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub element_type: MetadataElementType,
+    pub position: ElementPosition,
+    pub visible: bool,
+    #[serde(default)]
+    pub style: Option<ElementStyle>,
+}
+
+/// 元素显示的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataElementType {
+    Brand,
+    Model,
+    Aperture,
+    ShutterSpeed,
+    Iso,
+    Timestamp,
+    Location,
+    BrandLogo,
+}
+
+/// 元素左上角的位置，相对于图片的百分比（0-100）
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ElementPosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// 元素样式，未设置的项使用全局字体与背景设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ElementStyle {
+    pub font_size: Option<f32>,
+    pub color: Option<String>,
+    pub background_color: Option<String>,
+    pub padding: Option<f32>,
+}
+
+/// 日期格式设置
+/// 两项都为空时显示原始EXIF时间字符串
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateFormatSettings {
+    pub pattern: Option<String>, // 例如 "YYYY年M月D日 HH:mm"、"MMM D, YYYY"
+    pub locale: Option<String>,  // 例如 "zh-CN"、"en-US"
+}
+
+/// 位置显示设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocationFormatSettings {
+    #[serde(default)]
+    pub coordinate_format: CoordinateFormat,
+    #[serde(default)]
+    pub altitude_unit: AltitudeUnit,
+}
+
+/// 坐标显示格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoordinateFormat {
+    #[default]
+    Decimal, // 35.68944°N, 139.69167°E
+    Dms,     // 35°41'22"N, 139°41'30"E
+}
+
+/// 海拔单位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AltitudeUnit {
+    #[default]
+    Meters,
+    Feet,
+}
+
+/// 焦距显示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FocalLengthFormat {
+    Actual,     // 23mm
+    Equivalent, // 35mm (eq.)
+    #[default]
+    Both,       // 23mm (35mm eq. 35mm)
 }
 
 /// 叠加位置
@@ -55,10 +531,13 @@ pub enum OverlayPosition {
 /// 字体设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FontSettings {
+    /// 字体名称，可以是逗号分隔的候选列表（如 "Helvetica Neue, sans-serif"）
     pub family: String,
     pub size: f32,
     pub color: String, // RGB hex color
     pub weight: FontWeight,
+    #[serde(default)]
+    pub style: FontStyle,
 }
 
 /// 字体粗细
@@ -68,6 +547,25 @@ pub enum FontWeight {
     Bold,
 }
 
+/// 字体样式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+}
+
+/// 可用的字体家族，供界面选择字体
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FontFamilyInfo {
+    pub family: String,
+    /// 已有的字重（100-900），从小到大
+    pub weights: Vec<u16>,
+    pub has_italic: bool,
+    /// 是否有字形来自用户字体文件夹
+    pub user_font: bool,
+}
+
 /// 背景设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackgroundSettings {
@@ -78,7 +576,8 @@ pub struct BackgroundSettings {
 }
 
 /// 显示项目设置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 新增的项目带有 `#[serde(default)]`，旧版前端未传递时默认关闭
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DisplayItems {
     pub brand: bool,
     pub model: bool,
@@ -88,6 +587,53 @@ pub struct DisplayItems {
     pub timestamp: bool,
     pub location: bool,
     pub brand_logo: bool,
+    #[serde(default)]
+    pub lens: bool,
+    #[serde(default)]
+    pub exposure_bias: bool,
+    #[serde(default)]
+    pub flash: bool,
+    #[serde(default)]
+    pub metering_mode: bool,
+    #[serde(default)]
+    pub exposure_program: bool,
+    #[serde(default)]
+    pub white_balance: bool,
+    #[serde(default)]
+    pub artist: bool,
+    #[serde(default)]
+    pub copyright: bool,
+    #[serde(default)]
+    pub software: bool,
+    #[serde(default)]
+    pub headline: bool,
+    #[serde(default)]
+    pub caption: bool,
+    #[serde(default)]
+    pub keywords: bool,
+    #[serde(default)]
+    pub byline: bool,
+    #[serde(default)]
+    pub iptc_location: bool,
+    #[serde(default)]
+    pub film_simulation: bool,
+    /// 署名行，如 "© 作者 / 机构"
+    #[serde(default)]
+    pub credit_line: bool,
+    #[serde(default)]
+    pub altitude: bool,
+    #[serde(default)]
+    pub image_direction: bool,
+    #[serde(default)]
+    pub film_stock: bool,
+    #[serde(default)]
+    pub focal_length: bool,
+    #[serde(default)]
+    pub ev: bool,
+    #[serde(default)]
+    pub hyperfocal: bool,
+    #[serde(default)]
+    pub depth_of_field: bool,
 }
 
 /// 相框设置
@@ -118,6 +664,22 @@ pub struct ProcessingSettings {
     pub frame_settings: FrameSettings,
     pub output_format: OutputFormat,
     pub quality: u8, // 1-100 for JPEG
+    /// 是否将源文件的EXIF/XMP/ICC写入输出文件
+    #[serde(default = "default_preserve_metadata")]
+    pub preserve_metadata: bool,
+    /// 整批应用的元数据覆盖
+    #[serde(default)]
+    pub metadata_override: Option<MetadataOverride>,
+    /// 按图片路径指定的覆盖，优先于整批覆盖
+    #[serde(default)]
+    pub image_overrides: HashMap<String, MetadataOverride>,
+    /// 扫描底片的胶片卷记录表，按映射方式对应到各个文件
+    #[serde(default)]
+    pub film_roll: Option<FilmRoll>,
+}
+
+fn default_preserve_metadata() -> bool {
+    true
 }
 
 /// 输出格式
@@ -165,6 +727,66 @@ pub enum ErrorType {
     PermissionDenied,
 }
 
+/// 批量元数据扫描的进度（通过事件推送给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+/// 批量元数据扫描结果汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataScanSummary {
+    pub total_files: usize,
+    pub with_exif: usize,
+    /// 没有EXIF数据的文件（元数据只来自文件属性、XMP等）
+    pub without_exif: Vec<String>,
+    pub failed: Vec<ProcessingError>,
+    /// 按机身、镜头、ISO统计的照片数量
+    pub cameras: BTreeMap<String, usize>,
+    pub lenses: BTreeMap<String, usize>,
+    pub iso: BTreeMap<u32, usize>,
+    pub output_path: Option<String>,
+    pub total_time_ms: u64,
+}
+
+/// 多张图片的元数据对比结果，第一张图片作为参照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataComparison {
+    pub file_paths: Vec<String>,
+    /// 取值不同的字段，按字段名排序
+    pub differences: Vec<FieldDifference>,
+    /// 所有图片取值相同的字段
+    pub common_fields: BTreeMap<String, serde_json::Value>,
+    /// 各图片拍摄时间与参照图片的差（秒），缺少拍摄时间时为None
+    pub time_deltas: Vec<Option<i64>>,
+    /// 建议的时钟修正，可直接用作覆盖中的 `time_offset_seconds`
+    pub clock_offsets: Vec<ClockOffsetSuggestion>,
+}
+
+/// 单个字段在各图片中的取值，顺序与 `file_paths` 一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDifference {
+    pub field: String,
+    pub values: Vec<Option<serde_json::Value>>,
+}
+
+/// 建议的时钟修正
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockOffsetSuggestion {
+    pub file_path: String,
+    pub offset_seconds: i64,
+    pub basis: ClockOffsetBasis,
+}
+
+/// 时钟修正的依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockOffsetBasis {
+    GpsTime,   // 与同一文件中的GPS卫星时间对齐
+    Reference, // 与参照图片的拍摄时间对齐（假定是同一时刻拍摄的）
+}
+
 /// 预览设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreviewSettings {
@@ -172,4 +794,7 @@ pub struct PreviewSettings {
     pub max_height: u32,
     pub overlay_settings: OverlaySettings,
     pub frame_settings: FrameSettings,
+    /// 尚未保存的元数据覆盖
+    #[serde(default)]
+    pub metadata_override: Option<MetadataOverride>,
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use crate::types::*;
 use crate::image_processing::ImageProcessingService;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -19,16 +19,21 @@ struct CachedResult {
     preview_data: Vec<u8>,
     full_data: Option<Vec<u8>>,
     timestamp: u64,
-    settings_hash: String,
 }
 
 #[derive(Default, Clone)]
-struct ProcessingStats {
+pub struct ProcessingStats {
     cache_hits: u64,
     cache_misses: u64,
     total_processing_time: u64,
 }
 
+impl Default for UnifiedProcessingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UnifiedProcessingEngine {
     pub fn new() -> Self {
         Self {
@@ -38,7 +43,7 @@ impl UnifiedProcessingEngine {
     }
 
     /// 统一的图像处理入口点
-    /// 根据请求类型返回不同分辨率的结果
+    /// 根据请求类型返回不同分辨率的结果，`preserve_metadata` 只影响完整质量输出
     pub async fn process_image_unified(
         &self,
         input_path: &str,
@@ -46,6 +51,7 @@ impl UnifiedProcessingEngine {
         overlay_settings: OverlaySettings,
         frame_settings: FrameSettings,
         request_type: ProcessingRequestType,
+        preserve_metadata: bool,
     ) -> Result<ProcessingResult> {
         let start_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -55,9 +61,11 @@ impl UnifiedProcessingEngine {
         // 生成缓存键
         let cache_key = self.generate_cache_key(
             input_path,
+            &metadata,
             &overlay_settings,
             &frame_settings,
             &request_type,
+            preserve_metadata,
         );
 
         // 检查缓存
@@ -72,10 +80,10 @@ impl UnifiedProcessingEngine {
                 self.process_preview(input_path, metadata, overlay_settings, frame_settings).await?
             }
             ProcessingRequestType::FullQuality => {
-                self.process_full_quality(input_path, metadata, overlay_settings, frame_settings).await?
+                self.process_full_quality(input_path, metadata, overlay_settings, frame_settings, preserve_metadata).await?
             }
             ProcessingRequestType::Both => {
-                self.process_both(input_path, metadata, overlay_settings, frame_settings).await?
+                self.process_both(input_path, metadata, overlay_settings, frame_settings, preserve_metadata).await?
             }
         };
 
@@ -96,7 +104,7 @@ impl UnifiedProcessingEngine {
     async fn process_preview(
         &self,
         input_path: &str,
-        _metadata: PhotoMetadata,
+        metadata: PhotoMetadata,
         overlay_settings: OverlaySettings,
         frame_settings: FrameSettings,
     ) -> Result<ProcessingResult> {
@@ -106,9 +114,11 @@ impl UnifiedProcessingEngine {
             max_height: 600,
             overlay_settings,
             frame_settings,
+            metadata_override: None,
         };
 
-        let preview_data = ImageProcessingService::generate_preview(input_path, preview_settings).await?;
+        // 使用调用方传入的元数据（可能已应用手动覆盖）
+        let preview_data = ImageProcessingService::render_preview(input_path, &metadata, &preview_settings)?;
 
         Ok(ProcessingResult::Preview(preview_data))
     }
@@ -120,6 +130,7 @@ impl UnifiedProcessingEngine {
         metadata: PhotoMetadata,
         overlay_settings: OverlaySettings,
         frame_settings: FrameSettings,
+        preserve_metadata: bool,
     ) -> Result<ProcessingResult> {
         let output_path = format!("/tmp/processed_{}.jpg", 
             SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis());
@@ -131,6 +142,7 @@ impl UnifiedProcessingEngine {
             frame_settings,
             &output_path,
             95, // 高质量
+            preserve_metadata,
         ).await?;
 
         // 读取处理后的文件
@@ -149,6 +161,7 @@ impl UnifiedProcessingEngine {
         metadata: PhotoMetadata,
         overlay_settings: OverlaySettings,
         frame_settings: FrameSettings,
+        preserve_metadata: bool,
     ) -> Result<ProcessingResult> {
         // 并行处理预览和完整质量
         let preview_future = self.process_preview(
@@ -162,7 +175,8 @@ impl UnifiedProcessingEngine {
             input_path, 
             metadata, 
             overlay_settings, 
-            frame_settings
+            frame_settings,
+            preserve_metadata,
         );
 
         let (preview_result, full_result) = tokio::try_join!(preview_future, full_future)?;
@@ -184,18 +198,23 @@ impl UnifiedProcessingEngine {
     fn generate_cache_key(
         &self,
         input_path: &str,
+        metadata: &PhotoMetadata,
         overlay_settings: &OverlaySettings,
         frame_settings: &FrameSettings,
         request_type: &ProcessingRequestType,
+        preserve_metadata: bool,
     ) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
         let mut hasher = DefaultHasher::new();
         input_path.hash(&mut hasher);
+        // 元数据可能被手动覆盖修改，同一张图片的不同元数据不能共用缓存
+        format!("{:?}", metadata).hash(&mut hasher);
         format!("{:?}", overlay_settings).hash(&mut hasher);
         format!("{:?}", frame_settings).hash(&mut hasher);
         format!("{:?}", request_type).hash(&mut hasher);
+        preserve_metadata.hash(&mut hasher);
 
         format!("unified_cache_{:x}", hasher.finish())
     }
@@ -215,19 +234,16 @@ impl UnifiedProcessingEngine {
                 preview_data: data.clone(),
                 full_data: None,
                 timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                settings_hash: key.clone(),
             },
             ProcessingResult::FullQuality(data) => CachedResult {
                 preview_data: Vec::new(),
                 full_data: Some(data.clone()),
                 timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                settings_hash: key.clone(),
             },
             ProcessingResult::Both { preview_data, full_data } => CachedResult {
                 preview_data: preview_data.clone(),
                 full_data: Some(full_data.clone()),
                 timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                settings_hash: key.clone(),
             },
         };
 
@@ -0,0 +1,1195 @@
+use crate::date_format::DateFormatter;
+use crate::exif_service::ExifService;
+use crate::metadata_fields::MetadataFields;
+use crate::types::*;
+use anyhow::{bail, Context, Result};
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::name::{QName, ResolveResult};
+use quick_xml::reader::NsReader;
+use quick_xml::Writer;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// 本应用保存叠加方案使用的命名空间
+const OVERLAY_NS: &str = "https://github.com/lyndon-nuu/photo-metadata-overlay/ns/1.0/";
+
+/// 已知命名空间的规范前缀，属性名统一使用这些前缀，与文件中实际使用的前缀无关
+const NAMESPACES: &[(&str, &str)] = &[
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("photoshop", "http://ns.adobe.com/photoshop/1.0/"),
+    ("exif", "http://ns.adobe.com/exif/1.0/"),
+    ("exifEX", "http://cipa.jp/exif/1.0/"),
+    ("tiff", "http://ns.adobe.com/tiff/1.0/"),
+    ("aux", "http://ns.adobe.com/exif/1.0/aux/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("Iptc4xmpCore", "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/"),
+    ("pmo", OVERLAY_NS),
+    ("rdf", RDF_NS),
+    ("xml", XML_NS),
+];
+
+/// IPTC Core 文本字段与XMP属性的对应关系，布尔值表示属性是否为语言替代数组（rdf:Alt）
+const IPTC_CORE_PROPERTIES: &[(&str, &str, bool)] = &[
+    ("title", "dc:title", true),
+    ("headline", "photoshop:Headline", false),
+    ("caption", "dc:description", true),
+    ("byline_title", "photoshop:AuthorsPosition", false),
+    ("credit", "photoshop:Credit", false),
+    ("source", "photoshop:Source", false),
+    ("sublocation", "Iptc4xmpCore:Location", false),
+    ("city", "photoshop:City", false),
+    ("province_state", "photoshop:State", false),
+    ("country", "photoshop:Country", false),
+    ("country_code", "Iptc4xmpCore:CountryCode", false),
+];
+
+/// 新建边车文件的模板
+const SIDECAR_TEMPLATE: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="photo-metadata-overlay">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""/>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+/// 解析后的XMP属性
+/// 键为规范前缀的属性名（如 "dc:creator"），结构体成员以 "/" 连接（如 "exif:Flash/exif:Fired"）；
+/// 数组属性按顺序保留全部条目，空元素表示该字段被清除
+#[derive(Debug, Default, Clone)]
+pub struct XmpPacket {
+    properties: BTreeMap<String, Vec<String>>,
+}
+
+impl XmpPacket {
+    /// 属性的第一个值；属性存在但为空时返回 `Some(None)`
+    pub fn value(&self, key: &str) -> Option<Option<&str>> {
+        self.properties
+            .get(key)
+            .map(|values| values.first().map(String::as_str))
+    }
+
+    /// 数组属性的全部条目
+    pub fn values(&self, key: &str) -> &[String] {
+        self.properties.get(key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// 按顺序取第一个存在的属性
+    fn first_of(&self, keys: &[&str]) -> Option<Option<&str>> {
+        keys.iter().find_map(|key| self.value(key))
+    }
+}
+
+/// 写入边车的属性值
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmpValue {
+    Text(String),
+    Seq(Vec<String>),
+    Bag(Vec<String>),
+    Alt(String),
+    /// 结构体（rdf:parseType="Resource"），成员名带前缀，如 "exif:Fired"
+    Struct(Vec<(&'static str, String)>),
+    /// 写入空元素，读取时清除对应字段
+    Empty,
+}
+
+/// XMP边车服务
+/// 读取 Lightroom / darktable 等软件保存在照片旁的 `.xmp` 文件，并把编辑结果写回边车
+pub struct XmpService;
+
+impl XmpService {
+    /// 查找图片对应的边车文件
+    /// 优先 `photo.jpg.xmp`（darktable），其次 `photo.xmp`（Lightroom、Capture One）
+    pub fn find_sidecar<P: AsRef<Path>>(image_path: P) -> Option<PathBuf> {
+        Self::sidecar_candidates(image_path.as_ref())
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+    }
+
+    fn sidecar_candidates(image_path: &Path) -> Vec<PathBuf> {
+        let mut candidates = Self::own_sidecar_candidates(image_path);
+        candidates.push(image_path.with_extension("xmp"));
+        candidates.push(image_path.with_extension("XMP"));
+        candidates
+    }
+
+    /// 带完整文件名的边车 `photo.jpg.xmp`，同名的RAW+JPEG不会共用
+    fn own_sidecar_candidates(image_path: &Path) -> Vec<PathBuf> {
+        let Some(file_name) = image_path.file_name() else {
+            return Vec::new();
+        };
+        ["xmp", "XMP"]
+            .iter()
+            .map(|extension| {
+                let mut name = file_name.to_os_string();
+                name.push(".");
+                name.push(extension);
+                image_path.with_file_name(name)
+            })
+            .collect()
+    }
+
+    /// 读取并解析边车文件
+    pub fn read_sidecar<P: AsRef<Path>>(sidecar_path: P) -> Result<XmpPacket> {
+        let content = std::fs::read_to_string(&sidecar_path)
+            .with_context(|| format!("Failed to read XMP sidecar: {:?}", sidecar_path.as_ref()))?;
+        Self::parse(&content)
+    }
+
+    /// 解析XMP数据包
+    /// 同时支持属性写在 rdf:Description 的XML属性上和写成子元素两种形式
+    pub fn parse(content: &str) -> Result<XmpPacket> {
+        let mut reader = NsReader::from_str(content);
+        let mut packet = XmpPacket::default();
+        // 当前元素路径，rdf与外层x:xmpmeta元素记为None
+        let mut path: Vec<Option<String>> = Vec::new();
+
+        loop {
+            match reader.read_resolved_event().context("Invalid XMP data")? {
+                (namespace, Event::Start(element)) => {
+                    let key = Self::property_key(&namespace, &element);
+                    path.push(key);
+                    Self::collect_attributes(&reader, &element, &path, &mut packet);
+                }
+                (namespace, Event::Empty(element)) => {
+                    path.push(Self::property_key(&namespace, &element));
+                    Self::collect_attributes(&reader, &element, &path, &mut packet);
+                    path.pop();
+                }
+                (_, Event::End(_)) => {
+                    path.pop();
+                }
+                (_, Event::Text(text)) => {
+                    let text = text.unescape().context("Invalid XMP text")?;
+                    let text = text.trim();
+                    if let Some(key) = Self::joined_path(&path).filter(|_| !text.is_empty()) {
+                        packet.properties.entry(key).or_default().push(text.to_string());
+                    }
+                }
+                (_, Event::CData(data)) => {
+                    if let Some(key) = Self::joined_path(&path) {
+                        let text = String::from_utf8_lossy(&data).trim().to_string();
+                        packet.properties.entry(key).or_default().push(text);
+                    }
+                }
+                (_, Event::Eof) => break,
+                _ => {}
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// 元素对应的属性名，rdf语法元素与x:xmpmeta返回None
+    fn property_key(namespace: &ResolveResult, element: &BytesStart) -> Option<String> {
+        let local_name = String::from_utf8_lossy(element.local_name().as_ref()).to_string();
+        match namespace {
+            ResolveResult::Bound(ns) => {
+                let uri = String::from_utf8_lossy(ns.as_ref());
+                if uri == RDF_NS || uri == "adobe:ns:meta/" {
+                    return None;
+                }
+                Some(Self::qualified_name(&uri, &local_name, element.name()))
+            }
+            _ => Some(String::from_utf8_lossy(element.name().as_ref()).to_string()),
+        }
+    }
+
+    /// 已知命名空间使用规范前缀，未知命名空间沿用文件中的前缀
+    fn qualified_name(uri: &str, local_name: &str, raw_name: QName) -> String {
+        match NAMESPACES.iter().find(|(_, known)| *known == uri) {
+            Some((prefix, _)) => format!("{}:{}", prefix, local_name),
+            None => String::from_utf8_lossy(raw_name.as_ref()).to_string(),
+        }
+    }
+
+    fn joined_path(path: &[Option<String>]) -> Option<String> {
+        let parts: Vec<&str> = path.iter().flatten().map(String::as_str).collect();
+        (!parts.is_empty()).then(|| parts.join("/"))
+    }
+
+    /// 记录元素本身（空元素表示字段被清除）和写在XML属性上的XMP属性
+    fn collect_attributes(
+        reader: &NsReader<&[u8]>,
+        element: &BytesStart,
+        path: &[Option<String>],
+        packet: &mut XmpPacket,
+    ) {
+        let parent = Self::joined_path(path);
+        if let Some(parent) = &parent {
+            packet.properties.entry(parent.clone()).or_default();
+        }
+
+        for attribute in element.attributes().flatten() {
+            let raw_key = attribute.key;
+            if raw_key.as_namespace_binding().is_some() {
+                continue;
+            }
+
+            let (namespace, local_name) = reader.resolve_attribute(raw_key);
+            let ResolveResult::Bound(ns) = namespace else {
+                continue;
+            };
+            let uri = String::from_utf8_lossy(ns.as_ref()).to_string();
+            if uri == RDF_NS || uri == XML_NS || uri == "adobe:ns:meta/" {
+                continue;
+            }
+
+            let name = Self::qualified_name(&uri, &String::from_utf8_lossy(local_name.as_ref()), raw_key);
+            let key = match &parent {
+                Some(parent) => format!("{}/{}", parent, name),
+                None => name,
+            };
+            if let Ok(value) = attribute.unescape_value() {
+                packet.properties.entry(key).or_default().push(value.trim().to_string());
+            }
+        }
+    }
+
+    /// 将XMP中的字段合并到元数据，覆盖EXIF中的同名字段
+    pub fn apply_to_metadata(metadata: &mut PhotoMetadata, packet: &XmpPacket) {
+        Self::apply_text(&mut metadata.camera.make, packet, &["tiff:Make"]);
+        Self::apply_text(&mut metadata.camera.model, packet, &["tiff:Model"]);
+        Self::apply_text(&mut metadata.camera.lens_make, packet, &["exifEX:LensMake"]);
+        Self::apply_text(&mut metadata.camera.lens_model, packet, &["exifEX:LensModel", "aux:Lens"]);
+        Self::apply_text(&mut metadata.camera.serial_number, packet, &["exifEX:BodySerialNumber", "aux:SerialNumber"]);
+
+        Self::apply_number(&mut metadata.settings.aperture, packet, &["exif:FNumber"], ExifService::aperture_text);
+        Self::apply_number(&mut metadata.settings.shutter_speed, packet, &["exif:ExposureTime"], ExifService::shutter_speed_text);
+        Self::apply_number(&mut metadata.settings.focal_length, packet, &["exif:FocalLength"], ExifService::focal_length_text);
+        Self::apply_number(&mut metadata.settings.focal_length_35mm, packet, &["exif:FocalLengthIn35mmFilm"], ExifService::focal_length_text);
+        Self::apply_number(&mut metadata.settings.exposure_bias, packet, &["exif:ExposureBiasValue"], ExifService::exposure_bias_text);
+
+        if let Some(iso) = packet.first_of(&["exifEX:PhotographicSensitivity", "exif:ISOSpeedRatings"]) {
+            metadata.settings.iso = iso.and_then(|value| value.parse().ok());
+        }
+        if let Some(distance) = packet.value("exif:SubjectDistance") {
+            metadata.settings.subject_distance = distance.and_then(Self::parse_rational).filter(|distance| *distance > 0.0);
+        }
+
+        // 数值型枚举借用kamadak-exif的描述文本，与EXIF中读到的值保持一致
+        Self::apply_enum(&mut metadata.settings.metering_mode, packet, "exif:MeteringMode", exif::Tag::MeteringMode);
+        Self::apply_enum(&mut metadata.settings.exposure_program, packet, "exif:ExposureProgram", exif::Tag::ExposureProgram);
+        Self::apply_enum(&mut metadata.settings.white_balance, packet, "exif:WhiteBalance", exif::Tag::WhiteBalance);
+        if packet.value("exif:Flash").is_some() {
+            metadata.settings.flash = Self::flash_code(packet).map(|code| Self::enum_text(exif::Tag::Flash, code));
+        }
+        Self::apply_text(&mut metadata.settings.film_simulation, packet, &["pmo:FilmSimulation"]);
+        Self::apply_text(&mut metadata.software, packet, &["xmp:CreatorTool"]);
+
+        if let Some(date) = packet.first_of(&["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"]) {
+            let capture_time = date.and_then(|date| DateFormatter::parse_iso8601(date, TimestampSource::Xmp));
+            metadata.timestamp = capture_time.as_ref().map(DateFormatter::to_exif_string);
+            metadata.capture_time = capture_time;
+        }
+
+        if let (Some(latitude), Some(longitude)) = (packet.value("exif:GPSLatitude"), packet.value("exif:GPSLongitude")) {
+            let coordinates = latitude
+                .and_then(Self::parse_gps_coordinate)
+                .zip(longitude.and_then(Self::parse_gps_coordinate));
+            // 只替换坐标，保留EXIF中读到的海拔、方向等附加信息
+            metadata.location = coordinates.map(|(latitude, longitude)| LocationInfo {
+                latitude,
+                longitude,
+                ..metadata.location.take().unwrap_or_default()
+            });
+        }
+
+        if let Some(location) = metadata.location.as_mut() {
+            if let Some(altitude) = packet.value("exif:GPSAltitude") {
+                let below_sea_level = packet.value("exif:GPSAltitudeRef").flatten() == Some("1");
+                location.altitude = altitude
+                    .and_then(Self::parse_rational)
+                    .map(|altitude| if below_sea_level { -altitude } else { altitude });
+            }
+            if let Some(direction) = packet.value("exif:GPSImgDirection") {
+                location.image_direction = direction.and_then(Self::parse_rational);
+            }
+            Self::apply_text(&mut location.image_direction_ref, packet, &["exif:GPSImgDirectionRef"]);
+            Self::apply_text(&mut location.map_datum, packet, &["exif:GPSMapDatum"]);
+            if let Some(time) = packet.value("exif:GPSTimeStamp") {
+                location.gps_time = time.and_then(|time| DateFormatter::parse_iso8601(time, TimestampSource::Gps));
+            }
+        }
+
+        Self::apply_text(&mut metadata.artist, packet, &["dc:creator"]);
+        Self::apply_text(&mut metadata.copyright, packet, &["dc:rights"]);
+        Self::apply_iptc_core(metadata, packet);
+    }
+
+    /// 将XMP中的IPTC Core字段合并到元数据
+    pub fn apply_iptc_core(metadata: &mut PhotoMetadata, packet: &XmpPacket) {
+        let has_iptc = IPTC_CORE_PROPERTIES
+            .iter()
+            .map(|(_, property, _)| *property)
+            .chain(["dc:subject", "dc:creator", "dc:rights"])
+            .any(|property| packet.value(property).is_some());
+        if !has_iptc {
+            return;
+        }
+
+        let iptc = metadata.iptc.get_or_insert_with(IptcInfo::default);
+        for (field, property, _) in IPTC_CORE_PROPERTIES {
+            if let Some(target) = Self::iptc_text_field(iptc, field) {
+                Self::apply_text(target, packet, &[property]);
+            }
+        }
+        Self::apply_text(&mut iptc.byline, packet, &["dc:creator"]);
+        Self::apply_text(&mut iptc.copyright_notice, packet, &["dc:rights"]);
+        if packet.value("dc:subject").is_some() {
+            iptc.keywords = packet.values("dc:subject").to_vec();
+        }
+    }
+
+    fn iptc_text_field<'a>(iptc: &'a mut IptcInfo, field: &str) -> Option<&'a mut Option<String>> {
+        Some(match field {
+            "title" => &mut iptc.title,
+            "headline" => &mut iptc.headline,
+            "caption" => &mut iptc.caption,
+            "byline_title" => &mut iptc.byline_title,
+            "credit" => &mut iptc.credit,
+            "source" => &mut iptc.source,
+            "sublocation" => &mut iptc.sublocation,
+            "city" => &mut iptc.city,
+            "province_state" => &mut iptc.province_state,
+            "country" => &mut iptc.country,
+            "country_code" => &mut iptc.country_code,
+            _ => return None,
+        })
+    }
+
+    fn apply_text(target: &mut Option<String>, packet: &XmpPacket, keys: &[&str]) {
+        if let Some(value) = packet.first_of(keys) {
+            *target = value.filter(|value| !value.is_empty()).map(str::to_string);
+        }
+    }
+
+    fn apply_number(target: &mut Option<String>, packet: &XmpPacket, keys: &[&str], format: fn(f64) -> String) {
+        if let Some(value) = packet.first_of(keys) {
+            *target = value.and_then(Self::parse_rational).map(format);
+        }
+    }
+
+    fn apply_enum(target: &mut Option<String>, packet: &XmpPacket, key: &str, tag: exif::Tag) {
+        if let Some(value) = packet.value(key) {
+            *target = value
+                .and_then(|value| value.parse::<u16>().ok())
+                .map(|value| Self::enum_text(tag, value));
+        }
+    }
+
+    /// EXIF枚举值的描述文本
+    fn enum_text(tag: exif::Tag, code: u16) -> String {
+        let field = exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Short(vec![code]),
+        };
+        field.display_value().to_string()
+    }
+
+    /// 描述文本对应的EXIF枚举值（`enum_text` 的逆运算），也接受数字
+    fn enum_code(tag: exif::Tag, text: &str) -> Option<u16> {
+        let text = text.trim();
+        text.parse()
+            .ok()
+            .or_else(|| (0..=u8::MAX.into()).find(|code| Self::enum_text(tag, *code).eq_ignore_ascii_case(text)))
+    }
+
+    /// 由 exif:Flash 结构体的成员还原EXIF闪光灯值，没有 exif:Fired 时视为没有闪光灯信息
+    fn flash_code(packet: &XmpPacket) -> Option<u16> {
+        let member = |name: &str| packet.value(&format!("exif:Flash/exif:{}", name)).flatten();
+        let flag = |name: &str| u16::from(member(name).is_some_and(|value| value.eq_ignore_ascii_case("true")));
+        let number = |name: &str| member(name).and_then(|value| value.parse::<u16>().ok()).unwrap_or(0) & 3;
+
+        member("Fired")?;
+        Some(flag("Fired") | number("Return") << 1 | number("Mode") << 3 | flag("Function") << 5 | flag("RedEyeMode") << 6)
+    }
+
+    /// 解析XMP有理数（"28/10"）或小数
+    fn parse_rational(text: &str) -> Option<f64> {
+        match text.split_once('/') {
+            Some((numerator, denominator)) => {
+                let denominator: f64 = denominator.trim().parse().ok()?;
+                (denominator != 0.0).then_some(numerator.trim().parse::<f64>().ok()? / denominator)
+            }
+            None => text.trim().parse().ok(),
+        }
+    }
+
+    /// 解析XMP的GPS坐标（"31,12.345N" 或 "31,12,20.7N"）
+    fn parse_gps_coordinate(text: &str) -> Option<f64> {
+        let text = text.trim();
+        let reference = text.chars().last()?;
+        let parts: Vec<f64> = text[..text.len() - reference.len_utf8()]
+            .split(',')
+            .map(|part| part.trim().parse())
+            .collect::<Result<_, _>>()
+            .ok()?;
+
+        let coordinate = match parts.as_slice() {
+            [degrees, minutes] => degrees + minutes / 60.0,
+            [degrees, minutes, seconds] => degrees + minutes / 60.0 + seconds / 3600.0,
+            _ => return None,
+        };
+
+        match reference.to_ascii_uppercase() {
+            'N' | 'E' => Some(coordinate),
+            'S' | 'W' => Some(-coordinate),
+            _ => None,
+        }
+    }
+
+    /// 将叠加方案和编辑过的元数据写入边车文件，返回边车路径
+    /// 只写入与图片当前元数据不同的字段，已有边车中的其他内容（如Lightroom的调整参数）保持不变
+    pub fn write_sidecar<P: AsRef<Path>>(
+        image_path: P,
+        metadata: &PhotoMetadata,
+        overlay_settings: &OverlaySettings,
+    ) -> Result<PathBuf> {
+        let image_path = image_path.as_ref();
+        let original = ExifService::extract_metadata(image_path)?;
+
+        let mut updates = Self::edited_properties(&original, metadata)?;
+        updates.push((
+            "pmo:OverlaySettings",
+            XmpValue::Text(serde_json::to_string(overlay_settings)?),
+        ));
+
+        Self::update_sidecar(image_path, &updates)
+    }
+
+    /// 将属性合并到图片自己的边车文件 `photo.jpg.xmp`
+    /// 只有 `photo.xmp` 时以其内容为基础新建，`photo.xmp` 本身可能属于同名的RAW或JPEG，不写回
+    pub fn update_sidecar(image_path: &Path, updates: &[(&str, XmpValue)]) -> Result<PathBuf> {
+        let candidates = Self::own_sidecar_candidates(image_path);
+        let sidecar_path = candidates
+            .iter()
+            .find(|candidate| candidate.is_file())
+            .or(candidates.first())
+            .cloned()
+            .context("Invalid image path")?;
+        let existing = match Self::find_sidecar(image_path) {
+            Some(source) => std::fs::read_to_string(&source)
+                .with_context(|| format!("Failed to read XMP sidecar: {:?}", source))?,
+            None => SIDECAR_TEMPLATE.to_string(),
+        };
+
+        let content = Self::merge_properties(&existing, updates)?;
+        std::fs::write(&sidecar_path, content)
+            .with_context(|| format!("Failed to write XMP sidecar: {:?}", sidecar_path))?;
+
+        println!("📝 Wrote XMP sidecar: {:?} ({} properties)", sidecar_path, updates.len());
+        Ok(sidecar_path)
+    }
+
+    /// 对比原始元数据与编辑后的元数据，生成需要写入的XMP属性
+    /// 有无法用XMP表示的改动（如不是EXIF定义的测光模式）时返回错误，列出这些字段
+    fn edited_properties(original: &PhotoMetadata, edited: &PhotoMetadata) -> Result<Vec<(&'static str, XmpValue)>> {
+        let before = MetadataFields::flatten(original);
+        let after = MetadataFields::flatten(edited);
+        let changed = |key: &str| before.get(key) != after.get(key);
+
+        let text = |value: &Option<String>| value.clone().map_or(XmpValue::Empty, XmpValue::Text);
+        let number = |value: &Option<String>, scale: f64| {
+            value
+                .as_deref()
+                .and_then(ExifService::display_number)
+                .map_or(XmpValue::Empty, |number| XmpValue::Text(Self::rational_text(number, scale)))
+        };
+
+        let camera = &edited.camera;
+        let settings = &edited.settings;
+        let mut updates = Vec::new();
+        let mut unwritable = Vec::new();
+
+        if changed("camera.make") {
+            updates.push(("tiff:Make", text(&camera.make)));
+        }
+        if changed("camera.model") {
+            updates.push(("tiff:Model", text(&camera.model)));
+        }
+        if changed("camera.lens_make") {
+            updates.push(("exifEX:LensMake", text(&camera.lens_make)));
+        }
+        if changed("camera.lens_model") {
+            updates.push(("exifEX:LensModel", text(&camera.lens_model)));
+            updates.push(("aux:Lens", text(&camera.lens_model)));
+        }
+        if changed("camera.serial_number") {
+            updates.push(("exifEX:BodySerialNumber", text(&camera.serial_number)));
+            updates.push(("aux:SerialNumber", XmpValue::Empty));
+        }
+        if changed("settings.aperture") {
+            updates.push(("exif:FNumber", number(&settings.aperture, 10.0)));
+        }
+        if changed("settings.shutter_speed") {
+            let value = settings.shutter_speed.as_deref().and_then(Self::shutter_rational);
+            updates.push(("exif:ExposureTime", value.map_or(XmpValue::Empty, XmpValue::Text)));
+        }
+        if changed("settings.focal_length") {
+            updates.push(("exif:FocalLength", number(&settings.focal_length, 10.0)));
+        }
+        if changed("settings.focal_length_35mm") || changed("settings.focal_length_35mm_mm") {
+            // 只改了显示文本时数值还是旧的
+            let value = if changed("settings.focal_length_35mm_mm") {
+                settings.focal_length_35mm_mm
+            } else {
+                settings.focal_length_35mm.as_deref().and_then(ExifService::display_number)
+            };
+            updates.push(("exif:FocalLengthIn35mmFilm", value.map_or(XmpValue::Empty, |value| XmpValue::Text(format!("{:.0}", value)))));
+        }
+        if changed("settings.exposure_bias") {
+            updates.push(("exif:ExposureBiasValue", number(&settings.exposure_bias, 10.0)));
+        }
+        if changed("settings.iso") {
+            let value = settings.iso.map_or(XmpValue::Empty, |iso| XmpValue::Seq(vec![iso.to_string()]));
+            updates.push(("exif:ISOSpeedRatings", value));
+            updates.push(("exifEX:PhotographicSensitivity", XmpValue::Empty));
+        }
+        if changed("settings.subject_distance") {
+            let value = settings.subject_distance.map_or(XmpValue::Empty, |distance| XmpValue::Text(Self::rational_text(distance, 100.0)));
+            updates.push(("exif:SubjectDistance", value));
+        }
+
+        // 数值型枚举写入EXIF定义的数值
+        let enums = [
+            ("settings.metering_mode", "exif:MeteringMode", exif::Tag::MeteringMode, &settings.metering_mode),
+            ("settings.exposure_program", "exif:ExposureProgram", exif::Tag::ExposureProgram, &settings.exposure_program),
+            ("settings.white_balance", "exif:WhiteBalance", exif::Tag::WhiteBalance, &settings.white_balance),
+        ];
+        for (field, property, tag, value) in enums {
+            if !changed(field) {
+                continue;
+            }
+            match value.as_deref().map(|value| Self::enum_code(tag, value)) {
+                Some(Some(code)) => updates.push((property, XmpValue::Text(code.to_string()))),
+                Some(None) => unwritable.push(field),
+                None => updates.push((property, XmpValue::Empty)),
+            }
+        }
+        if changed("settings.flash") {
+            match settings.flash.as_deref().map(|value| Self::enum_code(exif::Tag::Flash, value)) {
+                Some(Some(code)) => updates.push(("exif:Flash", Self::flash_struct(code))),
+                Some(None) => unwritable.push("settings.flash"),
+                None => updates.push(("exif:Flash", XmpValue::Empty)),
+            }
+        }
+        if changed("settings.film_simulation") {
+            updates.push(("pmo:FilmSimulation", text(&settings.film_simulation)));
+        }
+        if changed("software") {
+            updates.push(("xmp:CreatorTool", text(&edited.software)));
+        }
+        if changed("capture_time") || changed("timestamp") {
+            let value = edited
+                .capture_time
+                .as_ref()
+                .map_or(XmpValue::Empty, |time| XmpValue::Text(DateFormatter::to_iso8601(time)));
+            updates.push(("exif:DateTimeOriginal", value.clone()));
+            updates.push(("photoshop:DateCreated", value));
+        }
+        if changed("location.latitude") || changed("location.longitude") {
+            let (latitude, longitude) = match &edited.location {
+                Some(location) => (
+                    XmpValue::Text(Self::gps_text(location.latitude, 'N', 'S')),
+                    XmpValue::Text(Self::gps_text(location.longitude, 'E', 'W')),
+                ),
+                None => (XmpValue::Empty, XmpValue::Empty),
+            };
+            updates.push(("exif:GPSLatitude", latitude));
+            updates.push(("exif:GPSLongitude", longitude));
+        }
+        let location = edited.location.as_ref();
+        if changed("location.altitude") {
+            let (altitude, reference) = match location.and_then(|location| location.altitude) {
+                Some(altitude) => (
+                    XmpValue::Text(Self::rational_text(altitude.abs(), 10.0)),
+                    XmpValue::Text(if altitude < 0.0 { "1" } else { "0" }.to_string()),
+                ),
+                None => (XmpValue::Empty, XmpValue::Empty),
+            };
+            updates.push(("exif:GPSAltitude", altitude));
+            updates.push(("exif:GPSAltitudeRef", reference));
+        }
+        if changed("location.image_direction") {
+            let value = location
+                .and_then(|location| location.image_direction)
+                .map_or(XmpValue::Empty, |direction| XmpValue::Text(Self::rational_text(direction, 100.0)));
+            updates.push(("exif:GPSImgDirection", value));
+        }
+        if changed("location.image_direction_ref") {
+            updates.push(("exif:GPSImgDirectionRef", text(&location.and_then(|location| location.image_direction_ref.clone()))));
+        }
+        if changed("artist") {
+            let value = edited.artist.clone().map_or(XmpValue::Empty, |artist| XmpValue::Seq(vec![artist]));
+            updates.push(("dc:creator", value));
+        }
+        if changed("copyright") {
+            let value = edited.copyright.clone().map_or(XmpValue::Empty, XmpValue::Alt);
+            updates.push(("dc:rights", value));
+        }
+
+        // IPTC Core；作者与版权和EXIF字段共用属性，两边都改动时以EXIF字段为准
+        let mut iptc = edited.iptc.clone().unwrap_or_default();
+        for (field, property, alt) in IPTC_CORE_PROPERTIES {
+            if !changed(&format!("iptc.{}", field)) {
+                continue;
+            }
+            let value = Self::iptc_text_field(&mut iptc, field).and_then(|value| value.clone());
+            updates.push((property, match value {
+                Some(value) if *alt => XmpValue::Alt(value),
+                Some(value) => XmpValue::Text(value),
+                None => XmpValue::Empty,
+            }));
+        }
+        if changed("iptc.keywords") {
+            let value = if iptc.keywords.is_empty() { XmpValue::Empty } else { XmpValue::Bag(iptc.keywords.clone()) };
+            updates.push(("dc:subject", value));
+        }
+        if changed("iptc.byline") && !changed("artist") {
+            let value = iptc.byline.clone().map_or(XmpValue::Empty, |byline| XmpValue::Seq(vec![byline]));
+            updates.push(("dc:creator", value));
+        }
+        if changed("iptc.copyright_notice") && !changed("copyright") {
+            let value = iptc.copyright_notice.clone().map_or(XmpValue::Empty, XmpValue::Alt);
+            updates.push(("dc:rights", value));
+        }
+
+        if !unwritable.is_empty() {
+            bail!("Cannot write edited fields to XMP: {}", unwritable.join(", "));
+        }
+        Ok(updates)
+    }
+
+    /// EXIF闪光灯值转换为 exif:Flash 结构体
+    fn flash_struct(code: u16) -> XmpValue {
+        let flag = |bit: u16| if code >> bit & 1 == 1 { "True" } else { "False" }.to_string();
+        XmpValue::Struct(vec![
+            ("exif:Fired", flag(0)),
+            ("exif:Return", (code >> 1 & 3).to_string()),
+            ("exif:Mode", (code >> 3 & 3).to_string()),
+            ("exif:Function", flag(5)),
+            ("exif:RedEyeMode", flag(6)),
+        ])
+    }
+
+    fn rational_text(value: f64, scale: f64) -> String {
+        format!("{}/{}", (value * scale).round() as i64, scale as i64)
+    }
+
+    /// 快门速度显示文本转换为有理数（"1/250" 保持不变，"2s" → "2/1"）
+    fn shutter_rational(text: &str) -> Option<String> {
+        if let Some((numerator, denominator)) = text.split_once('/') {
+            let denominator = denominator.trim_end_matches('s');
+            return Some(format!("{}/{}", numerator.parse::<u32>().ok()?, denominator.parse::<u32>().ok()?));
+        }
+        ExifService::display_number(text).map(|seconds| Self::rational_text(seconds, 10.0))
+    }
+
+    /// 十进制坐标转换为XMP的 "度,分.小数分" 形式
+    fn gps_text(coordinate: f64, positive: char, negative: char) -> String {
+        let reference = if coordinate < 0.0 { negative } else { positive };
+        let coordinate = coordinate.abs();
+        let degrees = coordinate.trunc();
+        format!("{},{:.6}{}", degrees as u32, (coordinate - degrees) * 60.0, reference)
+    }
+
+    /// 把属性写入第一个 rdf:Description，替换其中同名的旧属性，其余内容原样保留
+    fn merge_properties(existing: &str, updates: &[(&str, XmpValue)]) -> Result<String> {
+        let mut reader = NsReader::from_str(existing);
+        let mut writer = Writer::new(Vec::new());
+
+        let mut depth = 0usize;
+        let mut description_depth: Option<usize> = None;
+        let mut skip_depth: Option<usize> = None;
+        let mut merged = false;
+        // 元素之间的空白暂存起来，被替换的属性连同它前面的缩进一起去掉
+        let mut pending_whitespace: Option<Event> = None;
+
+        loop {
+            let (namespace, event) = reader.read_resolved_event().context("Invalid XMP sidecar")?;
+
+            // 跳过被替换属性的全部内容
+            if let Some(skip) = skip_depth {
+                match event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => {
+                        if depth == skip {
+                            skip_depth = None;
+                        }
+                        depth -= 1;
+                    }
+                    Event::Eof => break,
+                    _ => {}
+                }
+                continue;
+            }
+
+            let replaced = match &event {
+                Event::Start(element) | Event::Empty(element) => {
+                    description_depth == Some(depth)
+                        && is_update_key(&Self::property_key(&namespace, element), updates)
+                }
+                _ => false,
+            };
+            if replaced {
+                pending_whitespace = None;
+                if matches!(event, Event::Start(_)) {
+                    depth += 1;
+                    skip_depth = Some(depth);
+                }
+                continue;
+            }
+
+            if let Event::Text(text) = &event {
+                if text.iter().all(u8::is_ascii_whitespace) {
+                    pending_whitespace = Some(event);
+                    continue;
+                }
+            }
+
+            let closes_description = matches!(event, Event::End(_)) && description_depth == Some(depth);
+            if closes_description {
+                pending_whitespace = None;
+            } else if let Some(whitespace) = pending_whitespace.take() {
+                writer.write_event(whitespace)?;
+            }
+
+            match event {
+                Event::Start(element) => {
+                    depth += 1;
+                    if !merged && description_depth.is_none() && Self::is_description(&namespace, &element) {
+                        writer.write_event(Event::Start(Self::description_start(&reader, &element, updates)?))?;
+                        description_depth = Some(depth);
+                    } else {
+                        writer.write_event(Event::Start(element))?;
+                    }
+                }
+                Event::Empty(element) => {
+                    if !merged && description_depth.is_none() && Self::is_description(&namespace, &element) {
+                        let start = Self::description_start(&reader, &element, updates)?;
+                        let end = start.to_end().into_owned();
+                        writer.write_event(Event::Start(start))?;
+                        Self::write_properties(writer.get_mut(), updates, depth + 1);
+                        writer.write_event(Event::End(end))?;
+                        merged = true;
+                    } else {
+                        writer.write_event(Event::Empty(element))?;
+                    }
+                }
+                Event::End(element) => {
+                    if closes_description {
+                        Self::write_properties(writer.get_mut(), updates, depth);
+                        description_depth = None;
+                        merged = true;
+                    }
+                    writer.write_event(Event::End(element))?;
+                    depth = depth.saturating_sub(1);
+                }
+                Event::Eof => break,
+                event => writer.write_event(event)?,
+            }
+        }
+
+        if !merged {
+            bail!("XMP sidecar has no rdf:Description");
+        }
+
+        String::from_utf8(writer.into_inner()).context("Invalid UTF-8 in XMP sidecar")
+    }
+
+    /// 替换数据包中已有属性的值，XML属性和简单元素两种形式都会替换，不存在的属性不新增
+    /// 用于嵌入输出图片前修正方向、像素尺寸等已过时的字段
+    pub fn replace_values(content: &str, values: &[(&str, String)]) -> Result<String> {
+        let mut reader = NsReader::from_str(content);
+        let mut writer = Writer::new(Vec::new());
+        // 正在替换内容的属性元素对应的新值
+        let mut replacing: Option<&str> = None;
+
+        loop {
+            let (namespace, event) = reader.read_resolved_event().context("Invalid XMP data")?;
+            match event {
+                Event::Start(element) => {
+                    let key = Self::property_key(&namespace, &element);
+                    replacing = values
+                        .iter()
+                        .find(|(name, _)| key.as_deref() == Some(*name))
+                        .map(|(_, value)| value.as_str());
+                    writer.write_event(Event::Start(Self::replace_attributes(&reader, &element, values)?))?;
+                }
+                Event::Empty(element) => {
+                    writer.write_event(Event::Empty(Self::replace_attributes(&reader, &element, values)?))?;
+                }
+                Event::Text(text) => match replacing {
+                    Some(value) => writer.write_event(Event::Text(BytesText::new(value)))?,
+                    None => writer.write_event(Event::Text(text))?,
+                },
+                Event::End(element) => {
+                    replacing = None;
+                    writer.write_event(Event::End(element))?;
+                }
+                Event::Eof => break,
+                event => writer.write_event(event)?,
+            }
+        }
+
+        String::from_utf8(writer.into_inner()).context("Invalid UTF-8 in XMP data")
+    }
+
+    /// 重建开始标签，替换写在XML属性上的XMP属性值
+    fn replace_attributes(
+        reader: &NsReader<&[u8]>,
+        element: &BytesStart,
+        values: &[(&str, String)],
+    ) -> Result<BytesStart<'static>> {
+        let mut start = BytesStart::new(String::from_utf8_lossy(element.name().as_ref()).to_string());
+
+        for attribute in element.attributes() {
+            let attribute = attribute.context("Invalid XMP attribute")?;
+            let (namespace, local_name) = reader.resolve_attribute(attribute.key);
+            let value = match namespace {
+                ResolveResult::Bound(ns) if attribute.key.as_namespace_binding().is_none() => {
+                    let key = Self::qualified_name(
+                        &String::from_utf8_lossy(ns.as_ref()),
+                        &String::from_utf8_lossy(local_name.as_ref()),
+                        attribute.key,
+                    );
+                    values.iter().find(|(name, _)| *name == key).map(|(_, value)| value)
+                }
+                _ => None,
+            };
+            match value {
+                Some(value) => start.push_attribute((attribute.key.as_ref(), value.as_bytes())),
+                None => start.push_attribute(attribute),
+            }
+        }
+
+        Ok(start)
+    }
+
+    fn is_description(namespace: &ResolveResult, element: &BytesStart) -> bool {
+        matches!(namespace, ResolveResult::Bound(ns) if ns.as_ref() == RDF_NS.as_bytes())
+            && element.local_name().as_ref() == b"Description"
+    }
+
+    /// 重建 rdf:Description 开始标签：去掉将被替换的XML属性，补充缺少的命名空间声明
+    fn description_start(
+        reader: &NsReader<&[u8]>,
+        element: &BytesStart,
+        updates: &[(&str, XmpValue)],
+    ) -> Result<BytesStart<'static>> {
+        let mut start = BytesStart::new(String::from_utf8_lossy(element.name().as_ref()).to_string());
+
+        for attribute in element.attributes() {
+            let attribute = attribute.context("Invalid XMP attribute")?;
+            let (namespace, local_name) = reader.resolve_attribute(attribute.key);
+            let key = match namespace {
+                ResolveResult::Bound(ns) => Some(Self::qualified_name(
+                    &String::from_utf8_lossy(ns.as_ref()),
+                    &String::from_utf8_lossy(local_name.as_ref()),
+                    attribute.key,
+                )),
+                _ => None,
+            };
+            if attribute.key.as_namespace_binding().is_none() && is_update_key(&key, updates) {
+                continue;
+            }
+            start.push_attribute(attribute);
+        }
+
+        let mut prefixes: Vec<&str> = updates
+            .iter()
+            .filter_map(|(name, _)| name.split_once(':').map(|(prefix, _)| prefix))
+            .collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+
+        for prefix in prefixes {
+            let Some((_, uri)) = NAMESPACES.iter().find(|(known, _)| *known == prefix) else {
+                continue;
+            };
+            let probe = format!("{}:probe", prefix);
+            match reader.resolve_element(QName(probe.as_bytes())).0 {
+                ResolveResult::Bound(ns) if ns.as_ref() == uri.as_bytes() => {}
+                ResolveResult::Bound(_) => bail!("XMP sidecar binds prefix '{}' to another namespace", prefix),
+                _ => start.push_attribute((format!("xmlns:{}", prefix).as_str(), *uri)),
+            }
+        }
+
+        Ok(start)
+    }
+
+    fn write_properties(output: &mut Vec<u8>, updates: &[(&str, XmpValue)], depth: usize) {
+        let indent = " ".repeat(depth);
+        let escape = |text: &str| quick_xml::escape::partial_escape(text).to_string();
+
+        for (name, value) in updates {
+            let element = match value {
+                XmpValue::Text(text) => format!("<{name}>{}</{name}>", escape(text)),
+                XmpValue::Seq(items) | XmpValue::Bag(items) => {
+                    let container = if matches!(value, XmpValue::Seq(_)) { "rdf:Seq" } else { "rdf:Bag" };
+                    let items: String = items
+                        .iter()
+                        .map(|item| format!("<rdf:li>{}</rdf:li>", escape(item)))
+                        .collect();
+                    format!("<{name}><{container}>{items}</{container}></{name}>")
+                }
+                XmpValue::Alt(text) => format!(
+                    "<{name}><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></{name}>",
+                    escape(text)
+                ),
+                XmpValue::Struct(members) => {
+                    let members: String = members
+                        .iter()
+                        .map(|(member, text)| format!("<{member}>{}</{member}>", escape(text)))
+                        .collect();
+                    format!("<{name} rdf:parseType=\"Resource\">{members}</{name}>")
+                }
+                XmpValue::Empty => format!("<{name}/>"),
+            };
+            output.extend_from_slice(format!("\n{}{}", indent, element).as_bytes());
+        }
+        output.extend_from_slice(format!("\n{}", " ".repeat(depth.saturating_sub(1))).as_bytes());
+    }
+}
+
+fn is_update_key(key: &Option<String>, updates: &[(&str, XmpValue)]) -> bool {
+    key.as_deref()
+        .is_some_and(|key| updates.iter().any(|(name, _)| *name == key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlay_settings() -> OverlaySettings {
+        serde_json::from_value(serde_json::json!({
+            "position": "BottomRight",
+            "font": { "family": "Arial", "size": 16.0, "color": "#FFFFFF", "weight": "Normal" },
+            "background": { "color": "#000000", "opacity": 0.8, "padding": 10.0, "border_radius": 5.0 },
+            "display_items": {
+                "brand": true, "model": true, "aperture": true, "shutter_speed": true,
+                "iso": true, "timestamp": true, "location": false, "brand_logo": false
+            }
+        }))
+        .unwrap()
+    }
+
+    const LIGHTROOM_SIDECAR: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+    xmlns:exif="http://ns.adobe.com/exif/1.0/"
+    xmlns:ps="http://ns.adobe.com/photoshop/1.0/"
+    xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"
+    tiff:Make="FUJIFILM"
+    exif:FNumber="28/10"
+    exif:ExposureTime="1/250"
+    exif:GPSLatitude="31,12.5N"
+    exif:GPSLongitude="121,30.25E"
+    exif:GPSAltitude="125/10"
+    exif:GPSAltitudeRef="1"
+    exif:GPSImgDirection="27135/100"
+    ps:DateCreated="2024-05-03T14:02:11.12+09:00"
+    crs:Exposure2012="+0.35">
+   <exif:ISOSpeedRatings><rdf:Seq><rdf:li>400</rdf:li></rdf:Seq></exif:ISOSpeedRatings>
+   <dc:creator xmlns:dc="http://purl.org/dc/elements/1.1/"><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator>
+   <dc:rights xmlns:dc="http://purl.org/dc/elements/1.1/"><rdf:Alt><rdf:li xml:lang="x-default">© Jane Doe</rdf:li></rdf:Alt></dc:rights>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_and_apply_sidecar() {
+        let packet = XmpService::parse(LIGHTROOM_SIDECAR).unwrap();
+        assert_eq!(packet.value("photoshop:DateCreated"), Some(Some("2024-05-03T14:02:11.12+09:00")));
+        assert_eq!(packet.values("exif:ISOSpeedRatings"), ["400"]);
+
+        let mut metadata = PhotoMetadata::default();
+        metadata.camera.make = Some("Fujifilm".to_string());
+        metadata.artist = Some("Embedded".to_string());
+        XmpService::apply_to_metadata(&mut metadata, &packet);
+
+        assert_eq!(metadata.camera.make.as_deref(), Some("FUJIFILM"));
+        assert_eq!(metadata.settings.aperture.as_deref(), Some("f/2.8"));
+        assert_eq!(metadata.settings.shutter_speed.as_deref(), Some("1/250"));
+        assert_eq!(metadata.settings.iso, Some(400));
+        assert_eq!(metadata.artist.as_deref(), Some("Jane Doe"));
+        assert_eq!(metadata.copyright.as_deref(), Some("© Jane Doe"));
+        assert_eq!(metadata.timestamp.as_deref(), Some("2024:05:03 14:02:11"));
+        assert_eq!(metadata.capture_time.as_ref().unwrap().offset_minutes, Some(540));
+
+        let location = metadata.location.unwrap();
+        assert!((location.latitude - 31.208333).abs() < 1e-5);
+        assert!((location.longitude - 121.504167).abs() < 1e-5);
+        assert_eq!(location.altitude, Some(-12.5));
+        assert_eq!(location.image_direction, Some(271.35));
+    }
+
+    #[test]
+    fn test_sidecar_lookup_and_precedence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("photo.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+        assert!(XmpService::find_sidecar(&image_path).is_none());
+
+        std::fs::write(temp_dir.path().join("photo.xmp"), LIGHTROOM_SIDECAR).unwrap();
+        assert_eq!(XmpService::find_sidecar(&image_path), Some(temp_dir.path().join("photo.xmp")));
+
+        // 带原扩展名的边车优先
+        std::fs::write(temp_dir.path().join("photo.png.xmp"), SIDECAR_TEMPLATE).unwrap();
+        assert_eq!(XmpService::find_sidecar(&image_path), Some(temp_dir.path().join("photo.png.xmp")));
+        std::fs::remove_file(temp_dir.path().join("photo.png.xmp")).unwrap();
+
+        let metadata = ExifService::extract_metadata(&image_path).unwrap();
+        assert_eq!(metadata.camera.make.as_deref(), Some("Fujifilm"));
+        assert_eq!(metadata.camera.raw_make.as_deref(), Some("FUJIFILM"));
+        assert_eq!(metadata.field_sources.get("camera.make"), Some(&MetadataSource::Xmp));
+        assert_eq!(metadata.capture_time.as_ref().unwrap().source, TimestampSource::Xmp);
+        assert_eq!(metadata.field_sources.get("file"), Some(&MetadataSource::File));
+    }
+
+    #[test]
+    fn test_write_sidecar_preserves_other_properties() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("photo.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+        std::fs::write(temp_dir.path().join("photo.xmp"), LIGHTROOM_SIDECAR).unwrap();
+
+        let mut metadata = ExifService::extract_metadata(&image_path).unwrap();
+        metadata.camera.model = Some("X100V".to_string());
+        metadata.settings.aperture = Some("f/4.0".to_string());
+        metadata.artist = Some("John <Doe>".to_string());
+        metadata.copyright = None;
+
+        let sidecar_path = XmpService::write_sidecar(&image_path, &metadata, &overlay_settings()).unwrap();
+        assert_eq!(sidecar_path, temp_dir.path().join("photo.png.xmp"));
+        // 共用的 photo.xmp 保持不变
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join("photo.xmp")).unwrap(), LIGHTROOM_SIDECAR);
+
+        let content = std::fs::read_to_string(&sidecar_path).unwrap();
+        assert!(content.contains("crs:Exposure2012=\"+0.35\""));
+        assert!(!content.contains("28/10"));
+
+        let packet = XmpService::parse(&content).unwrap();
+        assert!(packet.value("pmo:OverlaySettings").flatten().unwrap().contains("\"position\""));
+
+        let reread = ExifService::extract_metadata(&image_path).unwrap();
+        assert_eq!(reread.camera.make.as_deref(), Some("Fujifilm"));
+        assert_eq!(reread.camera.model.as_deref(), Some("X100V"));
+        assert_eq!(reread.settings.aperture.as_deref(), Some("f/4.0"));
+        assert_eq!(reread.artist.as_deref(), Some("John <Doe>"));
+        assert_eq!(reread.copyright, None);
+        assert_eq!(reread.settings.iso, Some(400));
+    }
+
+    #[test]
+    fn test_write_sidecar_settings_and_location() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("photo.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+
+        let flash = XmpService::enum_text(exif::Tag::Flash, 0x19);
+        let metering_mode = XmpService::enum_text(exif::Tag::MeteringMode, 3);
+        let mut metadata = ExifService::extract_metadata(&image_path).unwrap();
+        metadata.settings.flash = Some(flash.clone());
+        metadata.settings.metering_mode = Some(metering_mode.clone());
+        metadata.settings.exposure_program = Some("Aperture priority".to_string());
+        metadata.settings.white_balance = Some("1".to_string());
+        metadata.settings.film_simulation = Some("Classic Chrome".to_string());
+        metadata.software = Some("Darkroom 2.0".to_string());
+        metadata.location = Some(LocationInfo {
+            latitude: 31.2,
+            longitude: 121.5,
+            altitude: Some(-12.5),
+            image_direction: Some(271.35),
+            ..Default::default()
+        });
+        XmpService::write_sidecar(&image_path, &metadata, &overlay_settings()).unwrap();
+
+        let reread = ExifService::extract_metadata(&image_path).unwrap();
+        let settings = &reread.settings;
+        assert_eq!(settings.flash.as_deref(), Some(flash.as_str()));
+        assert_eq!(settings.metering_mode.as_deref(), Some(metering_mode.as_str()));
+        assert_eq!(settings.exposure_program.as_deref(), Some("aperture priority"));
+        assert_eq!(settings.white_balance.as_deref(), Some("manual white balance"));
+        assert_eq!(settings.film_simulation.as_deref(), Some("Classic Chrome"));
+        assert_eq!(reread.software.as_deref(), Some("Darkroom 2.0"));
+        let location = reread.location.as_ref().unwrap();
+        assert_eq!(location.altitude, Some(-12.5));
+        assert_eq!(location.image_direction, Some(271.35));
+
+        // 无法表示为EXIF枚举值的改动不静默丢弃
+        metadata.settings.metering_mode = Some("my own metering".to_string());
+        metadata.settings.flash = Some("sometimes".to_string());
+        let error = XmpService::write_sidecar(&image_path, &metadata, &overlay_settings()).unwrap_err();
+        assert!(error.to_string().contains("settings.metering_mode, settings.flash"));
+    }
+
+    #[test]
+    fn test_raw_jpeg_pair_sidecars() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let jpeg_path = temp_dir.path().join("DSC_0001.png");
+        let raw_path = temp_dir.path().join("DSC_0001.nef");
+        image::RgbImage::new(4, 4).save(&jpeg_path).unwrap();
+        std::fs::write(&raw_path, b"raw").unwrap();
+
+        let jpeg_sidecar = XmpService::update_sidecar(&jpeg_path, &[("tiff:Model", XmpValue::Text("JPEG".to_string()))]).unwrap();
+        let raw_sidecar = XmpService::update_sidecar(&raw_path, &[("tiff:Model", XmpValue::Text("RAW".to_string()))]).unwrap();
+        assert_eq!(jpeg_sidecar, temp_dir.path().join("DSC_0001.png.xmp"));
+        assert_eq!(raw_sidecar, temp_dir.path().join("DSC_0001.nef.xmp"));
+        assert!(!temp_dir.path().join("DSC_0001.xmp").exists());
+
+        let model = |path: &Path| {
+            let packet = XmpService::read_sidecar(XmpService::find_sidecar(path).unwrap()).unwrap();
+            packet.value("tiff:Model").flatten().map(str::to_string)
+        };
+        assert_eq!(model(&jpeg_path).as_deref(), Some("JPEG"));
+        assert_eq!(model(&raw_path).as_deref(), Some("RAW"));
+    }
+
+    #[test]
+    fn test_iptc_core_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("press.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+
+        let mut metadata = ExifService::extract_metadata(&image_path).unwrap();
+        metadata.iptc = Some(IptcInfo {
+            headline: Some("Harbour at dusk".to_string()),
+            caption: Some("Ferries leaving the pier".to_string()),
+            keywords: vec!["harbour".to_string(), "sunset".to_string()],
+            byline: Some("Jane Doe".to_string()),
+            credit: Some("Example Press".to_string()),
+            city: Some("Shanghai".to_string()),
+            ..Default::default()
+        });
+        XmpService::write_sidecar(&image_path, &metadata, &overlay_settings()).unwrap();
+
+        let reread = ExifService::extract_metadata(&image_path).unwrap();
+        let iptc = reread.iptc.as_ref().unwrap();
+        assert_eq!(iptc.caption.as_deref(), Some("Ferries leaving the pier"));
+        assert_eq!(iptc.keywords, ["harbour", "sunset"]);
+        assert_eq!(iptc.byline.as_deref(), Some("Jane Doe"));
+        assert_eq!(iptc.city.as_deref(), Some("Shanghai"));
+        assert_eq!(reread.field_sources.get("iptc.headline"), Some(&MetadataSource::Xmp));
+    }
+
+    #[test]
+    fn test_write_sidecar_creates_new_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("scan.png");
+        image::RgbImage::new(4, 4).save(&image_path).unwrap();
+
+        let mut metadata = ExifService::extract_metadata(&image_path).unwrap();
+        metadata.camera.make = Some("Leica".to_string());
+
+        let sidecar_path = XmpService::write_sidecar(&image_path, &metadata, &overlay_settings()).unwrap();
+        assert_eq!(sidecar_path, temp_dir.path().join("scan.png.xmp"));
+
+        let reread = ExifService::extract_metadata(&image_path).unwrap();
+        assert_eq!(reread.camera.make.as_deref(), Some("Leica"));
+    }
+}
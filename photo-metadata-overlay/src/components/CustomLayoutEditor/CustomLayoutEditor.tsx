@@ -202,7 +202,7 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
     // 如果启用网格对齐，调整位置到网格点
     let finalPosition = position;
     if (customLayout.snapToGrid) {
-      const gridSize = customLayout.gridSize;
+      const gridSize = customLayout.gridSizePercent;
       finalPosition = {
         x: Math.round(position.x / gridSize) * gridSize,
         y: Math.round(position.y / gridSize) * gridSize,
@@ -216,7 +216,7 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
     };
 
     onElementMove(elementId, finalPosition);
-  }, [customLayout.snapToGrid, customLayout.gridSize, onElementMove]);
+  }, [customLayout.snapToGrid, customLayout.gridSizePercent, onElementMove]);
 
   // 处理元素选择
   const handleElementSelect = useCallback((elementId: string) => {
@@ -255,7 +255,7 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
   const handleGridSizeChange = useCallback((size: number) => {
     onLayoutChange({
       ...customLayout,
-      gridSize: size,
+      gridSizePercent: size,
     });
   }, [customLayout, onLayoutChange]);
 
@@ -286,14 +286,14 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
           <div className="flex items-center gap-2">
             <span className="text-sm text-gray-600">网格大小:</span>
             <Slider
-              value={customLayout.gridSize}
+              value={customLayout.gridSizePercent}
               onChange={handleGridSizeChange}
-              min={5}
-              max={50}
-              step={5}
+              min={1}
+              max={20}
+              step={1}
               className="w-24"
             />
-            <span className="text-sm text-gray-500">{customLayout.gridSize}px</span>
+            <span className="text-sm text-gray-500">{customLayout.gridSizePercent}%</span>
           </div>
           
           <Button
@@ -331,7 +331,7 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
                   linear-gradient(to right, rgba(0,0,0,0.1) 1px, transparent 1px),
                   linear-gradient(to bottom, rgba(0,0,0,0.1) 1px, transparent 1px)
                 `,
-                backgroundSize: `${customLayout.gridSize}px ${customLayout.gridSize}px`,
+                backgroundSize: `${customLayout.gridSizePercent}% ${customLayout.gridSizePercent}%`,
               }}
             />
           )}
@@ -357,7 +357,7 @@ export const CustomLayoutEditor: React.FC<CustomLayoutEditorProps> = ({
                   onSelect={handleElementSelect}
                   onDragEnd={handleElementDragEnd}
                   onStyleChange={onElementStyleChange}
-                  gridSize={customLayout.snapToGrid ? customLayout.gridSize : 0}
+                  gridSize={customLayout.snapToGrid ? customLayout.gridSizePercent : 0}
                 />
               );
             })}
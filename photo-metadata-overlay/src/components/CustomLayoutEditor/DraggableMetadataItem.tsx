@@ -9,6 +9,7 @@ interface DraggableMetadataItemProps {
   onSelect: (elementId: string) => void;
   onDragEnd: (elementId: string, position: { x: number; y: number }) => void;
   onStyleChange: (elementId: string, style: Partial<MetadataElement['style']>) => void;
+  // 网格间距（百分比，与元素位置相同），0表示不吸附
   gridSize?: number;
 }
 
@@ -118,11 +119,8 @@ export const DraggableMetadataItem: React.FC<DraggableMetadataItemProps> = ({
       let gridY = finalY;
       
       if (gridSize > 0) {
-        const gridPercentX = (gridSize / imageRect.width) * 100;
-        const gridPercentY = (gridSize / imageRect.height) * 100;
-        
-        gridX = Math.round(finalX / gridPercentX) * gridPercentX;
-        gridY = Math.round(finalY / gridPercentY) * gridPercentY;
+        gridX = Math.round(finalX / gridSize) * gridSize;
+        gridY = Math.round(finalY / gridSize) * gridSize;
       }
       
       // 最终边界检查
@@ -187,7 +187,7 @@ const OverlaySettingsTab: React.FC<OverlaySettingsTabProps> = ({
       updatedSettings.customLayout = {
         elements: defaultElements,
         gridEnabled: true,
-        gridSize: 20,
+        gridSizePercent: 5,
         snapToGrid: true,
       };
     }
@@ -360,19 +360,19 @@ const OverlaySettingsTab: React.FC<OverlaySettingsTabProps> = ({
             
             <div>
               <label className="block text-sm text-gray-700 dark:text-gray-300 mb-1">
-                网格大小: {settings.customLayout.gridSize}px
+                网格大小: {settings.customLayout.gridSizePercent}%
               </label>
               <input
                 type="range"
-                min="10"
-                max="50"
-                step="5"
-                value={settings.customLayout.gridSize}
+                min="1"
+                max="20"
+                step="1"
+                value={settings.customLayout.gridSizePercent}
                 onChange={(e) => onChange({
                   ...settings,
                   customLayout: {
                     ...settings.customLayout!,
-                    gridSize: parseInt(e.target.value),
+                    gridSizePercent: parseInt(e.target.value),
                   },
                 })}
                 className="w-full"
@@ -98,7 +98,7 @@ export const DEFAULT_OVERLAY_SETTINGS: OverlaySettings = {
   customLayout: {
     elements: [],
     gridEnabled: true,
-    gridSize: 20,
+    gridSizePercent: 5,
     snapToGrid: true,
   },
   font: {
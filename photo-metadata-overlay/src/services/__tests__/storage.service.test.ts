@@ -142,6 +142,27 @@ describe('StorageService', () => {
       expect(result.font.size).toBe(72); // Clamped to max
       expect(result.background.opacity).toBe(1); // Clamped to max
     });
+
+    it('should drop legacy pixel grid sizes and clamp percent grid sizes', async () => {
+      const legacySettings = {
+        ...DEFAULT_OVERLAY_SETTINGS,
+        customLayout: { elements: [], gridEnabled: true, gridSize: 50, snapToGrid: true },
+      };
+      localStorageMock.getItem.mockReturnValue(JSON.stringify(legacySettings));
+
+      const legacy = await storageService.loadOverlaySettings();
+      expect(legacy.customLayout?.gridSizePercent).toBe(DEFAULT_OVERLAY_SETTINGS.customLayout?.gridSizePercent);
+      expect(legacy.customLayout).not.toHaveProperty('gridSize');
+
+      const oversized = {
+        ...DEFAULT_OVERLAY_SETTINGS,
+        customLayout: { ...DEFAULT_OVERLAY_SETTINGS.customLayout!, gridSizePercent: 50 },
+      };
+      localStorageMock.getItem.mockReturnValue(JSON.stringify(oversized));
+
+      const clamped = await storageService.loadOverlaySettings();
+      expect(clamped.customLayout?.gridSizePercent).toBe(20);
+    });
   });
 
   describe('Frame Settings', () => {
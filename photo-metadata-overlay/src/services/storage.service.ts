@@ -1,4 +1,4 @@
-import { StorageService, UserSettings, OverlaySettings, FrameSettings, OverlayConfig, ImageProcessingSettings } from '../types';
+import { StorageService, UserSettings, OverlaySettings, CustomLayoutSettings, FrameSettings, OverlayConfig, ImageProcessingSettings } from '../types';
 import { DEFAULT_OVERLAY_SETTINGS, DEFAULT_FRAME_SETTINGS, DEFAULT_IMAGE_PROCESSING_SETTINGS } from '../constants/design-tokens';
 
 /**
@@ -415,7 +415,7 @@ export class StorageServiceImpl implements StorageService {
     return {
       layoutMode: settings.layoutMode || DEFAULT_OVERLAY_SETTINGS.layoutMode,
       position: settings.position || DEFAULT_OVERLAY_SETTINGS.position,
-      customLayout: settings.customLayout || DEFAULT_OVERLAY_SETTINGS.customLayout,
+      customLayout: this.validateCustomLayout(settings.customLayout),
       font: {
         family: settings.font?.family || DEFAULT_OVERLAY_SETTINGS.font.family,
         size: Math.max(8, Math.min(72, settings.font?.size || DEFAULT_OVERLAY_SETTINGS.font.size)),
@@ -441,6 +441,25 @@ export class StorageServiceImpl implements StorageService {
     };
   }
 
+  /**
+   * 验证自定义布局设置
+   * 网格间距改为百分比后字段名改为 gridSizePercent，旧版本以像素保存的 gridSize 不再沿用，改用默认值
+   */
+  private validateCustomLayout(layout?: Partial<CustomLayoutSettings>): CustomLayoutSettings | undefined {
+    const defaultLayout = DEFAULT_OVERLAY_SETTINGS.customLayout;
+    if (!layout || !defaultLayout) {
+      return defaultLayout;
+    }
+
+    const gridSizePercent = typeof layout.gridSizePercent === 'number' ? layout.gridSizePercent : defaultLayout.gridSizePercent;
+    return {
+      elements: Array.isArray(layout.elements) ? layout.elements : defaultLayout.elements,
+      gridEnabled: layout.gridEnabled ?? defaultLayout.gridEnabled,
+      gridSizePercent: Math.max(1, Math.min(20, Math.round(gridSizePercent))),
+      snapToGrid: layout.snapToGrid ?? defaultLayout.snapToGrid,
+    };
+  }
+
   /**
    * 验证相框设置
    */
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
   BackendPhotoMetadata,
   BackendOverlaySettings,
@@ -7,6 +8,16 @@ import {
   BackendProcessedImageInfo,
   BackendBatchProcessingResult,
   BackendPreviewSettings,
+  BackendMetadataOverride,
+  BackendFilmRoll,
+  BackendImageFileReport,
+  BackendThumbnail,
+  BackendScanProgress,
+  BackendMetadataScanSummary,
+  BackendMetadataComparison,
+  BackendFontFamilyInfo,
+  BackendCustomLayoutSettings,
+  BackendMetadataElementType,
   TauriAPI,
 } from '../types';
 
@@ -40,7 +51,7 @@ class TauriAPIService implements TauriAPI {
   }
 
   /**
-   * 验证图片文件格式
+   * 验证图片文件格式（按文件内容检查）
    */
   async validateImageFile(filePath: string): Promise<boolean> {
     try {
@@ -51,6 +62,88 @@ class TauriAPIService implements TauriAPI {
     }
   }
 
+  /**
+   * 检查图片文件的真实格式、尺寸、颜色类型、元数据与截断情况
+   */
+  async inspectImageFile(filePath: string): Promise<BackendImageFileReport> {
+    try {
+      return await invoke('inspect_image_file', { filePath });
+    } catch (error) {
+      console.error('Inspect image file failed:', error);
+      throw new Error(`文件检查失败: ${error}`);
+    }
+  }
+
+  /**
+   * 获取文件列表缩略图，优先使用内嵌的EXIF缩略图或MPF预览
+   */
+  async getThumbnail(filePath: string, maxSize?: number): Promise<BackendThumbnail> {
+    try {
+      return await invoke('get_thumbnail', { filePath, maxSize });
+    } catch (error) {
+      console.error('Get thumbnail failed:', error);
+      throw new Error(`缩略图生成失败: ${error}`);
+    }
+  }
+
+  /**
+   * 对比多张图片的元数据（第一张作为参照），返回字段差异与建议的时钟修正
+   */
+  async compareMetadata(imagePaths: string[]): Promise<BackendMetadataComparison> {
+    try {
+      return await invoke('compare_metadata', { imagePaths });
+    } catch (error) {
+      console.error('Compare metadata failed:', error);
+      throw new Error(`元数据对比失败: ${error}`);
+    }
+  }
+
+  /**
+   * 列出可用的字体家族（系统字体与用户导入的字体）
+   */
+  async listFontFamilies(): Promise<BackendFontFamilyInfo[]> {
+    try {
+      return await invoke('list_font_families');
+    } catch (error) {
+      console.error('List font families failed:', error);
+      throw new Error(`字体列表获取失败: ${error}`);
+    }
+  }
+
+  /**
+   * 导入TTF/OTF字体文件，返回其中的字体家族
+   */
+  async importFont(filePath: string): Promise<string[]> {
+    try {
+      return await invoke('import_font', { filePath });
+    } catch (error) {
+      console.error('Import font failed:', error);
+      throw new Error(`字体导入失败: ${error}`);
+    }
+  }
+
+  /**
+   * 批量扫描文件夹中图片的元数据，指定outputPath时按扩展名导出为CSV或JSON
+   */
+  async scanFolderMetadata(
+    folder: string,
+    recursive?: boolean,
+    outputPath?: string,
+    onProgress?: (progress: BackendScanProgress) => void
+  ): Promise<BackendMetadataScanSummary> {
+    const unlisten = onProgress
+      ? await listen<BackendScanProgress>('metadata-scan-progress', event => onProgress(event.payload))
+      : undefined;
+    try {
+      return await invoke('scan_folder_metadata', { folder, recursive, outputPath });
+    } catch (error) {
+      console.error('Scan folder metadata failed:', error);
+      throw new Error(`元数据扫描失败: ${error}`);
+    } finally {
+      unlisten?.();
+    }
+  }
+
   /**
    * 处理单张图片
    */
@@ -156,6 +249,92 @@ class TauriAPIService implements TauriAPI {
     }
   }
 
+  /**
+   * 将叠加方案和编辑过的元数据写入XMP边车文件
+   */
+  async writeXmpSidecar(
+    imagePath: string,
+    metadata: BackendPhotoMetadata,
+    overlaySettings: BackendOverlaySettings
+  ): Promise<string> {
+    try {
+      return await invoke<string>('write_xmp_sidecar', {
+        imagePath,
+        metadata,
+        overlaySettings,
+      });
+    } catch (error) {
+      console.error('Write XMP sidecar failed:', error);
+      throw new Error(`XMP边车写入失败: ${error}`);
+    }
+  }
+
+  /**
+   * 读取边车文件中保存的元数据覆盖
+   */
+  async getMetadataOverride(imagePath: string): Promise<BackendMetadataOverride | null> {
+    try {
+      return await invoke<BackendMetadataOverride | null>('get_metadata_override', { imagePath });
+    } catch (error) {
+      console.error('Get metadata override failed:', error);
+      throw new Error(`读取元数据覆盖失败: ${error}`);
+    }
+  }
+
+  /**
+   * 将元数据覆盖保存到一张或多张图片的边车文件
+   */
+  async saveMetadataOverride(
+    imagePaths: string[],
+    metadataOverride: BackendMetadataOverride
+  ): Promise<string[]> {
+    try {
+      return await invoke<string[]>('save_metadata_override', { imagePaths, metadataOverride });
+    } catch (error) {
+      console.error('Save metadata override failed:', error);
+      throw new Error(`保存元数据覆盖失败: ${error}`);
+    }
+  }
+
+  /**
+   * 删除边车文件中保存的元数据覆盖
+   */
+  async clearMetadataOverride(imagePaths: string[]): Promise<void> {
+    try {
+      await invoke('clear_metadata_override', { imagePaths });
+    } catch (error) {
+      console.error('Clear metadata override failed:', error);
+      throw new Error(`清除元数据覆盖失败: ${error}`);
+    }
+  }
+
+  /**
+   * 读取胶片卷记录表（CSV或TOML）
+   */
+  async loadFilmRoll(sheetPath: string): Promise<BackendFilmRoll> {
+    try {
+      return await invoke<BackendFilmRoll>('load_film_roll', { sheetPath });
+    } catch (error) {
+      console.error('Load film roll failed:', error);
+      throw new Error(`胶片卷记录表读取失败: ${error}`);
+    }
+  }
+
+  /**
+   * 将胶片卷记录表对应到扫描文件，返回每个文件的元数据覆盖
+   */
+  async mapFilmRoll(
+    roll: BackendFilmRoll,
+    imagePaths: string[]
+  ): Promise<Record<string, BackendMetadataOverride>> {
+    try {
+      return await invoke<Record<string, BackendMetadataOverride>>('map_film_roll', { roll, imagePaths });
+    } catch (error) {
+      console.error('Map film roll failed:', error);
+      throw new Error(`胶片卷记录表匹配失败: ${error}`);
+    }
+  }
+
   /**
    * 将前端设置转换为后端格式
    */
@@ -184,9 +363,46 @@ class TauriAPIService implements TauriAPI {
         location: frontendSettings.displayItems.location,
         brand_logo: frontendSettings.displayItems.brandLogo,
       },
+      layout_mode: frontendSettings.layoutMode === 'custom' ? 'Custom' : 'Preset',
+      custom_layout: frontendSettings.customLayout
+        ? this.convertCustomLayout(frontendSettings.customLayout)
+        : undefined,
+    };
+  }
+
+  /**
+   * 将前端自定义布局转换为后端格式
+   */
+  private convertCustomLayout(customLayout: any): BackendCustomLayoutSettings {
+    return {
+      elements: customLayout.elements.map((element: any) => ({
+        id: element.id,
+        type: this.convertElementType(element.type),
+        position: { x: element.position.x, y: element.position.y },
+        visible: element.visible,
+        style: element.style
+          ? {
+              // 与前端预览一致，0视为未设置
+              font_size: element.style.fontSize || undefined,
+              color: element.style.color || undefined,
+              background_color: element.style.backgroundColor || undefined,
+              padding: element.style.padding || undefined,
+            }
+          : undefined,
+      })),
+      grid_enabled: customLayout.gridEnabled,
+      grid_size: customLayout.gridSizePercent,
+      snap_to_grid: customLayout.snapToGrid,
     };
   }
 
+  /**
+   * 转换元素类型枚举（'shutterSpeed' → 'ShutterSpeed'）
+   */
+  private convertElementType(type: string): BackendMetadataElementType {
+    return (type.charAt(0).toUpperCase() + type.slice(1)) as BackendMetadataElementType;
+  }
+
   /**
    * 将前端相框设置转换为后端格式
    */
@@ -41,7 +41,7 @@ export const mockOverlaySettings: OverlaySettings = {
   customLayout: {
     elements: [],
     gridEnabled: true,
-    gridSize: 20,
+    gridSizePercent: 5,
     snapToGrid: true,
   },
   font: {
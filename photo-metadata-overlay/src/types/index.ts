@@ -96,7 +96,8 @@ export interface OverlaySettings {
 export interface CustomLayoutSettings {
   elements: MetadataElement[];
   gridEnabled: boolean;
-  gridSize: number;
+  // 网格间距，与元素位置同为百分比（旧版本以像素保存在 gridSize 中，加载时丢弃）
+  gridSizePercent: number;
   snapToGrid: boolean;
 }
 
@@ -438,28 +439,147 @@ export interface BackendPhotoMetadata {
   camera: {
     make?: string;
     model?: string;
+    lens_make?: string;
+    lens_model?: string;
+    serial_number?: string;
+    shutter_count?: number;
+    raw_make?: string;
+    raw_model?: string;
   };
   settings: {
     aperture?: string;
     shutter_speed?: string;
     iso?: number;
     focal_length?: string;
+    exposure_bias?: string;
+    flash?: string;
+    metering_mode?: string;
+    exposure_program?: string;
+    white_balance?: string;
+    film_simulation?: string;
+    focal_length_35mm?: string;
+    crop_factor?: number;
+    // 数值形式的拍摄参数
+    f_number?: number;
+    exposure_time?: number; // 秒
+    focal_length_mm?: number;
+    focal_length_35mm_mm?: number;
+    exposure_bias_ev?: number;
+    subject_distance?: number; // 米
+    // 由拍摄参数计算的值
+    ev100?: number;
+    hyperfocal_distance?: number; // 米
+    dof_near?: number; // 米
+    dof_far?: number; // 米，有 dof_near 而没有 dof_far 表示延伸到无穷远
   };
   timestamp?: string;
+  capture_time?: BackendCaptureTimestamp;
   location?: {
     latitude: number;
     longitude: number;
     address?: string;
+    altitude?: number; // 米，低于海平面为负
+    image_direction?: number;
+    image_direction_ref?: string;
+    speed?: number; // km/h
+    gps_time?: BackendCaptureTimestamp;
+    map_datum?: string;
+    place?: {
+      city: string;
+      region?: string;
+      country?: string;
+      country_code: string;
+      distance_km: number;
+    };
   };
+  orientation?: number;
+  artist?: string;
+  copyright?: string;
+  software?: string;
+  iptc?: {
+    title?: string;
+    headline?: string;
+    caption?: string;
+    keywords: string[];
+    byline?: string;
+    byline_title?: string;
+    credit?: string;
+    source?: string;
+    copyright_notice?: string;
+    sublocation?: string;
+    city?: string;
+    province_state?: string;
+    country?: string;
+    country_code?: string;
+  };
+  film?: {
+    stock?: string;
+    roll?: string;
+    frame?: number;
+    notes?: string;
+  };
+  file?: {
+    file_name: string;
+    file_size: number;
+    width?: number;
+    height?: number;
+    modified?: string;
+  };
+  field_sources?: Record<string, BackendMetadataSource>;
+}
+
+export interface BackendCaptureTimestamp {
+  year: number;
+  month: number;
+  day: number;
+  hour: number;
+  minute: number;
+  second: number;
+  nanosecond?: number;
+  offset_minutes?: number;
+  source: 'DateTimeOriginal' | 'DateTimeDigitized' | 'DateTime' | 'FileModified' | 'Xmp' | 'Gps' | 'Override';
+}
+
+export type BackendMetadataSource = 'Exif' | 'File' | 'Xmp' | 'Iptc' | 'MakerNote' | 'Gazetteer' | 'Override' | 'FilmRoll' | 'Computed';
+
+// 手动元数据覆盖，键为 "分组.字段" 路径（如 "camera.lens_model"），null 表示清除
+export interface BackendMetadataOverride {
+  fields?: Record<string, any>;
+  time_offset_seconds?: number;
+}
+
+// 胶片卷记录表（CSV或TOML），为扫描底片提供元数据
+export interface BackendFilmRoll {
+  roll?: string;
+  make?: string;
+  model?: string;
+  film_stock?: string;
+  iso?: number;
+  lens?: string;
+  mapping?: 'Order' | 'Pattern';
+  file_pattern?: string; // 如 "R12_{frame}*.tif"
+  frames?: BackendFilmFrame[];
+}
+
+export interface BackendFilmFrame {
+  frame?: number;
+  file?: string;
+  aperture?: string;
+  shutter_speed?: string;
+  lens?: string;
+  focal_length?: string;
+  date?: string;
+  notes?: string;
 }
 
 export interface BackendOverlaySettings {
   position: 'TopLeft' | 'TopRight' | 'BottomLeft' | 'BottomRight';
   font: {
-    family: string;
+    family: string; // 可以是逗号分隔的候选列表
     size: number;
     color: string;
     weight: 'Normal' | 'Bold';
+    style?: 'Normal' | 'Italic';
   };
   background: {
     color: string;
@@ -476,6 +596,74 @@ export interface BackendOverlaySettings {
     timestamp: boolean;
     location: boolean;
     brand_logo: boolean;
+    lens?: boolean;
+    exposure_bias?: boolean;
+    flash?: boolean;
+    metering_mode?: boolean;
+    exposure_program?: boolean;
+    white_balance?: boolean;
+    artist?: boolean;
+    copyright?: boolean;
+    software?: boolean;
+    headline?: boolean;
+    caption?: boolean;
+    keywords?: boolean;
+    byline?: boolean;
+    iptc_location?: boolean;
+    film_simulation?: boolean;
+    credit_line?: boolean;
+    altitude?: boolean;
+    image_direction?: boolean;
+    film_stock?: boolean;
+    focal_length?: boolean;
+    ev?: boolean;
+    hyperfocal?: boolean;
+    depth_of_field?: boolean;
+  };
+  date_format?: {
+    pattern?: string;
+    locale?: string;
+  };
+  location_format?: {
+    coordinate_format?: 'Decimal' | 'Dms';
+    altitude_unit?: 'Meters' | 'Feet';
+  };
+  focal_length_format?: 'Actual' | 'Equivalent' | 'Both';
+  text_layout?: {
+    arrangement?: 'Stacked' | 'SingleRow';
+    alignment?: 'Left' | 'Center' | 'Right';
+    line_spacing?: number; // 行高倍数
+    separator?: string; // 单行模式的分隔符
+    max_width_percent?: number; // 相对图片宽度的百分比
+  };
+  logo?: {
+    variant?: 'Auto' | 'Light' | 'Dark'; // Logo底色，Auto按背景明暗选择
+    placement?: 'Above' | 'Left' | 'Right'; // 相对文本块的位置
+  };
+  layout_mode?: 'Preset' | 'Custom';
+  custom_layout?: BackendCustomLayoutSettings; // 自定义模式使用
+}
+
+export type BackendMetadataElementType =
+  'Brand' | 'Model' | 'Aperture' | 'ShutterSpeed' | 'Iso' | 'Timestamp' | 'Location' | 'BrandLogo';
+
+export interface BackendCustomLayoutSettings {
+  elements: BackendMetadataElement[];
+  grid_enabled?: boolean;
+  grid_size?: number;
+  snap_to_grid?: boolean;
+}
+
+export interface BackendMetadataElement {
+  id: string;
+  type: BackendMetadataElementType;
+  position: { x: number; y: number }; // 百分比 (0-100)
+  visible: boolean;
+  style?: {
+    font_size?: number;
+    color?: string;
+    background_color?: string;
+    padding?: number;
   };
 }
 
@@ -493,6 +681,10 @@ export interface BackendProcessingSettings {
   frame_settings: BackendFrameSettings;
   output_format: 'Jpeg' | 'Png';
   quality: number;
+  preserve_metadata?: boolean; // 默认保留源文件的EXIF/XMP/ICC
+  metadata_override?: BackendMetadataOverride; // 应用于整批图片
+  image_overrides?: Record<string, BackendMetadataOverride>; // 按图片路径，优先于整批覆盖
+  film_roll?: BackendFilmRoll;
 }
 
 export interface BackendProcessedImageInfo {
@@ -510,6 +702,76 @@ export interface BackendBatchProcessingResult {
   total_time_ms: number;
 }
 
+export type BackendDetectedFormat = 'Jpeg' | 'Png' | 'Tiff' | 'Raw' | 'Heic' | 'Avif' | 'WebP' | 'Gif' | 'Bmp' | 'Unknown';
+
+export interface BackendImageFileReport {
+  file_path: string;
+  file_size: number;
+  format: BackendDetectedFormat;
+  extension_matches: boolean;
+  width?: number;
+  height?: number;
+  color_type?: string; // 'RGB' | 'RGBA' | 'Grayscale' | 'CMYK' | 'Palette' | 'CFA' 等
+  bit_depth?: number; // 每通道位数
+  has_exif: boolean;
+  has_icc: boolean;
+  truncated: boolean;
+  valid: boolean;
+  problems: string[];
+}
+
+export interface BackendThumbnail {
+  data: number[]; // JPEG数据
+  width: number;
+  height: number;
+  orientation: number; // EXIF方向标签，缩略图像素未旋转
+  source: 'ExifThumbnail' | 'MpfPreview' | 'RawPreview' | 'ScaledDecode';
+}
+
+export interface BackendFontFamilyInfo {
+  family: string;
+  weights: number[]; // 已有的字重（100-900）
+  has_italic: boolean;
+  user_font: boolean; // 是否来自用户导入的字体
+}
+
+export interface BackendScanProgress {
+  processed: number;
+  total: number;
+  current_file: string;
+}
+
+export interface BackendMetadataScanSummary {
+  total_files: number;
+  with_exif: number;
+  without_exif: string[];
+  failed: BackendProcessingError[];
+  cameras: Record<string, number>;
+  lenses: Record<string, number>;
+  iso: Record<string, number>;
+  output_path?: string;
+  total_time_ms: number;
+}
+
+export interface BackendFieldDifference {
+  field: string;
+  values: (unknown | null)[]; // 顺序与 file_paths 一致
+}
+
+export interface BackendClockOffsetSuggestion {
+  file_path: string;
+  offset_seconds: number; // 可直接用作覆盖中的 time_offset_seconds
+  basis: 'GpsTime' | 'Reference';
+}
+
+export interface BackendMetadataComparison {
+  file_paths: string[];
+  differences: BackendFieldDifference[];
+  common_fields: Record<string, unknown>;
+  time_deltas: (number | null)[]; // 与第一张图片的拍摄时间差（秒）
+  clock_offsets: BackendClockOffsetSuggestion[];
+}
+
 export interface BackendProcessingError {
   file_path: string;
   error_message: string;
@@ -521,6 +783,7 @@ export interface BackendPreviewSettings {
   max_height: number;
   overlay_settings: BackendOverlaySettings;
   frame_settings: BackendFrameSettings;
+  metadata_override?: BackendMetadataOverride;
 }
 
 // Tauri API wrapper functions
@@ -528,6 +791,17 @@ export interface TauriAPI {
   greet(name: string): Promise<string>;
   extractMetadata(filePath: string): Promise<BackendPhotoMetadata>;
   validateImageFile(filePath: string): Promise<boolean>;
+  inspectImageFile(filePath: string): Promise<BackendImageFileReport>;
+  getThumbnail(filePath: string, maxSize?: number): Promise<BackendThumbnail>;
+  compareMetadata(imagePaths: string[]): Promise<BackendMetadataComparison>;
+  listFontFamilies(): Promise<BackendFontFamilyInfo[]>;
+  importFont(filePath: string): Promise<string[]>;
+  scanFolderMetadata(
+    folder: string,
+    recursive?: boolean,
+    outputPath?: string,
+    onProgress?: (progress: BackendScanProgress) => void
+  ): Promise<BackendMetadataScanSummary>;
   processImage(
     inputPath: string,
     metadata: BackendPhotoMetadata,
@@ -552,6 +826,22 @@ export interface TauriAPI {
     frameSettings: BackendFrameSettings,
     quality: number
   ): Promise<string>; // Returns saved file path
+  writeXmpSidecar(
+    imagePath: string,
+    metadata: BackendPhotoMetadata,
+    overlaySettings: BackendOverlaySettings
+  ): Promise<string>; // Returns sidecar file path
+  getMetadataOverride(imagePath: string): Promise<BackendMetadataOverride | null>;
+  saveMetadataOverride(
+    imagePaths: string[],
+    metadataOverride: BackendMetadataOverride
+  ): Promise<string[]>; // Returns sidecar file paths
+  clearMetadataOverride(imagePaths: string[]): Promise<void>;
+  loadFilmRoll(sheetPath: string): Promise<BackendFilmRoll>;
+  mapFilmRoll(
+    roll: BackendFilmRoll,
+    imagePaths: string[]
+  ): Promise<Record<string, BackendMetadataOverride>>;
 }
 
 // Service interfaces